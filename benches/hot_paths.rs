@@ -0,0 +1,22 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use walk_the_dog::game::{bench::HotPaths, simulation};
+
+// The same hot paths `?bench` times in the browser, on the files on disk.
+fn hot_paths(criterion: &mut Criterion) {
+    let (boy, segments, terrain) =
+        simulation::read_world().expect("Could not read the game's files!");
+    let mut hot_paths = HotPaths::new(&boy, &segments, &terrain);
+    criterion.bench_function("collide", |bencher| {
+        bencher.iter(|| hot_paths.collide().expect("Collision checks are failing!"))
+    });
+    criterion.bench_function("generate", |bencher| {
+        bencher.iter(|| hot_paths.generate(black_box(7)))
+    });
+    criterion.bench_function("frame_name", |bencher| {
+        bencher.iter(|| hot_paths.frame_name())
+    });
+}
+
+criterion_group!(benches, hot_paths);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+use walk_the_dog::game::simulation;
+
+const DEFAULT_RUNS: u32 = 200;
+
+// `cargo run --release --example simulate [runs]`
+fn main() {
+    let runs = std::env::args()
+        .nth(1)
+        .and_then(|runs| runs.parse().ok())
+        .unwrap_or(DEFAULT_RUNS);
+    match simulation::simulate(runs) {
+        Ok(report) => print!("{}", report),
+        Err(err) => {
+            eprintln!("Could not simulate: {:?}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,22 +1,307 @@
 use anyhow::{anyhow, Result};
-use std::future::Future;
+use futures::{
+    future::{select, Either},
+    pin_mut,
+};
+use std::{fmt, future::Future, str::FromStr};
 use wasm_bindgen::closure::{Closure, WasmClosure, WasmClosureFnOnce};
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    CanvasRenderingContext2d, Document, HtmlCanvasElement, HtmlImageElement, Response, Window,
+    AudioBuffer, AudioContext, Blob, CanvasRenderingContext2d, Document, HtmlCanvasElement,
+    HtmlImageElement, ImageBitmap, KeyboardEvent, Request, RequestInit, Response, Storage, Window,
 };
 
+// Keys the game reads that the page would otherwise use to scroll.
+const GAME_KEYS: [&str; 7] = [
+    "ArrowUp",
+    "ArrowDown",
+    "ArrowLeft",
+    "ArrowRight",
+    "Space",
+    "PageUp",
+    "PageDown",
+];
+
+macro_rules! log_at {
+    ($level:ident, $($t:tt)*) => {
+        $crate::browser::logging::write(
+            $crate::browser::logging::Level::$level,
+            module_path!(),
+            &format!($($t)*),
+        )
+    };
+}
+
+macro_rules! trace {
+    ($($t:tt)*) => { log_at!(Trace, $($t)*) };
+}
+
+macro_rules! debug {
+    ($($t:tt)*) => { log_at!(Debug, $($t)*) };
+}
+
+// Info level, kept under its old name.
 macro_rules! log {
-    ($($t:tt)*) => {
-        web_sys::console::log_1(&format!($($t)*).into());
-    }
+    ($($t:tt)*) => { log_at!(Info, $($t)*) };
 }
 
+macro_rules! warn {
+    ($($t:tt)*) => { log_at!(Warn, $($t)*) };
+}
+
+macro_rules! error {
+    ($($t:tt)*) => { log_at!(Error, $($t)*) };
+}
+
+pub mod logging;
+pub mod offline;
+
 pub fn window() -> Result<Window> {
     web_sys::window().ok_or_else(|| anyhow!("No Window Found!"))
 }
 
+pub fn language() -> Result<String> {
+    window()?
+        .navigator()
+        .language()
+        .ok_or_else(|| anyhow!("No Language Found"))
+}
+
+// In gigabytes, where the browser says. Only Chromium-based ones do, so this
+// goes through reflection.
+pub fn device_memory() -> Option<f64> {
+    let navigator = window().ok()?.navigator();
+    js_sys::Reflect::get(&navigator, &JsValue::from_str("deviceMemory"))
+        .ok()?
+        .as_f64()
+}
+
+pub fn prefers_reduced_motion() -> Result<bool> {
+    Ok(window()?
+        .match_media("(prefers-reduced-motion: reduce)")
+        .map_err(|err| anyhow!("Could not query the media {:#?}", err))?
+        .is_some_and(|query| query.matches()))
+}
+
+// The safe-area insets in CSS pixels, top, right, bottom then left, as
+// styles.css copies them into custom properties on the root element.
+pub fn safe_area_insets() -> Result<[f64; 4]> {
+    let root = document()?
+        .document_element()
+        .ok_or_else(|| anyhow!("No root element"))?;
+    let style = window()?
+        .get_computed_style(&root)
+        .map_err(|err| anyhow!("Could not read the root style {:#?}", err))?
+        .ok_or_else(|| anyhow!("No computed style for the root element"))?;
+    Ok(["top", "right", "bottom", "left"].map(|edge| {
+        style
+            .get_property_value(&format!("--safe-area-{}", edge))
+            .ok()
+            .and_then(|value| value.trim().trim_end_matches("px").parse().ok())
+            .unwrap_or(0.0)
+    }))
+}
+
+// Screen pixels per CSS pixel.
+pub fn device_pixel_ratio() -> Result<f64> {
+    Ok(window()?.device_pixel_ratio())
+}
+
+// The viewport's width and height in CSS pixels.
+pub fn viewport_size() -> Result<(f64, f64)> {
+    let window = window()?;
+    let dimension = |value: std::result::Result<JsValue, JsValue>| {
+        value
+            .ok()
+            .and_then(|value| value.as_f64())
+            .ok_or_else(|| anyhow!("Could not read the viewport size"))
+    };
+    Ok((
+        dimension(window.inner_width())?,
+        dimension(window.inner_height())?,
+    ))
+}
+
+pub fn has_query_flag(name: &str) -> Result<bool> {
+    let search = window()?
+        .location()
+        .search()
+        .map_err(|err| anyhow!("Could not read the query string {:#?}", err))?;
+    Ok(search
+        .trim_start_matches('?')
+        .split('&')
+        .any(|pair| pair.split('=').next() == Some(name)))
+}
+
+pub fn query_value(name: &str) -> Result<Option<String>> {
+    let search = window()?
+        .location()
+        .search()
+        .map_err(|err| anyhow!("Could not read the query string {:#?}", err))?;
+    Ok(search.trim_start_matches('?').split('&').find_map(|pair| {
+        pair.split_once('=')
+            .filter(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    }))
+}
+
+// Options for one visit from the query string, for testing and challenge
+// links, e.g. `?seed=42&speed=1.5&start=300&character=golden&mute`. A value
+// that doesn't parse is ignored.
+#[derive(Clone, Debug, Default)]
+pub struct LaunchOptions {
+    pub seed: Option<u32>,
+    // Developer time controls; see `DebugControls`.
+    pub debug: bool,
+    pub mute: bool,
+    // Multiplies the boy's running speeds.
+    pub speed: Option<f32>,
+    // Meters into the course every run starts from.
+    pub start: Option<i32>,
+    // An outfit from the shop to wear without buying it.
+    pub character: Option<String>,
+}
+
+impl LaunchOptions {
+    pub fn from_query() -> Self {
+        LaunchOptions {
+            seed: parsed_query_value("seed"),
+            debug: has_query_flag("debug").unwrap_or(false),
+            mute: has_query_flag("mute").unwrap_or(false),
+            speed: parsed_query_value("speed").filter(|speed: &f32| *speed > 0.0),
+            start: parsed_query_value("start").filter(|start: &i32| *start >= 0),
+            character: query_value("character").ok().flatten(),
+        }
+    }
+}
+
+impl LaunchOptions {
+    // Whether the options change the course or how it plays.
+    pub fn is_practice(&self) -> bool {
+        self.seed.is_some() || self.speed.is_some() || self.start.is_some()
+    }
+
+    // A link to this page that starts runs from `seed` with the same speed,
+    // start and outfit, so a friend gets the same obstacles. Debug and mute
+    // are left for them to choose.
+    pub fn challenge_url(&self, seed: u32) -> Result<String> {
+        let location = window()?.location();
+        let page = |part: Result<String, JsValue>| {
+            part.map_err(|err| anyhow!("Could not read the page location {:#?}", err))
+        };
+        let mut url = format!(
+            "{}{}?seed={}",
+            page(location.origin())?,
+            page(location.pathname())?,
+            seed
+        );
+        if let Some(speed) = self.speed {
+            url.push_str(&format!("&speed={}", speed));
+        }
+        if let Some(start) = self.start {
+            url.push_str(&format!("&start={}", start));
+        }
+        if let Some(character) = &self.character {
+            url.push_str(&format!(
+                "&character={}",
+                js_sys::encode_uri_component(character)
+            ));
+        }
+        Ok(url)
+    }
+}
+
+fn parsed_query_value<T>(name: &str) -> Option<T>
+where
+    T: FromStr,
+    T::Err: fmt::Debug,
+{
+    let value = query_value(name).ok().flatten()?;
+    value
+        .parse()
+        .map_err(|err| warn!("Ignoring ?{}={}: {:?}", name, value, err))
+        .ok()
+}
+
+pub fn reload() -> Result<()> {
+    window()?
+        .location()
+        .reload()
+        .map_err(|err| anyhow!("Could not reload the page {:#?}", err))
+}
+
+// `navigator.clipboard` is still behind web-sys's unstable flag, so this goes
+// through reflection instead.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let clipboard = js_sys::Reflect::get(&window()?.navigator(), &JsValue::from_str("clipboard"))
+        .map_err(|err| anyhow!("Could not find the clipboard {:#?}", err))?;
+    let write_text: js_sys::Function =
+        js_sys::Reflect::get(&clipboard, &JsValue::from_str("writeText"))
+            .map_err(|err| anyhow!("Could not find writeText {:#?}", err))?
+            .dyn_into()
+            .map_err(|_| anyhow!("The clipboard cannot write text"))?;
+    write_text
+        .call1(&clipboard, &JsValue::from_str(text))
+        .map(|_| ())
+        .map_err(|err| anyhow!("Could not copy to the clipboard {:#?}", err))
+}
+
+// Only touch devices buzz; elsewhere there is nothing in the player's hand
+// to feel it.
+pub fn vibrate(pattern: &[u32]) -> Result<()> {
+    let navigator = window()?.navigator();
+    if navigator.max_touch_points() == 0 {
+        return Ok(());
+    }
+    let pattern: js_sys::Array = pattern.iter().map(|ms| JsValue::from(*ms)).collect();
+    navigator.vibrate_with_pattern(&pattern);
+    Ok(())
+}
+
+// `vibrationActuator.playEffect` is missing from web-sys, so this goes
+// through reflection. Pads without an actuator are left alone.
+pub fn rumble(index: u32, strength: f64, duration_ms: f64) -> Result<()> {
+    let Some(pad) = window()?
+        .navigator()
+        .get_gamepads()
+        .ok()
+        .and_then(|pads| pads.get(index).dyn_into::<web_sys::Gamepad>().ok())
+    else {
+        return Ok(());
+    };
+    let actuator = js_sys::Reflect::get(&pad, &JsValue::from_str("vibrationActuator"))
+        .map_err(|err| anyhow!("Could not find the vibration actuator {:#?}", err))?;
+    if actuator.is_undefined() || actuator.is_null() {
+        return Ok(());
+    }
+    let play_effect: js_sys::Function =
+        js_sys::Reflect::get(&actuator, &JsValue::from_str("playEffect"))
+            .map_err(|err| anyhow!("Could not find playEffect {:#?}", err))?
+            .dyn_into()
+            .map_err(|_| anyhow!("The actuator cannot play effects"))?;
+    let params = js_sys::Object::new();
+    for (key, value) in [
+        ("duration", duration_ms),
+        ("strongMagnitude", strength),
+        ("weakMagnitude", strength),
+    ] {
+        js_sys::Reflect::set(&params, &JsValue::from_str(key), &JsValue::from(value))
+            .map_err(|err| anyhow!("Could not set {} {:#?}", key, err))?;
+    }
+    play_effect
+        .call2(&actuator, &JsValue::from_str("dual-rumble"), &params)
+        .map(|_| ())
+        .map_err(|err| anyhow!("Could not rumble gamepad {} {:#?}", index, err))
+}
+
+pub fn local_storage() -> Result<Storage> {
+    window()?
+        .local_storage()
+        .map_err(|err| anyhow!("Error accessing localStorage {:#?}", err))?
+        .ok_or_else(|| anyhow!("No localStorage Found"))
+}
+
 pub fn document() -> Result<Document> {
     window()?
         .document()
@@ -24,15 +309,49 @@ pub fn document() -> Result<Document> {
 }
 
 pub fn canvas() -> Result<HtmlCanvasElement> {
+    find_canvas("canvas")?.ok_or_else(|| anyhow!("No Canvas Element found with ID 'canvas'"))
+}
+
+pub fn find_canvas(id: &str) -> Result<Option<HtmlCanvasElement>> {
     document()?
-        .get_element_by_id("canvas")
-        .ok_or_else(|| anyhow!("No Canvas Element found with ID 'canvas'"))?
-        .dyn_into::<web_sys::HtmlCanvasElement>()
-        .map_err(|element| anyhow!("Error converting {:#?} to HtmlCanvasElement", element))
+        .get_element_by_id(id)
+        .map(|element| {
+            element
+                .dyn_into::<web_sys::HtmlCanvasElement>()
+                .map_err(|element| anyhow!("Error converting {:#?} to HtmlCanvasElement", element))
+        })
+        .transpose()
+}
+
+pub fn download(href: &str, filename: &str) -> Result<()> {
+    let link = document()?
+        .create_element("a")
+        .map_err(|err| anyhow!("Could not create link element {:#?}", err))?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|element| anyhow!("Error converting {:#?} to HtmlAnchorElement", element))?;
+    link.set_href(href);
+    link.set_download(filename);
+    link.click();
+    Ok(())
 }
 
 pub fn context() -> Result<CanvasRenderingContext2d> {
-    canvas()?
+    context_2d(&canvas()?)
+}
+
+pub fn create_canvas(width: u32, height: u32) -> Result<HtmlCanvasElement> {
+    let canvas = document()?
+        .create_element("canvas")
+        .map_err(|err| anyhow!("Could not create canvas element {:#?}", err))?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|element| anyhow!("Error converting {:#?} to HtmlCanvasElement", element))?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    Ok(canvas)
+}
+
+pub fn context_2d(canvas: &HtmlCanvasElement) -> Result<CanvasRenderingContext2d> {
+    canvas
         .get_context("2d")
         .map_err(|js_value| anyhow!("Error getting 2d context {:#?}", js_value))?
         .ok_or_else(|| anyhow!("No 2d context found"))?
@@ -45,6 +364,40 @@ pub fn context() -> Result<CanvasRenderingContext2d> {
         })
 }
 
+pub struct Battery {
+    pub charging: bool,
+    // 0 to 1.
+    pub level: f64,
+}
+
+// `navigator.getBattery` is missing from web-sys and from most browsers
+// outside Chromium, so this goes through reflection and gives None there.
+pub async fn battery() -> Result<Option<Battery>> {
+    let navigator = window()?.navigator();
+    let get_battery = js_sys::Reflect::get(&navigator, &JsValue::from_str("getBattery"))
+        .map_err(|err| anyhow!("Could not look for getBattery {:#?}", err))?;
+    let Ok(get_battery) = get_battery.dyn_into::<js_sys::Function>() else {
+        return Ok(None);
+    };
+    let promise: js_sys::Promise = get_battery
+        .call0(&navigator)
+        .map_err(|err| anyhow!("Could not call getBattery {:#?}", err))?
+        .dyn_into()
+        .map_err(|_| anyhow!("getBattery did not return a promise"))?;
+    let manager = JsFuture::from(promise)
+        .await
+        .map_err(|err| anyhow!("Could not read the battery {:#?}", err))?;
+    let field = |name: &str| js_sys::Reflect::get(&manager, &JsValue::from_str(name)).ok();
+    Ok(Some(Battery {
+        charging: field("charging")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(true),
+        level: field("level")
+            .and_then(|value| value.as_f64())
+            .unwrap_or(1.0),
+    }))
+}
+
 pub fn spawn_local<F>(future: F)
 where
     F: Future<Output = ()> + 'static,
@@ -71,6 +424,165 @@ pub async fn fetch_json(json_path: &str) -> Result<JsValue> {
     .map_err(|err| anyhow!("error fetching JSON {:#?}", err))
 }
 
+pub async fn fetch_blob(resource: &str) -> Result<Blob> {
+    let resp: Response = fetch_with_str(resource)
+        .await?
+        .dyn_into()
+        .map_err(|element| anyhow!("Error converting {:#?} to Response", element))?;
+    if !resp.ok() {
+        return Err(anyhow!("{} answered {}", resource, resp.status()));
+    }
+    JsFuture::from(
+        resp.blob()
+            .map_err(|err| anyhow!("Could not get a Blob from response {:#?}", err))?,
+    )
+    .await
+    .map_err(|err| anyhow!("error fetching Blob {:#?}", err))?
+    .dyn_into()
+    .map_err(|element| anyhow!("Error converting {:#?} to Blob", element))
+}
+
+pub async fn fetch_array_buffer(resource: &str) -> Result<js_sys::ArrayBuffer> {
+    let resp: Response = fetch_with_str(resource)
+        .await?
+        .dyn_into()
+        .map_err(|element| anyhow!("Error converting {:#?} to Response", element))?;
+    if !resp.ok() {
+        return Err(anyhow!("{} answered {}", resource, resp.status()));
+    }
+    JsFuture::from(
+        resp.array_buffer()
+            .map_err(|err| anyhow!("Could not get an ArrayBuffer from response {:#?}", err))?,
+    )
+    .await
+    .map_err(|err| anyhow!("error fetching ArrayBuffer {:#?}", err))?
+    .dyn_into()
+    .map_err(|element| anyhow!("Error converting {:#?} to ArrayBuffer", element))
+}
+
+pub fn create_audio_context() -> Result<AudioContext> {
+    AudioContext::new().map_err(|err| anyhow!("Could not create an AudioContext {:#?}", err))
+}
+
+pub async fn decode_audio_data(
+    context: &AudioContext,
+    data: &js_sys::ArrayBuffer,
+) -> Result<AudioBuffer> {
+    JsFuture::from(
+        context
+            .decode_audio_data(data)
+            .map_err(|err| anyhow!("decodeAudioData is unavailable {:#?}", err))?,
+    )
+    .await
+    .map_err(|err| anyhow!("Could not decode the audio {:#?}", err))?
+    .dyn_into()
+    .map_err(|element| anyhow!("Error converting {:#?} to AudioBuffer", element))
+}
+
+// Decodes the blob off the main thread, in browsers that support it.
+pub async fn create_image_bitmap(blob: &Blob) -> Result<ImageBitmap> {
+    JsFuture::from(
+        window()?
+            .create_image_bitmap_with_blob(blob)
+            .map_err(|err| anyhow!("createImageBitmap is unavailable {:#?}", err))?,
+    )
+    .await
+    .map_err(|err| anyhow!("Could not decode the image {:#?}", err))?
+    .dyn_into()
+    .map_err(|element| anyhow!("Error converting {:#?} to ImageBitmap", element))
+}
+
+pub async fn post_json(url: &str, body: &str) -> Result<()> {
+    let mut init = RequestInit::new();
+    init.method("POST").body(Some(&JsValue::from_str(body)));
+    let request = Request::new_with_str_and_init(url, &init)
+        .map_err(|err| anyhow!("Could not create request for {} {:#?}", url, err))?;
+    request
+        .headers()
+        .set("Content-Type", "application/json")
+        .map_err(|err| anyhow!("Could not set headers {:#?}", err))?;
+    let resp: Response = JsFuture::from(window()?.fetch_with_request(&request))
+        .await
+        .map_err(|err| anyhow!("error posting to {} {:#?}", url, err))?
+        .dyn_into()
+        .map_err(|element| anyhow!("Error converting {:#?} to Response", element))?;
+    if resp.ok() {
+        Ok(())
+    } else {
+        Err(anyhow!("{} answered with status {}", url, resp.status()))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub initial_backoff_ms: i32,
+    pub timeout_ms: i32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 4,
+            initial_backoff_ms: 250,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+pub async fn retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    mut operation: F,
+    mut on_retry: impl FnMut(u32, &anyhow::Error),
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    let mut backoff_ms = policy.initial_backoff_ms;
+    loop {
+        match with_timeout(operation(), policy.timeout_ms).await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt >= policy.attempts => {
+                return Err(err.context(format!("Giving up after {} attempts", attempt)))
+            }
+            Err(err) => {
+                on_retry(attempt, &err);
+                sleep(backoff_ms).await?;
+                attempt += 1;
+                backoff_ms = backoff_ms.saturating_mul(2);
+            }
+        }
+    }
+}
+
+pub async fn with_timeout<T>(
+    future: impl Future<Output = Result<T>>,
+    timeout_ms: i32,
+) -> Result<T> {
+    let timeout = sleep(timeout_ms);
+    pin_mut!(future, timeout);
+    match select(future, timeout).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(anyhow!("Timed out after {}ms", timeout_ms)),
+    }
+}
+
+pub async fn sleep(ms: i32) -> Result<()> {
+    let window = window()?;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        if let Err(err) = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms)
+        {
+            error!("Error setting timeout {:#?}", err);
+        }
+    });
+    JsFuture::from(promise)
+        .await
+        .map(|_| ())
+        .map_err(|err| anyhow!("error sleeping {:#?}", err))
+}
+
 pub fn new_image() -> Result<HtmlImageElement> {
     HtmlImageElement::new().map_err(|err| anyhow!("Cloud not create HtmlImageElement {:#?}", err))
 }
@@ -86,6 +598,112 @@ pub fn closure_wrap<T: WasmClosure + ?Sized>(data: Box<T>) -> Closure<T> {
     Closure::wrap(data)
 }
 
+// Keeps the page from scrolling on a key the game uses.
+pub fn prevent_game_key_default(event: &KeyboardEvent) {
+    if GAME_KEYS.contains(&event.code().as_str()) {
+        event.prevent_default();
+    }
+}
+
+// Calls `callback` with false when the canvas or the whole window loses
+// focus, and with true when the canvas gets it back.
+pub fn on_focus_change(
+    canvas: &HtmlCanvasElement,
+    callback: impl FnMut(bool) + 'static,
+) -> Result<()> {
+    let callback = std::rc::Rc::new(std::cell::RefCell::new(callback));
+    let blur_callback = std::rc::Rc::clone(&callback);
+    let onblur =
+        closure_wrap(Box::new(move || (blur_callback.borrow_mut())(false)) as Box<dyn FnMut()>);
+    let onfocus = closure_wrap(Box::new(move || (callback.borrow_mut())(true)) as Box<dyn FnMut()>);
+    canvas.set_onblur(Some(onblur.as_ref().unchecked_ref()));
+    window()?.set_onblur(Some(onblur.as_ref().unchecked_ref()));
+    canvas.set_onfocus(Some(onfocus.as_ref().unchecked_ref()));
+    onblur.forget();
+    onfocus.forget();
+    Ok(())
+}
+
+// A connected gamepad as it is right now, in the standard layout.
+pub struct GamepadState {
+    pub buttons: Vec<bool>,
+    // The left stick, from -1 on the left to 1 on the right.
+    pub stick_x: f64,
+}
+
+pub fn gamepad(index: u32) -> Option<GamepadState> {
+    let pads = window().ok()?.navigator().get_gamepads().ok()?;
+    let pad = pads.get(index).dyn_into::<web_sys::Gamepad>().ok()?;
+    Some(GamepadState {
+        buttons: pad
+            .buttons()
+            .iter()
+            .map(|button| {
+                button
+                    .dyn_into::<web_sys::GamepadButton>()
+                    .is_ok_and(|button| button.pressed())
+            })
+            .collect(),
+        stick_x: pad.axes().get(0).as_f64().unwrap_or(0.0),
+    })
+}
+
+// Calls `callback` with whether the page is hidden each time the tab is
+// switched away from or back to.
+pub fn on_visibility_change(mut callback: impl FnMut(bool) + 'static) -> Result<()> {
+    let document = document()?;
+    let changed = document.clone();
+    let onchange = closure_wrap(Box::new(move || callback(changed.hidden())) as Box<dyn FnMut()>);
+    document.set_onvisibilitychange(Some(onchange.as_ref().unchecked_ref()));
+    onchange.forget();
+    Ok(())
+}
+
+// Calls `callback` whenever the page may be going away for good: it was
+// hidden, or it is being unloaded. This adds listeners rather than taking
+// over `onvisibilitychange`, so it can sit alongside `on_visibility_change`.
+pub fn on_page_hide(callback: impl FnMut() + 'static) -> Result<()> {
+    let callback = std::rc::Rc::new(std::cell::RefCell::new(callback));
+    let document = document()?;
+    let hidden = document.clone();
+    let hidden_callback = std::rc::Rc::clone(&callback);
+    let onchange = closure_wrap(Box::new(move || {
+        if hidden.hidden() {
+            (hidden_callback.borrow_mut())();
+        }
+    }) as Box<dyn FnMut()>);
+    let onpagehide = closure_wrap(Box::new(move || (callback.borrow_mut())()) as Box<dyn FnMut()>);
+    document
+        .add_event_listener_with_callback("visibilitychange", onchange.as_ref().unchecked_ref())
+        .map_err(|err| anyhow!("Could not listen for visibilitychange {:#?}", err))?;
+    window()?
+        .add_event_listener_with_callback("pagehide", onpagehide.as_ref().unchecked_ref())
+        .map_err(|err| anyhow!("Could not listen for pagehide {:#?}", err))?;
+    onchange.forget();
+    onpagehide.forget();
+    Ok(())
+}
+
+// Hands `body` to the browser to POST as JSON even if the page is unloading.
+pub fn send_beacon(url: &str, body: &str) -> Result<()> {
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_("application/json");
+    let blob = Blob::new_with_str_sequence_and_options(
+        &js_sys::Array::of1(&JsValue::from_str(body)),
+        &options,
+    )
+    .map_err(|err| anyhow!("Could not wrap the beacon body {:#?}", err))?;
+    if window()?
+        .navigator()
+        .send_beacon_with_opt_blob(url, Some(&blob))
+        .map_err(|err| anyhow!("Could not send a beacon to {} {:#?}", url, err))?
+    {
+        Ok(())
+    } else {
+        Err(anyhow!("The browser would not queue a beacon to {}", url))
+    }
+}
+
 pub type LoopClosure = Closure<dyn FnMut(f64)>;
 
 pub fn request_animation_frame(callback: &LoopClosure) -> Result<i32> {
@@ -98,6 +716,20 @@ pub fn create_raf_closure(f: impl FnMut(f64) + 'static) -> LoopClosure {
     closure_wrap(Box::new(f))
 }
 
+pub fn today_utc() -> String {
+    let date = js_sys::Date::new_0();
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.get_utc_full_year(),
+        date.get_utc_month() + 1,
+        date.get_utc_date()
+    )
+}
+
+pub fn random_seed() -> u32 {
+    (js_sys::Math::random() * f64::from(u32::MAX)) as u32
+}
+
 pub fn now() -> Result<f64> {
     Ok(window()?
         .performance()
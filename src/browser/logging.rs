@@ -0,0 +1,126 @@
+use crate::browser;
+use std::{cell::RefCell, collections::VecDeque};
+
+const PANEL_LINES: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn parse(name: &str) -> Option<Level> {
+        match name.to_ascii_lowercase().as_str() {
+            "trace" => Some(Level::Trace),
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warn" => Some(Level::Warn),
+            "error" => Some(Level::Error),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+// `?log=warn,walk_the_dog::net=trace` sets the default level to warn and
+// lets everything under `walk_the_dog::net` through. The longest matching
+// module prefix wins.
+struct Filter {
+    default: Level,
+    targets: Vec<(String, Level)>,
+}
+
+impl Filter {
+    fn parse(spec: &str) -> Self {
+        let mut filter = Filter {
+            default: Level::Info,
+            targets: vec![],
+        };
+        for directive in spec.split(',').filter(|directive| !directive.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    if let Some(level) = Level::parse(level) {
+                        filter.targets.push((target.to_string(), level));
+                    }
+                }
+                None => {
+                    if let Some(level) = Level::parse(directive) {
+                        filter.default = level;
+                    }
+                }
+            }
+        }
+        filter
+    }
+
+    fn enabled(&self, level: Level, target: &str) -> bool {
+        let threshold = self
+            .targets
+            .iter()
+            .filter(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, level)| *level);
+        level >= threshold
+    }
+}
+
+thread_local! {
+    static FILTER: RefCell<Filter> = RefCell::new(Filter::parse(""));
+    static PANEL: RefCell<Option<VecDeque<String>>> = const { RefCell::new(None) };
+}
+
+// Reads the `log` query parameter, and `debug`, which also keeps the last few
+// lines for the on-screen panel.
+pub fn init() {
+    if let Ok(Some(spec)) = browser::query_value("log") {
+        FILTER.with(|filter| *filter.borrow_mut() = Filter::parse(&spec));
+    }
+    if browser::has_query_flag("debug").unwrap_or(false) {
+        PANEL.with(|panel| *panel.borrow_mut() = Some(VecDeque::new()));
+    }
+}
+
+pub fn write(level: Level, target: &str, message: &str) {
+    if !FILTER.with(|filter| filter.borrow().enabled(level, target)) {
+        return;
+    }
+    let line = format!("[{} {}] {}", level.label(), target, message);
+    let value = line.as_str().into();
+    match level {
+        Level::Error => web_sys::console::error_1(&value),
+        Level::Warn => web_sys::console::warn_1(&value),
+        Level::Info => web_sys::console::info_1(&value),
+        Level::Debug | Level::Trace => web_sys::console::debug_1(&value),
+    }
+    PANEL.with(|panel| {
+        if let Some(lines) = panel.borrow_mut().as_mut() {
+            if lines.len() == PANEL_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(format!("{} {}", level.label(), message));
+        }
+    });
+}
+
+// The most recent lines, oldest first, when the panel is on.
+pub fn panel() -> Option<Vec<String>> {
+    PANEL.with(|panel| {
+        panel
+            .borrow()
+            .as_ref()
+            .map(|lines| lines.iter().cloned().collect())
+    })
+}
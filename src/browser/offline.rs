@@ -0,0 +1,52 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+use std::{cell::Cell, rc::Rc};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+
+// Registers the service worker that keeps `/static/*` and the wasm bundle in
+// the Cache API. The worker only turns active once everything is cached, so
+// `is_ready` means the game will start without a network.
+pub struct OfflineCache {
+    ready: Rc<Cell<bool>>,
+}
+
+impl OfflineCache {
+    pub fn register(script: &'static str) -> Self {
+        let ready = Rc::new(Cell::new(false));
+        let task_ready = Rc::clone(&ready);
+        browser::spawn_local(async move {
+            match activate(script).await {
+                Ok(()) => task_ready.set(true),
+                Err(err) => {
+                    warn!("Offline play is unavailable: {:?}", err);
+                }
+            }
+        });
+        OfflineCache { ready }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready.get()
+    }
+}
+
+async fn activate(script: &str) -> Result<()> {
+    let navigator = browser::window()?.navigator();
+    // Missing outside secure contexts, e.g. when served over plain http.
+    if !js_sys::Reflect::has(&navigator, &JsValue::from_str("serviceWorker")).unwrap_or(false) {
+        return Err(anyhow!("No service worker support"));
+    }
+    let container = navigator.service_worker();
+    JsFuture::from(container.register(script))
+        .await
+        .map_err(|err| anyhow!("Could not register {} {:#?}", script, err))?;
+    JsFuture::from(
+        container
+            .ready()
+            .map_err(|err| anyhow!("Could not wait for the service worker {:#?}", err))?,
+    )
+    .await
+    .map(|_| ())
+    .map_err(|err| anyhow!("Service worker never became ready {:#?}", err))
+}
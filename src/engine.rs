@@ -1,14 +1,52 @@
-use crate::browser;
-use anyhow::{anyhow, Result};
+pub mod assets;
+pub mod capture;
+pub mod chart;
+pub mod crash;
+pub mod fixed;
+pub mod font;
+#[cfg(all(test, target_arch = "wasm32"))]
+mod golden;
+pub mod lighting;
+pub mod particles;
+pub mod probe;
+pub mod recolor;
+pub mod rng;
+pub mod scene;
+pub mod sound;
+pub mod state_machine;
+pub mod style;
+pub mod timer;
+pub mod tween;
+pub mod ui;
+
+use self::{
+    assets::AssetError,
+    style::{Color, Palette, StrokeStyle, TextAlign, TextStyle},
+    timer::Timer,
+    tween::{Easing, Tween},
+};
+use crate::browser::{self, RetryPolicy};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use futures::channel::{
-    mpsc::{unbounded, UnboundedReceiver},
-    oneshot::channel,
+use futures::{
+    channel::{
+        mpsc::{unbounded, UnboundedReceiver},
+        oneshot::channel,
+    },
+    StreamExt,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell as StdCell, RefCell},
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
 };
-use serde::Deserialize;
-use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Mutex};
 use wasm_bindgen::{closure::Closure, JsCast, JsValue};
-use web_sys::{CanvasRenderingContext2d, HtmlImageElement};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, ImageBitmap};
 
 #[derive(Deserialize, Clone)]
 pub struct SheetRect {
@@ -18,16 +56,203 @@ pub struct SheetRect {
     pub h: i16,
 }
 
+#[derive(Deserialize, Clone, Copy, Default)]
+pub struct SourceSize {
+    pub w: i16,
+    pub h: i16,
+}
+
 #[derive(Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Cell {
     pub frame: SheetRect,
     pub sprite_source_size: SheetRect,
+    pub source_size: SourceSize,
+}
+
+impl Cell {
+    // Where the trimmed frame lands when its untrimmed source sits at the
+    // bottom centre of a `frame_size` box with its top-left at `position`, so
+    // frames trimmed or exported differently keep their feet in one place.
+    pub fn destination(&self, position: &Point, frame_size: SourceSize) -> Rect {
+        Rect {
+            x: position.x + (frame_size.w - self.source_size.w) / 2 + self.sprite_source_size.x,
+            y: position.y + frame_size.h - self.source_size.h + self.sprite_source_size.y,
+            width: self.frame.w,
+            height: self.frame.h,
+        }
+    }
 }
 
 #[derive(Deserialize, Clone)]
 pub struct Sheet {
     pub frames: HashMap<String, Cell>,
+    // Its name in the asset manifest, for errors.
+    #[serde(skip)]
+    pub name: String,
+}
+
+impl Sheet {
+    // The largest untrimmed frame, a common box to anchor every frame in.
+    pub fn frame_size(&self) -> SourceSize {
+        self.frames
+            .values()
+            .fold(SourceSize::default(), |size, cell| SourceSize {
+                w: size.w.max(cell.source_size.w),
+                h: size.h.max(cell.source_size.h),
+            })
+    }
+
+    // Checks every frame in `required` is there and every frame fits in
+    // `image`, reporting all of what is wrong rather than the first thing.
+    pub fn validate<S: AsRef<str>>(&self, image: &Texture, required: &[S]) -> Result<()> {
+        let (width, height) = (image.width(), image.height());
+        let mut problems = vec![];
+        let missing: Vec<String> = required
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|name| !self.frames.contains_key(*name))
+            .map(String::from)
+            .collect();
+        if !missing.is_empty() {
+            problems.push(AssetError::MissingFrame {
+                sheet: self.name.clone(),
+                frames: missing,
+            });
+        }
+        let mut oversized: Vec<String> = self
+            .frames
+            .iter()
+            .filter(|(_, cell)| {
+                let frame = &cell.frame;
+                frame.x < 0
+                    || frame.y < 0
+                    || i64::from(frame.x) + i64::from(frame.w) > i64::from(width)
+                    || i64::from(frame.y) + i64::from(frame.h) > i64::from(height)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        oversized.sort();
+        if !oversized.is_empty() {
+            problems.push(AssetError::FrameOutOfBounds {
+                sheet: self.name.clone(),
+                frames: oversized,
+                width,
+                height,
+            });
+        }
+        match problems.len() {
+            0 => Ok(()),
+            1 => Err(problems.remove(0).into()),
+            _ => Err(AssetError::Several(problems).into()),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Decoded {
+    Bitmap(ImageBitmap),
+    Element(HtmlImageElement),
+    Canvas(HtmlCanvasElement),
+    // Only a size, for running the game without a browser.
+    Blank { width: u32, height: u32 },
+}
+
+// A decoded image, ready to draw. A bitmap when the browser can decode off the
+// main thread, otherwise the loaded <img> element, or a canvas for images made
+// at run time.
+#[derive(Clone)]
+pub struct Texture {
+    source: String,
+    decoded: Decoded,
+    // Decoded pixels per pixel of the original image, below 1 for a
+    // downscaled copy. Sizes and frames are always in original pixels.
+    scale: f64,
+}
+
+impl Texture {
+    // Has the size of the image at `source` without any pixels, and draws
+    // nothing.
+    pub fn blank(source: &str, width: u32, height: u32) -> Self {
+        Texture {
+            source: source.to_string(),
+            decoded: Decoded::Blank { width, height },
+            scale: 1.0,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn width(&self) -> u32 {
+        (f64::from(self.decoded_size().0) / self.scale).round() as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        (f64::from(self.decoded_size().1) / self.scale).round() as u32
+    }
+
+    fn decoded_size(&self) -> (u32, u32) {
+        match &self.decoded {
+            Decoded::Bitmap(bitmap) => (bitmap.width(), bitmap.height()),
+            Decoded::Element(element) => (element.width(), element.height()),
+            Decoded::Canvas(canvas) => (canvas.width(), canvas.height()),
+            Decoded::Blank { width, height } => (*width, *height),
+        }
+    }
+
+    // A copy at `factor` of the resolution, copied onto a canvas of its own.
+    // It draws the same frames at the same size, only softer.
+    pub fn downscaled(&self, factor: f64) -> Result<Texture> {
+        let (width, height) = self.decoded_size();
+        let scaled = |size: u32| (f64::from(size) * factor).round().max(1.0) as u32;
+        let canvas = browser::create_canvas(scaled(width), scaled(height))?;
+        let context = browser::context_2d(&canvas)?;
+        Source::from(self).draw(
+            &context,
+            &self.frame(),
+            0.0,
+            0.0,
+            &Rect {
+                x: 0,
+                y: 0,
+                width: canvas.width() as i16,
+                height: canvas.height() as i16,
+            },
+        );
+        Ok(Texture {
+            source: self.source.clone(),
+            decoded: Decoded::Canvas(canvas),
+            scale: self.scale * factor,
+        })
+    }
+
+    // The whole image, in original pixels.
+    fn frame(&self) -> Rect {
+        Rect {
+            x: 0,
+            y: 0,
+            width: self.width() as i16,
+            height: self.height() as i16,
+        }
+    }
+}
+
+pub async fn load_texture(source: &str) -> Result<Texture> {
+    let blob = browser::fetch_blob(source).await?;
+    let decoded = match browser::create_image_bitmap(&blob).await {
+        Ok(bitmap) => Decoded::Bitmap(bitmap),
+        Err(err) => {
+            log!("Falling back to <img> for {}: {:#}", source, err);
+            Decoded::Element(load_image(source).await?)
+        }
+    };
+    Ok(Texture {
+        source: source.to_string(),
+        decoded,
+        scale: 1.0,
+    })
 }
 
 pub async fn load_image(source: &str) -> Result<HtmlImageElement> {
@@ -37,13 +262,13 @@ pub async fn load_image(source: &str) -> Result<HtmlImageElement> {
     let error_tx = Rc::clone(&success_tx);
     let callback = browser::closure_once(move || {
         if let Some(success_tx) = success_tx.lock().ok().and_then(|mut opt| opt.take()) {
-            success_tx.send(Ok(()));
+            let _ = success_tx.send(Ok(()));
         }
     });
 
     let error_callback: Closure<dyn FnMut(JsValue)> = browser::closure_once(move |err| {
         if let Some(error_tx) = error_tx.lock().ok().and_then(|mut opt| opt.take()) {
-            error_tx.send(Err(anyhow!("Error Loading Image: {:#?}", err)));
+            let _ = error_tx.send(Err(anyhow!("Error Loading Image: {:#?}", err)));
         }
     });
 
@@ -56,44 +281,384 @@ pub async fn load_image(source: &str) -> Result<HtmlImageElement> {
     Ok(image)
 }
 
+// Images with more pixels than this are loaded at half resolution on
+// devices the probe finds slow.
+const LARGE_IMAGE_PIXELS: u32 = 500_000;
+const DOWNSCALE_FACTOR: f64 = 0.5;
+
+pub struct Loader {
+    policy: RetryPolicy,
+    renderer: Renderer,
+    downscale: bool,
+}
+
+impl Loader {
+    pub fn new(policy: RetryPolicy) -> Result<Self> {
+        Ok(Loader {
+            policy,
+            renderer: Renderer::new()?,
+            downscale: probe::is_low_end(),
+        })
+    }
+
+    pub async fn json(&self, path: &str) -> Result<JsValue> {
+        self.show(&format!("Loading {}", path));
+        browser::retry(
+            &self.policy,
+            || browser::fetch_json(path),
+            |attempt, err| self.show_retry(path, attempt, err),
+        )
+        .await
+        .with_context(|| format!("Could not load {}", path))
+    }
+
+    pub async fn image(&self, path: &str) -> Result<Texture> {
+        self.show(&format!("Loading {}", path));
+        self.fetch_image(path, |attempt, err| self.show_retry(path, attempt, err))
+            .await
+    }
+
+    // For loads during play: retries are logged but nothing is drawn.
+    pub async fn image_quietly(&self, path: &str) -> Result<Texture> {
+        self.fetch_image(path, |attempt, err| self.warn_retry(path, attempt, err))
+            .await
+    }
+
+    async fn fetch_image(
+        &self,
+        path: &str,
+        on_retry: impl FnMut(u32, &anyhow::Error),
+    ) -> Result<Texture> {
+        let texture = browser::retry(&self.policy, || load_texture(path), on_retry)
+            .await
+            .with_context(|| format!("Could not load {}", path))?;
+        if self.downscale && texture.width() * texture.height() > LARGE_IMAGE_PIXELS {
+            return texture.downscaled(DOWNSCALE_FACTOR);
+        }
+        Ok(texture)
+    }
+
+    pub async fn array_buffer(&self, path: &str) -> Result<js_sys::ArrayBuffer> {
+        self.show(&format!("Loading {}", path));
+        browser::retry(
+            &self.policy,
+            || browser::fetch_array_buffer(path),
+            |attempt, err| self.show_retry(path, attempt, err),
+        )
+        .await
+        .with_context(|| format!("Could not load {}", path))
+    }
+
+    fn show_retry(&self, path: &str, attempt: u32, err: &anyhow::Error) {
+        self.warn_retry(path, attempt, err);
+        self.show(&format!(
+            "Retrying {} ({}/{})",
+            path,
+            attempt + 1,
+            self.policy.attempts
+        ));
+    }
+
+    fn warn_retry(&self, path: &str, attempt: u32, err: &anyhow::Error) {
+        warn!(
+            "Loading {} failed on attempt {} of {}: {:#}",
+            path, attempt, self.policy.attempts, err
+        );
+    }
+
+    fn show(&self, message: &str) {
+        self.renderer.fit();
+        self.renderer.clear(&self.renderer.viewport());
+        self.renderer
+            .draw_text(message, &Point { x: 20, y: 300 }, &SCREEN_TEXT);
+    }
+}
+
 #[async_trait(?Send)]
 pub trait Game {
     async fn initialize(&self) -> Result<Box<dyn Game>>;
-    fn update(&mut self, keystate: &KeyState);
-    fn draw(&self, renderer: &Renderer);
+    fn update(&mut self, keystate: &KeyState) -> Result<()>;
+    fn draw(&self, renderer: &Renderer) -> Result<()>;
+
+    // How fast `update` runs against the wall clock; see `TimeScale`.
+    fn time_scale(&self) -> f32 {
+        1.0
+    }
+
+    // Runs at the fixed rate whatever the time scale, for things like HUD
+    // effects that shouldn't slow down with the world.
+    fn update_unscaled(&mut self) {}
+
+    // Whether the developer time controls are on.
+    fn debug_controls(&self) -> bool {
+        false
+    }
+
+    // Whether to skip every other animation frame, halving the frame rate
+    // to save battery. Updates keep their fixed rate.
+    fn half_rate(&self) -> bool {
+        false
+    }
+}
+
+// Lets gameplay slow the world down for a moment, such as after a near miss.
+// The effect is counted in unscaled frames so it lasts the same real time
+// whatever the scale.
+#[derive(Clone, Copy, Default)]
+pub struct TimeScale {
+    scale: f32,
+    frames_left: Timer,
+}
+
+impl TimeScale {
+    pub fn slow(&mut self, scale: f32, frames: u16) {
+        self.scale = scale;
+        self.frames_left = Timer::after(frames);
+    }
+
+    pub fn reset(&mut self) {
+        self.frames_left = Timer::default();
+    }
+
+    // Call once per `Game::update_unscaled`.
+    pub fn tick(&mut self) {
+        self.frames_left.tick();
+    }
+
+    pub fn get(&self) -> f32 {
+        if self.frames_left.is_running() {
+            self.scale
+        } else {
+            1.0
+        }
+    }
+}
+
+// The size everything is laid out and drawn at, whatever the size and shape
+// of the canvas on the page.
+pub const LOGICAL_WIDTH: i16 = 960;
+pub const LOGICAL_HEIGHT: i16 = 600;
+
+// How the logical view is scaled up to the canvas.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Scaling {
+    Smooth,
+    // Images are scaled without smoothing, for hard pixel edges.
+    Pixelated,
+    // Pixelated, and only by whole multiples, so every art pixel covers the
+    // same number of screen pixels. The bars grow to make up the rest.
+    Integer,
+}
+
+// Read when the letterbox is worked out, which pointer events do too.
+static INTEGER_SCALING: AtomicBool = AtomicBool::new(false);
+
+// Where the logical view sits on a surface: scaled to fit whole, centered,
+// with bars down the sides or across the top and bottom for the rest.
+#[derive(Clone, Copy)]
+pub struct Letterbox {
+    scale: f64,
+    x: f64,
+    y: f64,
+}
+
+impl Letterbox {
+    // Surfaces smaller than the view are always scaled down smoothly.
+    pub fn fit(width: f64, height: f64, integer: bool) -> Self {
+        let logical_width = f64::from(LOGICAL_WIDTH);
+        let logical_height = f64::from(LOGICAL_HEIGHT);
+        let mut scale = (width / logical_width).min(height / logical_height);
+        if integer && scale >= 1.0 {
+            scale = scale.floor();
+        }
+        Letterbox {
+            scale,
+            x: ((width - logical_width * scale) / 2.0).round(),
+            y: ((height - logical_height * scale) / 2.0).round(),
+        }
+    }
+
+    // In the canvas's own pixels.
+    pub fn of_canvas(canvas: &HtmlCanvasElement) -> Self {
+        Letterbox::fit(
+            canvas.width().into(),
+            canvas.height().into(),
+            INTEGER_SCALING.load(Ordering::Relaxed),
+        )
+    }
+
+    // In the canvas's CSS pixels.
+    pub fn of_element(canvas: &HtmlCanvasElement) -> Self {
+        let letterbox = Letterbox::of_canvas(canvas);
+        if canvas.width() == 0 {
+            return letterbox;
+        }
+        let ratio = f64::from(canvas.client_width()) / f64::from(canvas.width());
+        Letterbox {
+            scale: letterbox.scale * ratio,
+            x: letterbox.x * ratio,
+            y: letterbox.y * ratio,
+        }
+    }
+
+    pub fn to_logical(self, x: f64, y: f64) -> Point {
+        if self.scale <= 0.0 {
+            return Point {
+                x: x as i16,
+                y: y as i16,
+            };
+        }
+        Point {
+            x: ((x - self.x) / self.scale) as i16,
+            y: ((y - self.y) / self.scale) as i16,
+        }
+    }
+
+    // The logical view's left, top, right and bottom edges on the surface.
+    pub fn edges(&self) -> [f64; 4] {
+        [
+            self.x,
+            self.y,
+            self.x + f64::from(LOGICAL_WIDTH) * self.scale,
+            self.y + f64::from(LOGICAL_HEIGHT) * self.scale,
+        ]
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
 }
 
-const FRAME_SIZE: f32 = 1.0 / 60.0 * 1000.0;
+// Seconds of game time each call to `Game::update` covers.
+pub const FIXED_DT: f64 = 1.0 / 60.0;
+const FRAME_SIZE: f32 = (FIXED_DT * 1000.0) as f32;
 pub struct GameLoop {
     last_frame: f64,
     accumulated_delta: f32,
+    unscaled_delta: f32,
+    debug: DebugControls,
+    skipped: bool,
+}
+
+const SLOW_MOTION_KEY: &str = "KeyT";
+const FREEZE_KEY: &str = "KeyF";
+const STEP_KEY: &str = "Period";
+const SLOW_MOTION_SCALE: f32 = 0.25;
+
+// Developer time controls, on with `?debug`: T toggles quarter speed, F
+// freezes the game and Period then runs exactly one fixed update per press.
+struct DebugControls {
+    enabled: bool,
+    slow_motion: bool,
+    frozen: bool,
+    held: [bool; 3],
+}
+
+impl DebugControls {
+    fn new(enabled: bool) -> Self {
+        DebugControls {
+            enabled,
+            slow_motion: false,
+            frozen: false,
+            held: [false; 3],
+        }
+    }
+
+    // Returns whether a single step was asked for this frame.
+    fn update(&mut self, keystate: &KeyState) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let pressed = [SLOW_MOTION_KEY, FREEZE_KEY, STEP_KEY].map(|key| keystate.is_pressed(key));
+        let [slow_motion, freeze, step] =
+            [0, 1, 2].map(|index| pressed[index] && !self.held[index]);
+        self.held = pressed;
+        if slow_motion {
+            self.slow_motion = !self.slow_motion;
+        }
+        if freeze {
+            self.frozen = !self.frozen;
+        }
+        step && self.frozen
+    }
+
+    fn time_scale(&self) -> f32 {
+        if self.frozen {
+            0.0
+        } else if self.slow_motion {
+            SLOW_MOTION_SCALE
+        } else {
+            1.0
+        }
+    }
 }
 type SharedLoopClosure = Rc<RefCell<Option<browser::LoopClosure>>>;
 
+#[derive(Clone, Copy, PartialEq)]
+enum Visibility {
+    Shown,
+    Hidden,
+    // Shown again after being hidden, with the clock not yet caught up.
+    Returned,
+}
+
 impl GameLoop {
     pub async fn start(game: impl Game + 'static) -> Result<()> {
         let mut keyevent_receiver = prepare_input()?;
-        let mut game = game.initialize().await?;
+        let mut game = loop {
+            match game.initialize().await {
+                Ok(game) => break game,
+                Err(err) => {
+                    error!("Could not initialize the game: {:?}", err);
+                    wait_for_retry(&err, &mut keyevent_receiver).await?;
+                }
+            }
+        };
         let mut game_loop = GameLoop {
             last_frame: browser::now()?,
             accumulated_delta: 0.0,
+            unscaled_delta: 0.0,
+            debug: DebugControls::new(game.debug_controls()),
+            skipped: false,
         };
-        let renderer = Renderer {
-            context: browser::context()?,
-        };
+        let renderer = Renderer::new()?;
         let f: SharedLoopClosure = Rc::new(RefCell::new(None));
         let g = f.clone();
 
+        let visibility = Rc::new(StdCell::new(Visibility::Shown));
+        let changed = Rc::clone(&visibility);
+        browser::on_visibility_change(move |hidden| {
+            changed.set(if hidden {
+                Visibility::Hidden
+            } else {
+                Visibility::Returned
+            });
+        })?;
+
         let mut keystate = KeyState::new();
         *g.borrow_mut() = Some(browser::create_raf_closure(move |perf: f64| {
+            if crash::crashed() {
+                return;
+            }
             process_input(&mut keystate, &mut keyevent_receiver);
-            game_loop.accumulated_delta += (perf - game_loop.last_frame) as f32;
-            while game_loop.accumulated_delta > FRAME_SIZE {
-                game.update(&keystate);
-                game_loop.accumulated_delta -= FRAME_SIZE;
+            // The time spent in another tab is skipped rather than caught up
+            // on all at once.
+            match visibility.get() {
+                Visibility::Shown => {}
+                Visibility::Hidden => {
+                    browser::request_animation_frame(f.borrow().as_ref().unwrap()).unwrap();
+                    return;
+                }
+                Visibility::Returned => {
+                    game_loop.resync(perf);
+                    visibility.set(Visibility::Shown);
+                }
+            }
+            if let Err(err) = game_loop.frame(perf, game.as_mut(), &mut keystate, &renderer) {
+                report_error("game loop", &err);
+                return;
             }
-            game_loop.last_frame = perf;
-            game.draw(&renderer);
             browser::request_animation_frame(f.borrow().as_ref().unwrap()).unwrap();
         }));
         browser::request_animation_frame(
@@ -103,12 +668,292 @@ impl GameLoop {
         )?;
         Ok(())
     }
+
+    fn resync(&mut self, perf: f64) {
+        self.last_frame = perf;
+        self.accumulated_delta = 0.0;
+        self.unscaled_delta = 0.0;
+    }
+
+    fn frame(
+        &mut self,
+        perf: f64,
+        game: &mut dyn Game,
+        keystate: &mut KeyState,
+        renderer: &Renderer,
+    ) -> Result<()> {
+        // The next frame's elapsed time covers this one too, so it runs the
+        // updates for both.
+        self.skipped = game.half_rate() && !self.skipped;
+        if self.skipped {
+            return Ok(());
+        }
+        if self.debug.update(keystate) {
+            game.update(keystate)?;
+            keystate.end_update();
+        }
+        let elapsed = (perf - self.last_frame) as f32 * self.debug.time_scale();
+        self.unscaled_delta += elapsed;
+        while self.unscaled_delta > FRAME_SIZE {
+            game.update_unscaled();
+            self.unscaled_delta -= FRAME_SIZE;
+        }
+        self.accumulated_delta += elapsed * game.time_scale();
+        while self.accumulated_delta > FRAME_SIZE {
+            game.update(keystate)?;
+            keystate.end_update();
+            self.accumulated_delta -= FRAME_SIZE;
+        }
+        self.last_frame = perf;
+        renderer.fit();
+        game.draw(renderer)
+    }
+}
+
+const MESSAGE_LINE_LENGTH: usize = 48;
+const MESSAGE_LINES: usize = 4;
+const MESSAGE_BUTTON: Rect = Rect {
+    x: 0,
+    y: 380,
+    width: 130,
+    height: 40,
+};
+const MESSAGE_BUTTON_GAP: i16 = 20;
+
+const SCREEN_TEXT: TextStyle = TextStyle {
+    font: "bold 20px sans-serif",
+    color: Color::BLACK,
+    align: TextAlign::Left,
+};
+
+pub fn report_error(stage: &str, err: &anyhow::Error) {
+    error!("Walk the Dog failed during {}: {:?}", stage, err);
+    if let Err(screen_err) = show_error_screen(err) {
+        error!("Could not show the error screen: {:?}", screen_err);
+    }
+}
+
+// A full-screen message for when the game cannot go on: a title, the
+// message wrapped over a few lines, and a row of buttons centered under it.
+// Returns where each button was drawn, for hit-testing.
+fn draw_message_screen<const N: usize>(
+    renderer: &Renderer,
+    title: &str,
+    message: &str,
+    labels: [&str; N],
+) -> [Rect; N] {
+    renderer.fit();
+    renderer.fill_rect(&renderer.viewport(), Color::WHITE.with_alpha(0.9));
+    renderer.draw_text(title, &Point { x: 40, y: 200 }, &SCREEN_TEXT);
+    let chars: Vec<char> = message.chars().collect();
+    for (line, chunk) in chars
+        .chunks(MESSAGE_LINE_LENGTH)
+        .take(MESSAGE_LINES)
+        .enumerate()
+    {
+        renderer.draw_text(
+            &chunk.iter().collect::<String>(),
+            &Point {
+                x: 40,
+                y: 240 + line as i16 * 26,
+            },
+            &SCREEN_TEXT,
+        );
+    }
+    let step = MESSAGE_BUTTON.width + MESSAGE_BUTTON_GAP;
+    let left = (LOGICAL_WIDTH - N as i16 * step + MESSAGE_BUTTON_GAP) / 2;
+    let buttons = std::array::from_fn(|index| Rect {
+        x: left + index as i16 * step,
+        ..MESSAGE_BUTTON
+    });
+    for (button, label) in buttons.iter().zip(labels) {
+        renderer.fill_rect(button, Color::rgb(0xDD, 0xDD, 0xDD));
+        renderer.draw_text(
+            label,
+            &Point {
+                x: button.x + button.width / 2,
+                y: button.y + 27,
+            },
+            &TextStyle {
+                align: TextAlign::Center,
+                ..SCREEN_TEXT
+            },
+        );
+    }
+    buttons
+}
+
+fn show_error_screen(err: &anyhow::Error) -> Result<()> {
+    let canvas = browser::canvas()?;
+    let renderer = Renderer::new()?;
+    let [reload] = draw_message_screen(
+        &renderer,
+        "Something went wrong",
+        &err.to_string(),
+        ["Reload"],
+    );
+
+    let click_canvas = canvas.clone();
+    let onclick = browser::closure_wrap(Box::new(move |event: web_sys::MouseEvent| {
+        let clicked = canvas_point(
+            &click_canvas,
+            f64::from(event.offset_x()),
+            f64::from(event.offset_y()),
+        );
+        if reload.contains(&clicked) {
+            if let Err(err) = browser::reload() {
+                error!("Could not reload: {:?}", err);
+            }
+        }
+    }) as Box<dyn FnMut(web_sys::MouseEvent)>);
+    canvas.set_onclick(Some(onclick.as_ref().unchecked_ref()));
+    onclick.forget();
+    Ok(())
+}
+
+// The loading-failed screen: what could not be loaded and a Retry button.
+// Resolves once the button is clicked or tapped, or Enter, Space or R is
+// pressed, so loading can start over without reloading the page.
+async fn wait_for_retry(
+    err: &anyhow::Error,
+    keyevent_receiver: &mut UnboundedReceiver<KeyPress>,
+) -> Result<()> {
+    const RETRY_KEYS: [&str; 3] = ["Enter", "Space", "KeyR"];
+    let renderer = Renderer::new()?;
+    let [retry] = draw_message_screen(&renderer, "Loading failed", &err.to_string(), ["Retry"]);
+
+    // A press only counts if it started on the button too.
+    let mut pressed = false;
+    while let Some(event) = keyevent_receiver.next().await {
+        match event {
+            KeyPress::KeyDown(event) if RETRY_KEYS.contains(&event.code().as_str()) => {
+                return Ok(());
+            }
+            KeyPress::Pointer { position, down, .. } => {
+                let on_button = retry.contains(&position);
+                if pressed && on_button && !down {
+                    return Ok(());
+                }
+                pressed = down && (pressed || on_button);
+            }
+            _ => {}
+        }
+    }
+    Err(anyhow!("Input stopped before Retry was pressed"))
+}
+
+// A second canvas that shows a copy of what the game just drew, such as a
+// zoomed follow-cam for a stream overlay.
+pub struct Mirror {
+    context: CanvasRenderingContext2d,
+}
+
+impl Mirror {
+    pub fn new(canvas: &HtmlCanvasElement) -> Result<Self> {
+        Ok(Mirror {
+            context: browser::context_2d(canvas)?,
+        })
+    }
+}
+
+enum Pixels<'a> {
+    Image(&'a HtmlImageElement),
+    Bitmap(&'a ImageBitmap),
+    Canvas(&'a HtmlCanvasElement),
+    Blank,
+}
+
+// Something to draw from, with frames given in its original pixels.
+struct Source<'a> {
+    pixels: Pixels<'a>,
+    scale: f64,
+}
+
+impl<'a> From<&'a Texture> for Source<'a> {
+    fn from(texture: &'a Texture) -> Self {
+        let pixels = match &texture.decoded {
+            Decoded::Bitmap(bitmap) => Pixels::Bitmap(bitmap),
+            Decoded::Element(element) => Pixels::Image(element),
+            Decoded::Canvas(canvas) => Pixels::Canvas(canvas),
+            Decoded::Blank { .. } => Pixels::Blank,
+        };
+        Source {
+            pixels,
+            scale: texture.scale,
+        }
+    }
+}
+
+impl Source<'_> {
+    // Draws `frame` of the source at (x, y), stretched to `size`'s width and
+    // height.
+    fn draw(&self, context: &CanvasRenderingContext2d, frame: &Rect, x: f64, y: f64, size: &Rect) {
+        let [frame_x, frame_y, frame_width, frame_height] =
+            [frame.x, frame.y, frame.width, frame.height]
+                .map(|value| f64::from(value) * self.scale);
+        match self.pixels {
+            Pixels::Image(image) => context
+                .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    image,
+                    frame_x,
+                    frame_y,
+                    frame_width,
+                    frame_height,
+                    x,
+                    y,
+                    size.width.into(),
+                    size.height.into(),
+                ),
+            Pixels::Bitmap(bitmap) => context
+                .draw_image_with_image_bitmap_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    bitmap,
+                    frame_x,
+                    frame_y,
+                    frame_width,
+                    frame_height,
+                    x,
+                    y,
+                    size.width.into(),
+                    size.height.into(),
+                ),
+            Pixels::Canvas(canvas) => context
+                .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    canvas,
+                    frame_x,
+                    frame_y,
+                    frame_width,
+                    frame_height,
+                    x,
+                    y,
+                    size.width.into(),
+                    size.height.into(),
+                ),
+            Pixels::Blank => Ok(()),
+        }
+        .expect("Drawing is throwing exceptions!");
+    }
+}
+
+// Image draws since the last `clear`, and how many of them were skipped for
+// being entirely outside the camera's view.
+#[derive(Clone, Copy, Default)]
+pub struct DrawStats {
+    pub drawn: u32,
+    pub culled: u32,
 }
 
 pub struct Renderer {
     context: CanvasRenderingContext2d,
+    scratch_canvas: HtmlCanvasElement,
+    scratch_context: CanvasRenderingContext2d,
+    cull_to: StdCell<Option<Rect>>,
+    stats: StdCell<DrawStats>,
+    hitboxes: StdCell<bool>,
+    palette: StdCell<Palette>,
+    scaling: StdCell<Scaling>,
 }
 
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Rect {
     pub x: i16,
     pub y: i16,
@@ -124,6 +969,20 @@ impl Rect {
             && self.bottom() > rect.y
     }
 
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= self.x && point.x < self.right() && point.y >= self.y && point.y < self.bottom()
+    }
+
+    // Grown by `margin` on every side.
+    pub fn inflate(&self, margin: i16) -> Rect {
+        Rect {
+            x: self.x - margin,
+            y: self.y - margin,
+            width: self.width + margin * 2,
+            height: self.height + margin * 2,
+        }
+    }
+
     pub fn right(&self) -> i16 {
         self.x + self.width
     }
@@ -133,8 +992,233 @@ impl Rect {
     }
 }
 
+#[derive(Clone)]
+pub struct Camera {
+    viewport: Rect,
+    // How far the view has been moved off the viewport, in world pixels.
+    pan: Point,
+    zoom: Tween,
+    // How many updates a change of zoom takes.
+    zoom_ticks: u16,
+    shake: f64,
+    shake_frame: u32,
+}
+
+const SHAKE_DECAY: f64 = 0.85;
+const TEXT_OUTLINE_WIDTH: f64 = 4.0;
+
+impl Camera {
+    pub fn new(viewport: Rect, zoom_ticks: u16) -> Self {
+        Camera {
+            viewport,
+            pan: Point { x: 0, y: 0 },
+            zoom: Tween::at(1.0),
+            zoom_ticks,
+            shake: 0.0,
+            shake_frame: 0,
+        }
+    }
+
+    // Jolts the view by up to `strength` pixels, dying away over a few
+    // frames.
+    pub fn shake(&mut self, strength: f64) {
+        self.shake = self.shake.max(strength);
+    }
+
+    pub fn pan_by(&mut self, x: i16, y: i16) {
+        self.pan.x += x;
+        self.pan.y += y;
+    }
+
+    pub fn zoom_to(&mut self, zoom: f64) {
+        self.zoom.retarget(zoom, self.zoom_ticks, Easing::EaseInOut);
+    }
+
+    pub fn update(&mut self) {
+        self.zoom.tick();
+        self.shake = if self.shake < 0.5 {
+            0.0
+        } else {
+            self.shake * SHAKE_DECAY
+        };
+        self.shake_frame = self.shake_frame.wrapping_add(1);
+    }
+
+    fn shake_offset(&self) -> (f64, f64) {
+        let frame = f64::from(self.shake_frame);
+        (
+            (frame * 1.7).sin() * self.shake,
+            (frame * 2.3).cos() * self.shake,
+        )
+    }
+
+    // The camera zooms around the bottom-left corner of the viewport so the
+    // ground line stays put while more of the track comes into view.
+    pub fn view(&self) -> Rect {
+        let zoom = self.zoom.value();
+        let width = (f64::from(self.viewport.width) / zoom).ceil() as i16;
+        let height = (f64::from(self.viewport.height) / zoom).ceil() as i16;
+        Rect {
+            x: self.viewport.x + self.pan.x,
+            y: self.viewport.bottom() - height + self.pan.y,
+            width,
+            height,
+        }
+    }
+
+    pub fn is_visible(&self, rect: &Rect) -> bool {
+        self.view().intersects(rect)
+    }
+
+    // The world point under a point on the canvas, such as the mouse.
+    pub fn to_world(&self, screen: Point) -> Point {
+        let unzoom = |value: i16, anchor: i16| {
+            anchor + (f64::from(value - anchor) / self.zoom.value()).round() as i16
+        };
+        Point {
+            x: unzoom(screen.x, self.viewport.x) + self.pan.x,
+            y: unzoom(screen.y, self.viewport.bottom()) + self.pan.y,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Transform {
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    // Radians, clockwise around the center of the destination rect.
+    pub rotation: f64,
+}
+
+impl Transform {
+    fn is_identity(&self) -> bool {
+        !self.flip_horizontal && !self.flip_vertical && self.rotation == 0.0
+    }
+
+    fn scale_x(&self) -> f64 {
+        if self.flip_horizontal {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    fn scale_y(&self) -> f64 {
+        if self.flip_vertical {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct DrawOptions {
+    pub transform: Transform,
+    pub alpha: f64,
+    // The tint's alpha is how strongly it covers the sprite.
+    pub tint: Option<Color>,
+}
+
+impl Default for DrawOptions {
+    fn default() -> Self {
+        DrawOptions {
+            transform: Transform::default(),
+            alpha: 1.0,
+            tint: None,
+        }
+    }
+}
+
 impl Renderer {
+    pub fn new() -> Result<Self> {
+        Renderer::with_context(browser::context()?)
+    }
+
+    fn with_context(context: CanvasRenderingContext2d) -> Result<Self> {
+        let scratch_canvas = browser::create_canvas(1, 1)?;
+        Ok(Renderer {
+            context,
+            scratch_context: browser::context_2d(&scratch_canvas)?,
+            scratch_canvas,
+            cull_to: StdCell::new(None),
+            stats: StdCell::new(DrawStats::default()),
+            hitboxes: StdCell::new(true),
+            palette: StdCell::new(Palette::STANDARD),
+            scaling: StdCell::new(Scaling::Smooth),
+        })
+    }
+
+    // The logical view, which is what every draw call is in.
+    pub fn viewport(&self) -> Rect {
+        Rect {
+            x: 0,
+            y: 0,
+            width: LOGICAL_WIDTH,
+            height: LOGICAL_HEIGHT,
+        }
+    }
+
+    // Sizes the canvas to its box on the page at the screen's pixel density
+    // and maps the logical view into it. Drawing stays inside the view, so
+    // the page shows through the bars. Call before drawing a screen.
+    pub fn fit(&self) {
+        let Some(canvas) = self.context.canvas() else {
+            return;
+        };
+        let ratio = browser::device_pixel_ratio().unwrap_or(1.0);
+        let width = (f64::from(canvas.client_width()) * ratio).round() as u32;
+        let height = (f64::from(canvas.client_height()) * ratio).round() as u32;
+        if width > 0 && height > 0 && (canvas.width() != width || canvas.height() != height) {
+            canvas.set_width(width);
+            canvas.set_height(height);
+        }
+        let letterbox = self.letterbox();
+        // Undoes the last fit, which saved the state before it clipped.
+        self.context.restore();
+        self.context
+            .set_image_smoothing_enabled(self.scaling.get() == Scaling::Smooth);
+        self.context.save();
+        self.context
+            .set_transform(
+                letterbox.scale,
+                0.0,
+                0.0,
+                letterbox.scale,
+                letterbox.x,
+                letterbox.y,
+            )
+            .expect("Transform is throwing exceptions!");
+        self.context.begin_path();
+        self.context
+            .rect(0.0, 0.0, LOGICAL_WIDTH.into(), LOGICAL_HEIGHT.into());
+        self.context.clip();
+    }
+
+    // In the canvas's own pixels.
+    fn letterbox(&self) -> Letterbox {
+        self.context
+            .canvas()
+            .map(|canvas| Letterbox::of_canvas(&canvas))
+            .unwrap_or(Letterbox::fit(
+                LOGICAL_WIDTH.into(),
+                LOGICAL_HEIGHT.into(),
+                false,
+            ))
+    }
+
+    // Takes effect from the next `fit`.
+    pub fn set_scaling(&self, scaling: Scaling) {
+        self.scaling.set(scaling);
+        INTEGER_SCALING.store(scaling == Scaling::Integer, Ordering::Relaxed);
+    }
+
+    pub fn draw_stats(&self) -> DrawStats {
+        self.stats.get()
+    }
+
     pub fn clear(&self, rect: &Rect) {
+        self.stats.set(DrawStats::default());
         self.context.clear_rect(
             rect.x.into(),
             rect.y.into(),
@@ -143,64 +1227,439 @@ impl Renderer {
         );
     }
 
-    pub fn draw_image(&self, image: &HtmlImageElement, frame: &Rect, destination: &Rect) {
+    pub fn with_camera<T>(&self, camera: &Camera, draw: impl FnOnce(&Renderer) -> T) -> T {
+        let anchor_x = f64::from(camera.viewport.x);
+        let anchor_y = f64::from(camera.viewport.bottom());
+        let (shake_x, shake_y) = camera.shake_offset();
+        let zoom = camera.zoom.value();
+        self.context.save();
+        self.context
+            .translate(anchor_x + shake_x, anchor_y + shake_y)
+            .expect("Translate is throwing exceptions!");
         self.context
-            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                &image,
-                frame.x.into(),
-                frame.y.into(),
-                frame.width.into(),
-                frame.height.into(),
+            .scale(zoom, zoom)
+            .expect("Scale is throwing exceptions!");
+        self.context
+            .translate(
+                -anchor_x - f64::from(camera.pan.x),
+                -anchor_y - f64::from(camera.pan.y),
+            )
+            .expect("Translate is throwing exceptions!");
+        let outer_cull = self.cull_to.replace(Some(camera.view()));
+        let result = draw(self);
+        self.cull_to.set(outer_cull);
+        self.context.restore();
+        result
+    }
+
+    pub fn draw_image(
+        &self,
+        image: &Texture,
+        frame: &Rect,
+        destination: &Rect,
+        options: &DrawOptions,
+    ) {
+        self.draw_source(&image.into(), frame, destination, options);
+    }
+
+    pub fn draw_prerendered(
+        &self,
+        prerendered: &Prerendered,
+        destination: &Rect,
+        options: &DrawOptions,
+    ) {
+        self.draw_source(
+            &Source::from(&prerendered.texture),
+            &prerendered.frame(),
+            destination,
+            options,
+        );
+    }
+
+    fn draw_source(
+        &self,
+        source: &Source,
+        frame: &Rect,
+        destination: &Rect,
+        options: &DrawOptions,
+    ) {
+        let mut stats = self.stats.get();
+        let culled = self
+            .cull_to
+            .get()
+            .is_some_and(|view| !view.intersects(destination));
+        if culled {
+            stats.culled += 1;
+        } else {
+            stats.drawn += 1;
+        }
+        self.stats.set(stats);
+        if culled {
+            return;
+        }
+        // Most sprites are drawn as they are, so skip the save/transform/
+        // restore round trip for those.
+        if options.tint.is_none() && options.alpha == 1.0 && options.transform.is_identity() {
+            source.draw(
+                &self.context,
+                frame,
                 destination.x.into(),
                 destination.y.into(),
-                destination.width.into(),
-                destination.height.into(),
+                destination,
+            );
+            return;
+        }
+        let half_width = f64::from(destination.width) / 2.0;
+        let half_height = f64::from(destination.height) / 2.0;
+        self.context.save();
+        self.context.set_global_alpha(options.alpha);
+        self.context
+            .translate(
+                f64::from(destination.x) + half_width,
+                f64::from(destination.y) + half_height,
             )
-            .expect("Drawing is throwing exceptions!");
+            .expect("Translate is throwing exceptions!");
+        self.context
+            .rotate(options.transform.rotation)
+            .expect("Rotate is throwing exceptions!");
+        self.context
+            .scale(options.transform.scale_x(), options.transform.scale_y())
+            .expect("Scale is throwing exceptions!");
+        match options.tint {
+            Some(tint) => {
+                self.tint_into_scratch(source, frame, tint);
+                self.context
+                    .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                        &self.scratch_canvas,
+                        0.0,
+                        0.0,
+                        frame.width.into(),
+                        frame.height.into(),
+                        -half_width,
+                        -half_height,
+                        destination.width.into(),
+                        destination.height.into(),
+                    )
+                    .expect("Drawing is throwing exceptions!");
+            }
+            None => source.draw(&self.context, frame, -half_width, -half_height, destination),
+        }
+        self.context.restore();
+    }
+
+    // Tinting happens on the scratch canvas so that "source-atop" only
+    // colors the sprite's own pixels and not whatever is already on screen.
+    // The frame goes in its top-left corner. Resizing a canvas reallocates
+    // it, so the canvas only ever grows, to the largest frame tinted so far.
+    fn tint_into_scratch(&self, source: &Source, frame: &Rect, tint: Color) {
+        let width = frame.width.max(1) as u32;
+        let height = frame.height.max(1) as u32;
+        if self.scratch_canvas.width() < width {
+            self.scratch_canvas.set_width(width);
+        }
+        if self.scratch_canvas.height() < height {
+            self.scratch_canvas.set_height(height);
+        }
+        self.scratch_context
+            .clear_rect(0.0, 0.0, width.into(), height.into());
+        source.draw(&self.scratch_context, frame, 0.0, 0.0, frame);
+        self.scratch_context.save();
+        self.scratch_context
+            .set_global_composite_operation("source-atop")
+            .expect("Composite operation is throwing exceptions!");
+        self.scratch_context.set_fill_style(&tint.to_js());
+        self.scratch_context
+            .fill_rect(0.0, 0.0, width.into(), height.into());
+        self.scratch_context.restore();
     }
 
-    pub fn draw_entire_image(&self, image: &HtmlImageElement, position: &Point) {
-        self.draw_rect(&Rect {
-            x: position.x.into(),
-            y: position.y.into(),
+    pub fn draw_entire_image(&self, image: &Texture, position: &Point, options: &DrawOptions) {
+        let destination = Rect {
+            x: position.x,
+            y: position.y,
             width: image.width() as i16,
             height: image.height() as i16,
-        });
+        };
+        self.draw_rect(&destination);
+        self.draw_image(
+            image,
+            &Rect {
+                x: 0,
+                y: 0,
+                ..destination
+            },
+            &destination,
+            options,
+        );
+    }
+
+    pub fn draw_canvas(&self, canvas: &HtmlCanvasElement, position: &Point) {
         self.context
-            .draw_image_with_html_image_element(image, position.x.into(), position.y.into())
-            .expect("Drawing is throwing exceptions! Unrecoverable error.");
+            .draw_image_with_html_canvas_element(canvas, position.x.into(), position.y.into())
+            .expect("Drawing is throwing exceptions!");
+    }
+
+    // Scales `source`, in the logical view, to fill the mirror.
+    pub fn mirror(&self, mirror: &Mirror, source: &Rect) {
+        let (Some(from), Some(to)) = (self.context.canvas(), mirror.context.canvas()) else {
+            return;
+        };
+        let letterbox = self.letterbox();
+        let [left, top, _, _] = letterbox.edges();
+        let scale = letterbox.scale;
+        mirror
+            .context
+            .clear_rect(0.0, 0.0, to.width().into(), to.height().into());
+        mirror
+            .context
+            .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                &from,
+                left + f64::from(source.x) * scale,
+                top + f64::from(source.y) * scale,
+                f64::from(source.width) * scale,
+                f64::from(source.height) * scale,
+                0.0,
+                0.0,
+                to.width().into(),
+                to.height().into(),
+            )
+            .expect("Drawing is throwing exceptions!");
+    }
+
+    pub fn fill_rect(&self, rect: &Rect, color: Color) {
+        self.context.save();
+        self.context.set_fill_style(&color.to_js());
+        self.context.fill_rect(
+            rect.x.into(),
+            rect.y.into(),
+            rect.width.into(),
+            rect.height.into(),
+        );
+        self.context.restore();
+    }
+
+    pub fn draw_text(&self, text: &str, location: &Point, style: &TextStyle) {
+        self.context.save();
+        self.context.set_font(style.font);
+        self.context.set_fill_style(&style.color.to_js());
+        self.context.set_text_align(style.align.as_str());
+        if let Some(outline) = self.palette.get().text_outline {
+            self.context.set_stroke_style(&outline.to_js());
+            self.context.set_line_width(TEXT_OUTLINE_WIDTH);
+            self.context.set_line_join("round");
+            self.context
+                .stroke_text(text, location.x.into(), location.y.into())
+                .expect("Drawing text is throwing exceptions!");
+        }
+        self.context
+            .fill_text(text, location.x.into(), location.y.into())
+            .expect("Drawing text is throwing exceptions!");
+        self.context.restore();
+    }
+
+    // `text` drawn once onto a canvas of its own, outlined the way
+    // `draw_text` would outline it in the current palette, and where the
+    // start of its baseline is on that canvas. Text that rarely changes can
+    // then be drawn as an image.
+    pub fn prerender_text(&self, text: &str, style: &TextStyle) -> Result<(Texture, Point)> {
+        let outline = self.palette.get().text_outline;
+        let pad = if outline.is_some() {
+            (TEXT_OUTLINE_WIDTH / 2.0).ceil()
+        } else {
+            0.0
+        };
+        self.context.save();
+        self.context.set_font(style.font);
+        let metrics = self.context.measure_text(text);
+        self.context.restore();
+        let metrics = metrics.map_err(|err| anyhow!("Could not measure {} {:#?}", text, err))?;
+        let ascent = metrics.actual_bounding_box_ascent().ceil();
+        let descent = metrics.actual_bounding_box_descent().ceil();
+        let canvas = browser::create_canvas(
+            (metrics.width() + 2.0 * pad).ceil() as u32,
+            (ascent + descent + 2.0 * pad) as u32,
+        )?;
+        let context = browser::context_2d(&canvas)?;
+        context.set_font(style.font);
+        context.set_fill_style(&style.color.to_js());
+        let baseline = pad + ascent;
+        if let Some(outline) = outline {
+            context.set_stroke_style(&outline.to_js());
+            context.set_line_width(TEXT_OUTLINE_WIDTH);
+            context.set_line_join("round");
+            context
+                .stroke_text(text, pad, baseline)
+                .map_err(|err| anyhow!("Could not outline {} {:#?}", text, err))?;
+        }
+        context
+            .fill_text(text, pad, baseline)
+            .map_err(|err| anyhow!("Could not draw {} {:#?}", text, err))?;
+        Ok((
+            Texture {
+                source: text.to_string(),
+                decoded: Decoded::Canvas(canvas),
+                scale: 1.0,
+            },
+            Point {
+                x: pad as i16,
+                y: baseline as i16,
+            },
+        ))
+    }
+
+    // Runs `draw` with a CSS filter such as "grayscale(1)" over everything it
+    // draws.
+    pub fn with_filter<T>(&self, filter: &str, draw: impl FnOnce(&Renderer) -> T) -> T {
+        self.context.save();
+        self.context.set_filter(filter);
+        let result = draw(self);
+        self.context.restore();
+        result
+    }
+
+    pub fn measure_text(&self, text: &str, style: &TextStyle) -> i16 {
+        self.context.save();
+        self.context.set_font(style.font);
+        let width = self
+            .context
+            .measure_text(text)
+            .map(|metrics| metrics.width())
+            .unwrap_or(0.0);
+        self.context.restore();
+        width.ceil() as i16
+    }
+
+    // Bounding boxes are only outlined while hitboxes are turned on.
+    pub fn set_hitboxes(&self, visible: bool) {
+        self.hitboxes.set(visible);
+    }
+
+    pub fn set_palette(&self, palette: Palette) {
+        self.palette.set(palette);
+    }
+
+    pub fn palette(&self) -> Palette {
+        self.palette.get()
     }
 
     pub fn draw_rect(&self, bounding_box: &Rect) {
-        self.context.set_stroke_style(&JsValue::from_str("#FF0000"));
-        self.context.begin_path();
-        self.context.rect(
-            bounding_box.x.into(),
-            bounding_box.y.into(),
-            bounding_box.width.into(),
-            bounding_box.height.into(),
+        if !self.hitboxes.get() {
+            return;
+        }
+        self.stroke_rect(
+            bounding_box,
+            &StrokeStyle {
+                color: self.palette.get().hitbox,
+                width: 1.0,
+            },
         );
+    }
+
+    pub fn stroke_rect(&self, rect: &Rect, style: &StrokeStyle) {
+        self.stroke_path(style, |context| {
+            context.rect(
+                rect.x.into(),
+                rect.y.into(),
+                rect.width.into(),
+                rect.height.into(),
+            );
+        });
+    }
+
+    pub fn draw_line(&self, from: &Point, to: &Point, style: &StrokeStyle) {
+        self.draw_polyline(&[*from, *to], style);
+    }
+
+    pub fn draw_polyline(&self, points: &[Point], style: &StrokeStyle) {
+        if let Some((first, rest)) = points.split_first() {
+            self.stroke_path(style, |context| {
+                context.move_to(first.x.into(), first.y.into());
+                rest.iter()
+                    .for_each(|point| context.line_to(point.x.into(), point.y.into()));
+            });
+        }
+    }
+
+    pub fn fill_polygon(&self, points: &[Point], color: Color) {
+        if let Some((first, rest)) = points.split_first() {
+            self.context.save();
+            self.context.set_fill_style(&color.to_js());
+            self.context.begin_path();
+            self.context.move_to(first.x.into(), first.y.into());
+            rest.iter()
+                .for_each(|point| self.context.line_to(point.x.into(), point.y.into()));
+            self.context.close_path();
+            self.context.fill();
+            self.context.restore();
+        }
+    }
+
+    pub fn stroke_circle(&self, center: &Point, radius: i16, style: &StrokeStyle) {
+        self.stroke_path(style, |context| {
+            Self::circle_path(context, center, radius);
+        });
+    }
+
+    pub fn fill_circle(&self, center: &Point, radius: i16, color: Color) {
+        self.context.save();
+        self.context.set_fill_style(&color.to_js());
+        self.context.begin_path();
+        Self::circle_path(&self.context, center, radius);
+        self.context.fill();
+        self.context.restore();
+    }
+
+    fn stroke_path(&self, style: &StrokeStyle, path: impl FnOnce(&CanvasRenderingContext2d)) {
+        self.context.save();
+        self.context.set_stroke_style(&style.color.to_js());
+        self.context.set_line_width(style.width);
+        self.context.begin_path();
+        path(&self.context);
         self.context.stroke();
+        self.context.restore();
+    }
+
+    fn circle_path(context: &CanvasRenderingContext2d, center: &Point, radius: i16) {
+        context
+            .arc(
+                center.x.into(),
+                center.y.into(),
+                radius.into(),
+                0.0,
+                std::f64::consts::TAU,
+            )
+            .expect("Drawing an arc is throwing exceptions!");
     }
 }
 
 enum KeyPress {
     KeyUp(web_sys::KeyboardEvent),
     KeyDown(web_sys::KeyboardEvent),
+    Pointer {
+        position: Point,
+        down: bool,
+        touch: bool,
+    },
+    Focus(bool),
 }
 
 fn prepare_input() -> Result<UnboundedReceiver<KeyPress>> {
     let (keydown_sender, keyevent_receiver) = unbounded();
     let keydown_sender = Rc::new(RefCell::new(keydown_sender));
     let keyup_sender = Rc::clone(&keydown_sender);
+    let pointer_sender = Rc::clone(&keydown_sender);
+    let touch_sender = Rc::clone(&keydown_sender);
+    let focus_sender = Rc::clone(&keydown_sender);
     let onkeydown = browser::closure_wrap(Box::new(move |keycode: web_sys::KeyboardEvent| {
-        keydown_sender
+        browser::prevent_game_key_default(&keycode);
+        let _ = keydown_sender
             .borrow_mut()
             .start_send(KeyPress::KeyDown(keycode));
     }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
 
     let onkeyup = browser::closure_wrap(Box::new(move |keycode: web_sys::KeyboardEvent| {
-        keyup_sender
+        browser::prevent_game_key_default(&keycode);
+        let _ = keyup_sender
             .borrow_mut()
             .start_send(KeyPress::KeyUp(keycode));
     }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
@@ -214,30 +1673,132 @@ fn prepare_input() -> Result<UnboundedReceiver<KeyPress>> {
 
     onkeydown.forget();
     onkeyup.forget();
+
+    let canvas = browser::canvas()?;
+    let pointer_canvas = canvas.clone();
+    let onmouse = browser::closure_wrap(Box::new(move |event: web_sys::MouseEvent| {
+        let _ = pointer_sender.borrow_mut().start_send(KeyPress::Pointer {
+            position: canvas_point(
+                &pointer_canvas,
+                f64::from(event.offset_x()),
+                f64::from(event.offset_y()),
+            ),
+            down: event.buttons() & 1 != 0,
+            touch: false,
+        });
+    }) as Box<dyn FnMut(web_sys::MouseEvent)>);
+    canvas.set_onmousedown(Some(onmouse.as_ref().unchecked_ref()));
+    canvas.set_onmouseup(Some(onmouse.as_ref().unchecked_ref()));
+    canvas.set_onmousemove(Some(onmouse.as_ref().unchecked_ref()));
+    onmouse.forget();
+
+    // The first finger stands in for the mouse. Handling touches here also
+    // keeps the page from scrolling or sending emulated mouse events.
+    let touch_canvas = canvas.clone();
+    let ontouch = browser::closure_wrap(Box::new(move |event: web_sys::TouchEvent| {
+        event.prevent_default();
+        let Some(touch) = event.changed_touches().get(0) else {
+            return;
+        };
+        let bounds = touch_canvas.get_bounding_client_rect();
+        let _ = touch_sender.borrow_mut().start_send(KeyPress::Pointer {
+            position: canvas_point(
+                &touch_canvas,
+                f64::from(touch.client_x()) - bounds.left(),
+                f64::from(touch.client_y()) - bounds.top(),
+            ),
+            down: event.touches().length() > 0,
+            touch: true,
+        });
+    }) as Box<dyn FnMut(web_sys::TouchEvent)>);
+    canvas.set_ontouchstart(Some(ontouch.as_ref().unchecked_ref()));
+    canvas.set_ontouchmove(Some(ontouch.as_ref().unchecked_ref()));
+    canvas.set_ontouchend(Some(ontouch.as_ref().unchecked_ref()));
+    canvas.set_ontouchcancel(Some(ontouch.as_ref().unchecked_ref()));
+    ontouch.forget();
+
+    // Key ups that happen while the canvas isn't focused never arrive, so
+    // keys held at that moment would stay stuck down.
+    browser::on_focus_change(&canvas, move |focused| {
+        let _ = focus_sender
+            .borrow_mut()
+            .start_send(KeyPress::Focus(focused));
+    })?;
     Ok(keyevent_receiver)
 }
 
+// Pointer coordinates are in CSS pixels over the whole canvas, bars and all.
+fn canvas_point(canvas: &HtmlCanvasElement, x: f64, y: f64) -> Point {
+    Letterbox::of_element(canvas).to_logical(x, y)
+}
+
 pub struct KeyState {
     pressed_keys: HashMap<String, web_sys::KeyboardEvent>,
+    // The keys that were down during the last update, to tell presses and
+    // releases from keys being held.
+    previous_keys: HashSet<String>,
+    pointer: Point,
+    pointer_down: bool,
+    // Whether the pointer is a finger rather than the mouse.
+    touch: bool,
+    focused: bool,
 }
 
 impl KeyState {
     fn new() -> Self {
         KeyState {
             pressed_keys: HashMap::new(),
+            previous_keys: HashSet::new(),
+            pointer: Point { x: 0, y: 0 },
+            pointer_down: false,
+            touch: false,
+            focused: true,
         }
     }
 
+    pub fn has_focus(&self) -> bool {
+        self.focused
+    }
+
     pub fn is_pressed(&self, code: &str) -> bool {
         self.pressed_keys.contains_key(code)
     }
 
+    pub fn was_just_released(&self, code: &str) -> bool {
+        !self.is_pressed(code) && self.previous_keys.contains(code)
+    }
+
+    fn end_update(&mut self) {
+        self.previous_keys = self.pressed_keys.keys().cloned().collect();
+    }
+
+    // Where the mouse last was over the canvas, in canvas pixels.
+    pub fn pointer(&self) -> Point {
+        self.pointer
+    }
+
+    pub fn is_pointer_down(&self) -> bool {
+        self.pointer_down
+    }
+
+    pub fn is_touching(&self) -> bool {
+        self.touch && self.pointer_down
+    }
+
     fn set_pressed(&mut self, code: &str, event: web_sys::KeyboardEvent) {
         self.pressed_keys.insert(code.into(), event);
     }
 
     fn set_released(&mut self, code: &str) {
-        self.pressed_keys.remove(code.into());
+        self.pressed_keys.remove(code);
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        if !focused {
+            self.pressed_keys.clear();
+            self.pointer_down = false;
+        }
     }
 }
 
@@ -249,6 +1810,16 @@ fn process_input(state: &mut KeyState, keyevent_receiver: &mut UnboundedReceiver
             Ok(Some(evt)) => match evt {
                 KeyPress::KeyUp(evt) => state.set_released(&evt.code()),
                 KeyPress::KeyDown(evt) => state.set_pressed(&evt.code(), evt),
+                KeyPress::Pointer {
+                    position,
+                    down,
+                    touch,
+                } => {
+                    state.pointer = position;
+                    state.pointer_down = down;
+                    state.touch = touch;
+                }
+                KeyPress::Focus(focused) => state.set_focused(focused),
             },
         };
     }
@@ -260,17 +1831,57 @@ pub struct Point {
     pub y: i16,
 }
 
+// Regions of an image copied once, left to right, onto their own canvas, for
+// sprites that are drawn every frame and never change. Clones share the
+// canvas. A blank image makes a blank copy.
+#[derive(Clone)]
+pub struct Prerendered {
+    texture: Texture,
+}
+
+impl Prerendered {
+    pub fn new(image: &Texture, frames: &[Rect]) -> Result<Self> {
+        let width: i16 = frames.iter().map(|frame| frame.width).sum();
+        let height = frames.iter().map(|frame| frame.height).max().unwrap_or(0);
+        if let Decoded::Blank { .. } = image.decoded {
+            return Ok(Prerendered {
+                texture: Texture::blank(&image.source, width as u32, height as u32),
+            });
+        }
+        let canvas = browser::create_canvas(width as u32, height as u32)?;
+        let context = browser::context_2d(&canvas)?;
+        let source = Source::from(image);
+        let mut x = 0.0;
+        for frame in frames {
+            source.draw(&context, frame, x, 0.0, frame);
+            x += f64::from(frame.width);
+        }
+        Ok(Prerendered {
+            texture: Texture {
+                source: image.source.clone(),
+                decoded: Decoded::Canvas(canvas),
+                scale: 1.0,
+            },
+        })
+    }
+
+    pub fn frame(&self) -> Rect {
+        self.texture.frame()
+    }
+}
+
+#[derive(Clone)]
 pub struct Image {
-    element: HtmlImageElement,
+    element: Texture,
     position: Point,
     bounding_box: Rect,
 }
 
 impl Image {
-    pub fn new(element: HtmlImageElement, position: Point) -> Self {
+    pub fn new(element: Texture, position: Point) -> Self {
         let bounding_box = Rect {
-            x: position.x.into(),
-            y: position.y.into(),
+            x: position.x,
+            y: position.y,
             width: element.width() as i16,
             height: element.height() as i16,
         };
@@ -281,8 +1892,16 @@ impl Image {
         }
     }
 
-    pub fn draw(&self, renderer: &Renderer) {
-        renderer.draw_entire_image(&self.element, &self.position)
+    pub fn draw(&self, renderer: &Renderer, options: &DrawOptions) {
+        renderer.draw_entire_image(&self.element, &self.position, options)
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn source(&self) -> &str {
+        self.element.source()
     }
 
     pub fn bounding_box(&self) -> &Rect {
@@ -0,0 +1,293 @@
+use crate::engine::{
+    sound::{Mixer, Sound},
+    Loader, Sheet, Texture,
+};
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+};
+use wasm_bindgen::JsValue;
+
+// What is wrong with an asset, as opposed to the network failing to fetch it.
+#[derive(Debug)]
+pub enum AssetError {
+    // No entry under that name in the manifest.
+    NotFound {
+        kind: &'static str,
+        name: String,
+    },
+    ParseError {
+        path: String,
+        message: String,
+    },
+    MissingFrame {
+        sheet: String,
+        frames: Vec<String>,
+    },
+    // Frames that reach outside the sheet's image.
+    FrameOutOfBounds {
+        sheet: String,
+        frames: Vec<String>,
+        width: u32,
+        height: u32,
+    },
+    // Everything wrong with one asset, so it can all be fixed in one go.
+    Several(Vec<AssetError>),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::NotFound { kind, name } => {
+                write!(f, "No {} named '{}' in the asset manifest", kind, name)
+            }
+            AssetError::ParseError { path, message } => {
+                write!(f, "Could not parse {}: {}", path, message)
+            }
+            AssetError::MissingFrame { sheet, frames } => {
+                write!(f, "Sheet {} has no frame {}", sheet, frames.join(", "))
+            }
+            AssetError::FrameOutOfBounds {
+                sheet,
+                frames,
+                width,
+                height,
+            } => write!(
+                f,
+                "Sheet {} has frames outside its {}x{} image: {}",
+                sheet,
+                width,
+                height,
+                frames.join(", ")
+            ),
+            AssetError::Several(errors) => {
+                let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                write!(f, "{}", messages.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+fn parse<T: DeserializeOwned>(path: &str, json: JsValue) -> Result<T, AssetError> {
+    serde_wasm_bindgen::from_value(json).map_err(|err| AssetError::ParseError {
+        path: path.to_string(),
+        message: err.to_string(),
+    })
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    images: HashMap<String, String>,
+    sheets: HashMap<String, String>,
+    data: HashMap<String, String>,
+    sounds: HashMap<String, String>,
+}
+
+// What an asset was loaded for. `unload` lets go of everything loaded for a
+// scope at once, while assets also loaded for the session stay.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Scope {
+    Session,
+    Theme,
+    // The boy's sheet and hitboxes, until he changes outfit.
+    Character,
+    // What only the level editor reads, until it is left.
+    Editor,
+}
+
+// Which cache an asset is kept in, and under what key.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum Cached {
+    Image(String),
+    Sheet(String),
+    Data(String),
+}
+
+pub struct AssetStore {
+    loader: Loader,
+    manifest: Manifest,
+    images: RefCell<HashMap<String, Texture>>,
+    // For each cached image, sheet and piece of data.
+    scopes: RefCell<HashMap<Cached, Scope>>,
+    // Loads after the loading screen happen during play, so they don't
+    // draw their progress over the game.
+    quiet: Cell<bool>,
+    sheets: RefCell<HashMap<String, Sheet>>,
+    data: RefCell<HashMap<String, JsValue>>,
+    sounds: RefCell<HashMap<String, Sound>>,
+}
+
+impl AssetStore {
+    pub async fn load(loader: Loader, manifest_path: &str) -> Result<Self> {
+        let manifest = parse(manifest_path, loader.json(manifest_path).await?)?;
+        Ok(AssetStore {
+            loader,
+            manifest,
+            images: RefCell::new(HashMap::new()),
+            scopes: RefCell::new(HashMap::new()),
+            quiet: Cell::new(false),
+            sheets: RefCell::new(HashMap::new()),
+            data: RefCell::new(HashMap::new()),
+            sounds: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub async fn image(&self, name: &str) -> Result<Texture> {
+        self.image_in(Scope::Session, name).await
+    }
+
+    pub async fn image_in(&self, scope: Scope, name: &str) -> Result<Texture> {
+        let cached = self.images.borrow().get(name).cloned();
+        if let Some(image) = cached {
+            trace!("Image {} is already loaded", name);
+            self.claim(Cached::Image(name.to_string()), scope);
+            return Ok(image);
+        }
+        let path = path_for(&self.manifest.images, "image", name)?;
+        let image = if self.quiet.get() {
+            self.loader.image_quietly(path).await?
+        } else {
+            self.loader.image(path).await?
+        };
+        self.images
+            .borrow_mut()
+            .insert(name.to_string(), image.clone());
+        self.claim(Cached::Image(name.to_string()), scope);
+        Ok(image)
+    }
+
+    // Drops the cached images, sheets and data loaded only for `scope`, so
+    // they are freed once whatever uses them lets go too. Loading one again
+    // fetches it anew.
+    pub fn unload(&self, scope: Scope) {
+        let mut images = self.images.borrow_mut();
+        let mut sheets = self.sheets.borrow_mut();
+        let mut data = self.data.borrow_mut();
+        self.scopes.borrow_mut().retain(|cached, owner| {
+            if *owner != scope {
+                return true;
+            }
+            match cached {
+                Cached::Image(name) => {
+                    images.remove(name);
+                }
+                Cached::Sheet(name) => {
+                    sheets.remove(name);
+                }
+                Cached::Data(key) => {
+                    data.remove(key);
+                }
+            }
+            trace!("Unloaded {:?}", cached);
+            false
+        });
+    }
+
+    // An asset that belongs to the session as well stays in it.
+    fn claim(&self, cached: Cached, scope: Scope) {
+        let mut scopes = self.scopes.borrow_mut();
+        let owner = scopes.entry(cached).or_insert(scope);
+        if scope == Scope::Session {
+            *owner = scope;
+        }
+    }
+
+    // Call once the loading screen is done with.
+    pub fn finish_loading(&self) {
+        self.quiet.set(true);
+    }
+
+    pub async fn sheet(&self, name: &str) -> Result<Sheet> {
+        self.sheet_in(Scope::Session, name).await
+    }
+
+    pub async fn sheet_in(&self, scope: Scope, name: &str) -> Result<Sheet> {
+        let cached = self.sheets.borrow().get(name).cloned();
+        if let Some(sheet) = cached {
+            self.claim(Cached::Sheet(name.to_string()), scope);
+            return Ok(sheet);
+        }
+        let path = path_for(&self.manifest.sheets, "sheet", name)?;
+        let mut sheet: Sheet = parse(path, self.loader.json(path).await?)?;
+        sheet.name = name.to_string();
+        self.sheets
+            .borrow_mut()
+            .insert(name.to_string(), sheet.clone());
+        self.claim(Cached::Sheet(name.to_string()), scope);
+        Ok(sheet)
+    }
+
+    // JSON kept next to a sprite sheet, e.g. `rhb.hitboxes.json` beside
+    // `rhb.json`, so it needs no manifest entry of its own.
+    pub async fn sheet_sidecar_in<T: DeserializeOwned>(
+        &self,
+        scope: Scope,
+        sheet: &str,
+        suffix: &str,
+    ) -> Result<T> {
+        let sheet_path = path_for(&self.manifest.sheets, "sheet", sheet)?;
+        let path = format!(
+            "{}.{}.json",
+            sheet_path.strip_suffix(".json").unwrap_or(sheet_path),
+            suffix
+        );
+        let key = format!("{}.{}", sheet, suffix);
+        Ok(parse(&path, self.json(scope, key, &path).await?)?)
+    }
+
+    pub async fn data<T: DeserializeOwned>(&self, name: &str) -> Result<T> {
+        self.data_in(Scope::Session, name).await
+    }
+
+    pub async fn data_in<T: DeserializeOwned>(&self, scope: Scope, name: &str) -> Result<T> {
+        let path = path_for(&self.manifest.data, "data", name)?;
+        Ok(parse(
+            path,
+            self.json(scope, name.to_string(), path).await?,
+        )?)
+    }
+
+    // Decoded by `mixer`, which the sound can then only be played through.
+    pub async fn sound(&self, name: &str, mixer: &Mixer) -> Result<Sound> {
+        if let Some(sound) = self.sounds.borrow().get(name) {
+            return Ok(sound.clone());
+        }
+        let path = path_for(&self.manifest.sounds, "sound", name)?;
+        let sound = mixer.decode(&self.loader.array_buffer(path).await?).await?;
+        self.sounds
+            .borrow_mut()
+            .insert(name.to_string(), sound.clone());
+        Ok(sound)
+    }
+
+    async fn json(&self, scope: Scope, key: String, path: &str) -> Result<JsValue> {
+        let cached = self.data.borrow().get(&key).cloned();
+        if let Some(data) = cached {
+            self.claim(Cached::Data(key), scope);
+            return Ok(data);
+        }
+        let data = self.loader.json(path).await?;
+        self.data.borrow_mut().insert(key.clone(), data.clone());
+        self.claim(Cached::Data(key), scope);
+        Ok(data)
+    }
+}
+
+fn path_for<'a>(
+    section: &'a HashMap<String, String>,
+    kind: &'static str,
+    name: &str,
+) -> Result<&'a str, AssetError> {
+    section
+        .get(name)
+        .map(String::as_str)
+        .ok_or_else(|| AssetError::NotFound {
+            kind,
+            name: name.to_string(),
+        })
+}
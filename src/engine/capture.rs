@@ -0,0 +1,132 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::JsCast;
+use web_sys::{
+    Blob, BlobEvent, BlobPropertyBag, HtmlCanvasElement, MediaRecorder, MediaStream, Url,
+};
+
+const CHUNK_MS: i32 = 500;
+// Each take is restarted after twice this long, and the two are started this
+// far apart, so one of them always holds between one and two lengths of
+// footage, header included.
+const CLIP_MS: f64 = 3000.0;
+// Chunks kept after the one the knockout happened in.
+const TAIL_CHUNKS: usize = 1;
+const VIDEO_TYPE: &str = "video/webm";
+
+pub fn save_screenshot(canvas: &HtmlCanvasElement, filename: &str) -> Result<()> {
+    let url = canvas
+        .to_data_url_with_type("image/png")
+        .map_err(|err| anyhow!("Could not encode the canvas {:#?}", err))?;
+    browser::download(&url, filename)
+}
+
+// One MediaRecorder session. A WebM only plays from its first chunk, which
+// carries the header, so a take is kept whole from there on.
+struct Take {
+    chunks: Rc<RefCell<Vec<Blob>>>,
+    recorder: MediaRecorder,
+    started: f64,
+}
+
+impl Take {
+    fn start(stream: &MediaStream) -> Result<Self> {
+        let recorder = MediaRecorder::new_with_media_stream(stream)
+            .map_err(|err| anyhow!("Could not create MediaRecorder {:#?}", err))?;
+        let chunks = Rc::new(RefCell::new(vec![]));
+        let ondataavailable_chunks = Rc::clone(&chunks);
+        let ondataavailable = browser::closure_wrap(Box::new(move |event: BlobEvent| {
+            if let Some(blob) = event.data() {
+                ondataavailable_chunks.borrow_mut().push(blob);
+            }
+        }) as Box<dyn FnMut(BlobEvent)>);
+        recorder.set_ondataavailable(Some(ondataavailable.as_ref().unchecked_ref()));
+        ondataavailable.forget();
+        recorder
+            .start_with_time_slice(CHUNK_MS)
+            .map_err(|err| anyhow!("Could not start recording {:#?}", err))?;
+        Ok(Take {
+            chunks,
+            recorder,
+            started: browser::now()?,
+        })
+    }
+
+    fn stop(&self) {
+        if let Err(err) = self.recorder.stop() {
+            warn!("Could not stop a recording {:#?}", err);
+        }
+    }
+}
+
+// Records the canvas continuously in two staggered takes, each restarted
+// every 2 * CLIP_MS, and clips the older one. At a knockout the takes stop
+// rotating and the clip is cut a chunk after that moment, however long the
+// player waits before saving it.
+pub struct Recorder {
+    stream: MediaStream,
+    older: Take,
+    newer: Option<Take>,
+    // How many chunks of `older` the clip takes, once there was a knockout.
+    cut: Option<usize>,
+}
+
+impl Recorder {
+    pub fn start(canvas: &HtmlCanvasElement) -> Result<Self> {
+        let stream = canvas
+            .capture_stream()
+            .map_err(|err| anyhow!("Could not capture the canvas {:#?}", err))?;
+        Ok(Recorder {
+            older: Take::start(&stream)?,
+            newer: None,
+            stream,
+            cut: None,
+        })
+    }
+
+    // Call once per update.
+    pub fn update(&mut self) -> Result<()> {
+        if self.cut.is_some() {
+            return Ok(());
+        }
+        let now = browser::now()?;
+        if self.newer.is_none() && now - self.older.started >= CLIP_MS {
+            self.newer = Some(Take::start(&self.stream)?);
+        }
+        if now - self.older.started >= 2.0 * CLIP_MS {
+            if let Some(newer) = self.newer.take() {
+                self.older.stop();
+                self.older = newer;
+                self.newer = Some(Take::start(&self.stream)?);
+            }
+        }
+        Ok(())
+    }
+
+    // Ends the clip just after now.
+    pub fn knock_out(&mut self) {
+        self.cut = Some(self.older.chunks.borrow().len() + 1 + TAIL_CHUNKS);
+    }
+
+    // Back to rotating takes for the next clip.
+    pub fn resume(&mut self) {
+        self.cut = None;
+    }
+
+    pub fn export(&self, filename: &str) -> Result<()> {
+        let chunks = self.older.chunks.borrow();
+        let kept = self.cut.unwrap_or(chunks.len()).min(chunks.len());
+        let parts: js_sys::Array = chunks[..kept].iter().collect();
+        let mut options = BlobPropertyBag::new();
+        options.type_(VIDEO_TYPE);
+        let video = Blob::new_with_blob_sequence_and_options(&parts, &options)
+            .map_err(|err| anyhow!("Could not assemble the clip {:#?}", err))?;
+        let url = Url::create_object_url_with_blob(&video)
+            .map_err(|err| anyhow!("Could not create a URL for the clip {:#?}", err))?;
+        let result = browser::download(&url, filename);
+        Url::revoke_object_url(&url)
+            .map_err(|err| anyhow!("Could not revoke the clip URL {:#?}", err))?;
+        result
+    }
+}
@@ -0,0 +1,107 @@
+use crate::engine::{
+    style::{Color, StrokeStyle},
+    Point, Rect, Renderer,
+};
+
+const AXIS_STYLE: StrokeStyle = StrokeStyle {
+    color: Color::rgb(0x33, 0x33, 0x33),
+    width: 2.0,
+};
+
+pub struct Chart {
+    area: Rect,
+    slots: usize,
+    max_value: f64,
+}
+
+impl Chart {
+    pub fn new(area: Rect, slots: usize, max_value: f64) -> Self {
+        Chart {
+            area,
+            slots: slots.max(1),
+            max_value: max_value.max(1.0),
+        }
+    }
+
+    pub fn draw_axes(&self, renderer: &Renderer) {
+        renderer.draw_polyline(
+            &[
+                Point {
+                    x: self.area.x,
+                    y: self.area.y,
+                },
+                Point {
+                    x: self.area.x,
+                    y: self.area.bottom(),
+                },
+                Point {
+                    x: self.area.right(),
+                    y: self.area.bottom(),
+                },
+            ],
+            &AXIS_STYLE,
+        );
+    }
+
+    pub fn draw_bars(&self, renderer: &Renderer, values: &[f64], color: Color) {
+        let bar_width = (self.slot_width() * 0.7).max(1.0) as i16;
+        values.iter().enumerate().for_each(|(index, value)| {
+            let top = self.point_for(index, *value);
+            renderer.fill_rect(
+                &Rect {
+                    x: top.x - bar_width / 2,
+                    y: top.y,
+                    width: bar_width,
+                    height: self.area.bottom() - top.y,
+                },
+                color,
+            );
+        });
+    }
+
+    pub fn draw_line(&self, renderer: &Renderer, values: &[f64], color: Color) {
+        const MARKER_RADIUS: i16 = 3;
+        let points: Vec<Point> = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| self.point_for(index, *value))
+            .collect();
+        renderer.draw_polyline(&points, &StrokeStyle { color, width: 2.0 });
+        points
+            .iter()
+            .for_each(|point| renderer.fill_circle(point, MARKER_RADIUS, color));
+    }
+
+    pub fn draw_reference(&self, renderer: &Renderer, value: f64, color: Color) {
+        let y = self.point_for(0, value).y;
+        renderer.draw_line(
+            &Point { x: self.area.x, y },
+            &Point {
+                x: self.area.right(),
+                y,
+            },
+            &StrokeStyle { color, width: 2.0 },
+        );
+    }
+
+    pub fn highlight(&self, renderer: &Renderer, index: usize, value: f64, color: Color) {
+        const HIGHLIGHT_RADIUS: i16 = 8;
+        renderer.stroke_circle(
+            &self.point_for(index, value),
+            HIGHLIGHT_RADIUS,
+            &StrokeStyle { color, width: 2.0 },
+        );
+    }
+
+    fn slot_width(&self) -> f64 {
+        f64::from(self.area.width) / self.slots as f64
+    }
+
+    fn point_for(&self, index: usize, value: f64) -> Point {
+        let ratio = (value / self.max_value).clamp(0.0, 1.0);
+        Point {
+            x: self.area.x + (self.slot_width() * (index as f64 + 0.5)) as i16,
+            y: self.area.bottom() - (ratio * f64::from(self.area.height)) as i16,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use super::{canvas_point, draw_message_screen, Renderer};
+use crate::browser;
+use std::{cell::Cell, panic::PanicHookInfo};
+use wasm_bindgen::{JsCast, JsValue};
+
+thread_local! {
+    static CRASHED: Cell<bool> = const { Cell::new(false) };
+}
+
+// Replaces the default panic hook. The panic still goes to the console, and
+// the canvas gets an overlay with the message and a way to copy the details,
+// instead of freezing on whatever frame was last drawn.
+pub fn install() {
+    std::panic::set_hook(Box::new(|info| {
+        console_error_panic_hook::hook(info);
+        CRASHED.with(|crashed| crashed.set(true));
+        if let Err(err) = show_crash_screen(info) {
+            error!("Could not show the crash screen: {:?}", err);
+        }
+    }));
+}
+
+// The game loop checks this before each frame and stops for good once set.
+pub fn crashed() -> bool {
+    CRASHED.with(Cell::get)
+}
+
+fn show_crash_screen(info: &PanicHookInfo) -> anyhow::Result<()> {
+    let message = info.to_string();
+    let details = format!("{}\n\n{}", message, stack());
+    let canvas = browser::canvas()?;
+    let renderer = Renderer::new()?;
+    let [copy, reload] = draw_message_screen(
+        &renderer,
+        "The game crashed",
+        &message,
+        ["Copy details", "Reload"],
+    );
+
+    let click_canvas = canvas.clone();
+    let onclick = browser::closure_wrap(Box::new(move |event: web_sys::MouseEvent| {
+        let clicked = canvas_point(
+            &click_canvas,
+            f64::from(event.offset_x()),
+            f64::from(event.offset_y()),
+        );
+        let result = if copy.contains(&clicked) {
+            browser::copy_to_clipboard(&details)
+        } else if reload.contains(&clicked) {
+            browser::reload()
+        } else {
+            Ok(())
+        };
+        if let Err(err) = result {
+            error!("Crash screen button failed: {:?}", err);
+        }
+    }) as Box<dyn FnMut(web_sys::MouseEvent)>);
+    canvas.set_onclick(Some(onclick.as_ref().unchecked_ref()));
+    onclick.forget();
+    Ok(())
+}
+
+// Rust has no backtrace on wasm, so this is the JavaScript stack at the panic,
+// which still names the wasm functions.
+fn stack() -> String {
+    js_sys::Reflect::get(&js_sys::Error::new(""), &JsValue::from_str("stack"))
+        .ok()
+        .and_then(|stack| stack.as_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,74 @@
+// 16.16 fixed-point numbers for simulation values that need fractions.
+// Integer arithmetic gives the same bits on every platform, which floats
+// do not guarantee, so recorded runs replay identically everywhere.
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+const FRACTION_BITS: u32 = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    pub const fn from_int(value: i16) -> Self {
+        Fixed((value as i32) << FRACTION_BITS)
+    }
+
+    pub const fn from_ratio(numerator: i16, denominator: i16) -> Self {
+        Fixed(((numerator as i32) << FRACTION_BITS) / denominator as i32)
+    }
+
+    pub fn round(self) -> i16 {
+        ((self.0 + (1 << (FRACTION_BITS - 1))) >> FRACTION_BITS) as i16
+    }
+
+    // The cubic ease-in-out curve for progress from 0 to 1, the same shape as
+    // `Easing::EaseInOut` but without floats.
+    pub fn ease_in_out(self) -> Fixed {
+        let one = Fixed::from_int(1);
+        let t = self.clamp(Fixed::default(), one);
+        if t < Fixed::from_ratio(1, 2) {
+            Fixed::from_int(4) * t * t * t
+        } else {
+            let rest = Fixed::from_int(2) - Fixed::from_int(2) * t;
+            one - rest * rest * rest * Fixed::from_ratio(1, 2)
+        }
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_add(other.0))
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Fixed) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_sub(other.0))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed(self.0.wrapping_neg())
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, other: Fixed) -> Fixed {
+        Fixed(((i64::from(self.0) * i64::from(other.0)) >> FRACTION_BITS) as i32)
+    }
+}
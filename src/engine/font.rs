@@ -0,0 +1,207 @@
+use crate::engine::{
+    style::{Color, TextStyle},
+    DrawOptions, Point, Rect, Renderer, Texture,
+};
+use anyhow::Result;
+use std::cell::RefCell;
+
+const GLYPH_WIDTH: i16 = 3;
+const GLYPH_HEIGHT: i16 = 5;
+
+// 3x5 pixel glyphs, one row per three bits, top row in the high bits.
+fn glyph(character: char) -> u16 {
+    match character.to_ascii_uppercase() {
+        '0' => 0b111_101_101_101_111,
+        '1' => 0b010_110_010_010_111,
+        '2' => 0b111_001_111_100_111,
+        '3' => 0b111_001_111_001_111,
+        '4' => 0b101_101_111_001_001,
+        '5' => 0b111_100_111_001_111,
+        '6' => 0b111_100_111_101_111,
+        '7' => 0b111_001_001_001_001,
+        '8' => 0b111_101_111_101_111,
+        '9' => 0b111_101_111_001_111,
+        'A' => 0b010_101_111_101_101,
+        'B' => 0b110_101_110_101_110,
+        'C' => 0b011_100_100_100_011,
+        'D' => 0b110_101_101_101_110,
+        'E' => 0b111_100_110_100_111,
+        'F' => 0b111_100_110_100_100,
+        'G' => 0b011_100_101_101_011,
+        'H' => 0b101_101_111_101_101,
+        'I' => 0b111_010_010_010_111,
+        'J' => 0b001_001_001_101_010,
+        'K' => 0b101_101_110_101_101,
+        'L' => 0b100_100_100_100_111,
+        'M' => 0b101_111_111_101_101,
+        'N' => 0b110_101_101_101_101,
+        'O' => 0b010_101_101_101_010,
+        'P' => 0b110_101_110_100_100,
+        'Q' => 0b010_101_101_110_011,
+        'R' => 0b110_101_110_101_101,
+        'S' => 0b011_100_010_001_110,
+        'T' => 0b111_010_010_010_010,
+        'U' => 0b101_101_101_101_111,
+        'V' => 0b101_101_101_101_010,
+        'W' => 0b101_101_111_111_101,
+        'X' => 0b101_101_010_101_101,
+        'Y' => 0b101_101_010_010_010,
+        'Z' => 0b111_001_010_100_111,
+        '.' => 0b000_000_000_000_010,
+        ',' => 0b000_000_000_010_100,
+        '!' => 0b010_010_010_000_010,
+        '?' => 0b110_001_010_000_010,
+        '-' => 0b000_000_111_000_000,
+        ':' => 0b000_010_000_010_000,
+        '\'' => 0b010_010_000_000_000,
+        '/' => 0b001_001_010_100_100,
+        _ => 0,
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct BitmapFont {
+    pub scale: i16,
+    pub color: Color,
+}
+
+impl BitmapFont {
+    pub fn line_height(&self) -> i16 {
+        GLYPH_HEIGHT * self.scale
+    }
+
+    pub fn measure(&self, text: &str) -> i16 {
+        let count = text.chars().count() as i16;
+        if count == 0 {
+            0
+        } else {
+            (count * (GLYPH_WIDTH + 1) - 1) * self.scale
+        }
+    }
+
+    pub fn draw(&self, renderer: &Renderer, text: &str, origin: &Point) {
+        for (index, character) in text.chars().enumerate() {
+            let left = origin.x + index as i16 * (GLYPH_WIDTH + 1) * self.scale;
+            let bits = glyph(character);
+            for row in 0..GLYPH_HEIGHT {
+                for column in 0..GLYPH_WIDTH {
+                    let bit = (GLYPH_HEIGHT - row) * GLYPH_WIDTH - column - 1;
+                    if bits & (1 << bit) != 0 {
+                        renderer.fill_rect(
+                            &Rect {
+                                x: left + column * self.scale,
+                                y: origin.y + row * self.scale,
+                                width: self.scale,
+                                height: self.scale,
+                            },
+                            self.color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The digits 0 to 9 side by side in one image, in cells of equal width. A
+// number is a drawImage per digit, which is much cheaper than laying out
+// text for a value that changes every frame, like the score. Its label is
+// laid out once and kept as an image too.
+pub struct DigitStrip {
+    image: Texture,
+    digit_width: i16,
+    height: i16,
+    label: RefCell<Option<Label>>,
+}
+
+// Rendered again only when the text or the palette's outline changes.
+struct Label {
+    text: String,
+    outline: Option<Color>,
+    image: Texture,
+    origin: Point,
+}
+
+impl DigitStrip {
+    pub fn new(image: Texture) -> Self {
+        DigitStrip {
+            digit_width: (image.width() / 10) as i16,
+            height: image.height() as i16,
+            image,
+            label: RefCell::new(None),
+        }
+    }
+
+    // Draws `text` as `draw_text` would at `origin` and returns its width.
+    pub fn draw_label(
+        &self,
+        renderer: &Renderer,
+        text: &str,
+        style: &TextStyle,
+        origin: &Point,
+    ) -> Result<i16> {
+        let outline = renderer.palette().text_outline;
+        let mut label = self.label.borrow_mut();
+        let stale = label
+            .as_ref()
+            .is_none_or(|label| label.text != text || label.outline != outline);
+        if stale {
+            let (image, origin) = renderer.prerender_text(text, style)?;
+            *label = Some(Label {
+                text: text.to_string(),
+                outline,
+                image,
+                origin,
+            });
+        }
+        let Some(label) = label.as_ref() else {
+            return Ok(0);
+        };
+        renderer.draw_entire_image(
+            &label.image,
+            &Point {
+                x: origin.x - label.origin.x,
+                y: origin.y - label.origin.y,
+            },
+            &DrawOptions::default(),
+        );
+        Ok(label.image.width() as i16 - 2 * label.origin.x)
+    }
+
+    pub fn height(&self) -> i16 {
+        self.height
+    }
+
+    pub fn measure(&self, value: u32) -> i16 {
+        (value.checked_ilog10().unwrap_or(0) as i16 + 1) * self.digit_width
+    }
+
+    // Draws `value` with the top right of its last digit at `top_right`.
+    pub fn draw(&self, renderer: &Renderer, value: u32, top_right: &Point) {
+        let mut value = value;
+        let mut x = top_right.x;
+        loop {
+            x -= self.digit_width;
+            renderer.draw_image(
+                &self.image,
+                &Rect {
+                    x: (value % 10) as i16 * self.digit_width,
+                    y: 0,
+                    width: self.digit_width,
+                    height: self.height,
+                },
+                &Rect {
+                    x,
+                    y: top_right.y,
+                    width: self.digit_width,
+                    height: self.height,
+                },
+                &DrawOptions::default(),
+            );
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+    }
+}
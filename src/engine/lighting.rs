@@ -0,0 +1,76 @@
+use crate::{
+    browser,
+    engine::{style::Color, Point, Rect, Renderer},
+};
+use anyhow::Result;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+#[derive(Clone, Copy)]
+pub struct Light {
+    pub center: Point,
+    pub radius: i16,
+    pub intensity: f32,
+}
+
+// Darkness is painted into its own canvas and the lights are erased out of
+// it with `destination-out`, so halos reveal the scene instead of adding
+// glow on top of it.
+pub struct Lighting {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+}
+
+impl Lighting {
+    pub fn new() -> Result<Self> {
+        let canvas = browser::create_canvas(1, 1)?;
+        let context = browser::context_2d(&canvas)?;
+        Ok(Lighting { canvas, context })
+    }
+
+    pub fn draw(&self, renderer: &Renderer, area: &Rect, darkness: Color, lights: &[Light]) {
+        if darkness.a <= 0.0 {
+            return;
+        }
+        let width = area.width.max(1) as u32;
+        let height = area.height.max(1) as u32;
+        if self.canvas.width() != width || self.canvas.height() != height {
+            self.canvas.set_width(width);
+            self.canvas.set_height(height);
+        }
+        self.context
+            .clear_rect(0.0, 0.0, width.into(), height.into());
+        self.context.set_fill_style(&darkness.to_js());
+        self.context
+            .fill_rect(0.0, 0.0, width.into(), height.into());
+        self.context.save();
+        self.context
+            .set_global_composite_operation("destination-out")
+            .expect("Composite operation is throwing exceptions!");
+        for light in lights {
+            let x = f64::from(light.center.x - area.x);
+            let y = f64::from(light.center.y - area.y);
+            let radius = f64::from(light.radius);
+            let gradient = self
+                .context
+                .create_radial_gradient(x, y, 0.0, x, y, radius)
+                .expect("Gradient is throwing exceptions!");
+            gradient
+                .add_color_stop(0.0, &Color::BLACK.with_alpha(light.intensity).to_css())
+                .expect("Gradient is throwing exceptions!");
+            gradient
+                .add_color_stop(1.0, &Color::BLACK.with_alpha(0.0).to_css())
+                .expect("Gradient is throwing exceptions!");
+            self.context.set_fill_style(&gradient);
+            self.context
+                .fill_rect(x - radius, y - radius, radius * 2.0, radius * 2.0);
+        }
+        self.context.restore();
+        renderer.draw_canvas(
+            &self.canvas,
+            &Point {
+                x: area.x,
+                y: area.y,
+            },
+        );
+    }
+}
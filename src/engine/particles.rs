@@ -0,0 +1,110 @@
+use crate::engine::{rng::Rng, style::Color, Point, Rect, Renderer};
+
+#[derive(Clone, Copy)]
+pub struct Burst {
+    pub count: u8,
+    pub color: Color,
+    pub fade_to: Color,
+    pub size: i16,
+    pub lifetime: u8,
+    pub spread: f32,
+    pub lift: f32,
+    pub gravity: f32,
+}
+
+struct Particle {
+    x: f32,
+    y: f32,
+    velocity_x: f32,
+    velocity_y: f32,
+    gravity: f32,
+    size: i16,
+    color: Color,
+    fade_to: Color,
+    age: u8,
+    lifetime: u8,
+}
+
+impl Particle {
+    fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    fn update(&mut self) {
+        self.velocity_y += self.gravity;
+        self.x += self.velocity_x;
+        self.y += self.velocity_y;
+        self.age += 1;
+    }
+
+    fn current_color(&self) -> Color {
+        let progress = f32::from(self.age) / f32::from(self.lifetime);
+        self.color
+            .lerp(self.fade_to, progress)
+            .with_alpha(1.0 - progress)
+    }
+}
+
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    rng: Rng,
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        ParticleSystem {
+            particles: vec![],
+            rng: Rng::new(0x2545_F491),
+        }
+    }
+
+    pub fn spawn(&mut self, origin: Point, burst: &Burst) {
+        for _ in 0..burst.count {
+            let velocity_x = (self.rng.unit() * 2.0 - 1.0) * burst.spread;
+            let velocity_y = -self.rng.unit() * burst.lift;
+            self.particles.push(Particle {
+                x: f32::from(origin.x),
+                y: f32::from(origin.y),
+                velocity_x,
+                velocity_y,
+                gravity: burst.gravity,
+                size: burst.size,
+                color: burst.color,
+                fade_to: burst.fade_to,
+                age: 0,
+                lifetime: burst.lifetime,
+            });
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.particles.iter_mut().for_each(Particle::update);
+        self.particles.retain(Particle::is_alive);
+    }
+
+    pub fn move_horizontally(&mut self, distance: i16) {
+        self.particles
+            .iter_mut()
+            .for_each(|particle| particle.x += f32::from(distance));
+    }
+
+    pub fn draw(&self, renderer: &Renderer) {
+        self.particles.iter().for_each(|particle| {
+            renderer.fill_rect(
+                &Rect {
+                    x: particle.x as i16 - particle.size / 2,
+                    y: particle.y as i16 - particle.size / 2,
+                    width: particle.size,
+                    height: particle.size,
+                },
+                particle.current_color(),
+            );
+        });
+    }
+}
@@ -0,0 +1,61 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+
+// `navigator.deviceMemory` is rounded to a power of two and capped at 8, so
+// this catches the phones with 2GB or less.
+const LOW_MEMORY_GB: f64 = 2.0;
+const PROBE_SIZE: u32 = 256;
+const PROBE_DRAWS: u32 = 100;
+// Well over what a device that keeps up at 60fps needs for the draws above.
+const SLOW_PROBE_MS: f64 = 12.0;
+
+// Whether the device is likely to struggle with full-size sprite sheets:
+// it reports little memory, or copying a canvas around a hundred times
+// takes long enough to notice in the first frames.
+pub fn is_low_end() -> bool {
+    if browser::device_memory().is_some_and(|memory| memory <= LOW_MEMORY_GB) {
+        log!("Low device memory, downscaling large sheets");
+        return true;
+    }
+    match time_draws() {
+        Ok(elapsed) if elapsed > SLOW_PROBE_MS => {
+            log!(
+                "Slow canvas ({:.1}ms probe), downscaling large sheets",
+                elapsed
+            );
+            true
+        }
+        Ok(_) => false,
+        Err(err) => {
+            warn!("Could not probe drawing speed: {:#}", err);
+            false
+        }
+    }
+}
+
+fn time_draws() -> Result<f64> {
+    let source = browser::create_canvas(PROBE_SIZE, PROBE_SIZE)?;
+    let target = browser::create_canvas(PROBE_SIZE, PROBE_SIZE)?;
+    let source_context = browser::context_2d(&source)?;
+    let context = browser::context_2d(&target)?;
+    let size = f64::from(PROBE_SIZE);
+    source_context.fill_rect(0.0, 0.0, size, size);
+    let start = browser::now()?;
+    for draw in 0..PROBE_DRAWS {
+        let offset = f64::from(draw % 8);
+        context
+            .draw_image_with_html_canvas_element_and_dw_and_dh(
+                &source,
+                offset,
+                offset,
+                size / 2.0,
+                size / 2.0,
+            )
+            .map_err(|err| anyhow!("Could not draw the probe {:#?}", err))?;
+    }
+    // Reading a pixel back waits for the draws to actually happen.
+    context
+        .get_image_data(0.0, 0.0, 1.0, 1.0)
+        .map_err(|err| anyhow!("Could not read the probe {:#?}", err))?;
+    Ok(browser::now()? - start)
+}
@@ -0,0 +1,108 @@
+use super::{Decoded, Rect, Source, Texture};
+use crate::browser;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use wasm_bindgen::Clamped;
+use web_sys::ImageData;
+
+fn default_tolerance() -> f32 {
+    12.0
+}
+
+// Turns every pixel whose hue is within `tolerance` degrees of `from` to the
+// hue `to`, keeping its saturation and lightness so the shading survives.
+#[derive(Clone, Copy, Deserialize)]
+pub struct HueSwap {
+    pub from: f32,
+    pub to: f32,
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f32,
+}
+
+impl HueSwap {
+    // Returns whether the pixel matched.
+    fn apply(&self, pixel: &mut [u8]) -> bool {
+        let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|channel| f32::from(channel) / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        if chroma == 0.0 {
+            return false;
+        }
+        let hue = 60.0
+            * if max == r {
+                ((g - b) / chroma).rem_euclid(6.0)
+            } else if max == g {
+                (b - r) / chroma + 2.0
+            } else {
+                (r - g) / chroma + 4.0
+            };
+        let distance = (hue - self.from).rem_euclid(360.0);
+        if distance.min(360.0 - distance) > self.tolerance {
+            return false;
+        }
+        // Same chroma and lightness, so the same saturation, at the new hue.
+        let sector = self.to.rem_euclid(360.0) / 60.0;
+        let second = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match sector as u8 {
+            0 => (chroma, second, 0.0),
+            1 => (second, chroma, 0.0),
+            2 => (0.0, chroma, second),
+            3 => (0.0, second, chroma),
+            4 => (second, 0.0, chroma),
+            _ => (chroma, 0.0, second),
+        };
+        let offset = (max + min - chroma) / 2.0;
+        for (channel, value) in pixel.iter_mut().zip([r, g, b]) {
+            *channel = ((value + offset) * 255.0).round().clamp(0.0, 255.0) as u8;
+        }
+        true
+    }
+}
+
+// Copies `texture` with `swaps` applied, pixel by pixel, on a canvas of its
+// own. Meant for load time or a menu, not every frame.
+pub fn recolor(texture: &Texture, name: &str, swaps: &[HueSwap]) -> Result<Texture> {
+    let (width, height) = texture.decoded_size();
+    let canvas = browser::create_canvas(width, height)?;
+    let context = browser::context_2d(&canvas)?;
+    Source::from(texture).draw(
+        &context,
+        &texture.frame(),
+        0.0,
+        0.0,
+        &Rect {
+            x: 0,
+            y: 0,
+            width: width as i16,
+            height: height as i16,
+        },
+    );
+    let mut pixels = context
+        .get_image_data(0.0, 0.0, width.into(), height.into())
+        .map_err(|err| {
+            anyhow!(
+                "Could not read the pixels of {}: {:#?}",
+                texture.source,
+                err
+            )
+        })?
+        .data();
+    for pixel in pixels.chunks_exact_mut(4).filter(|pixel| pixel[3] > 0) {
+        for swap in swaps {
+            if swap.apply(pixel) {
+                break;
+            }
+        }
+    }
+    let recolored = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), width, height)
+        .map_err(|err| anyhow!("Could not build the pixels of {}: {:#?}", name, err))?;
+    context
+        .put_image_data(&recolored, 0.0, 0.0)
+        .map_err(|err| anyhow!("Could not write the pixels of {}: {:#?}", name, err))?;
+    Ok(Texture {
+        source: format!("{}#{}", texture.source, name),
+        decoded: Decoded::Canvas(canvas),
+        scale: texture.scale,
+    })
+}
@@ -0,0 +1,31 @@
+// xorshift32: cheap and deterministic, so the same seed always produces the
+// same sequence on every platform.
+#[derive(Clone, Copy)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Rng {
+            state: if seed == 0 { 0x2545_F491 } else { seed },
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    pub fn unit(&mut self) -> f32 {
+        (self.next_u32() % 1000) as f32 / 1000.0
+    }
+
+    // Uniform in `min..max`.
+    pub fn range(&mut self, min: i16, max: i16) -> i16 {
+        let span = (i32::from(max) - i32::from(min)).max(1) as u32;
+        (i32::from(min) + (self.next_u32() % span) as i32) as i16
+    }
+}
@@ -0,0 +1,137 @@
+use crate::engine::{Camera, DrawOptions, Image, Rect, Renderer};
+use std::any::Any;
+
+// Lets the scene hand back an entity as its concrete type.
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Render layers, back to front. All but the HUD are drawn through the
+// camera.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Background,
+    Terrain,
+    Entities,
+    Foreground,
+    Hud,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 5] = [
+        Layer::Background,
+        Layer::Terrain,
+        Layer::Entities,
+        Layer::Foreground,
+        Layer::Hud,
+    ];
+
+    pub fn in_world(self) -> bool {
+        self != Layer::Hud
+    }
+}
+
+pub trait Entity: AsAny {
+    fn update(&mut self, _dt: f64) {}
+    fn draw(&self, renderer: &Renderer, options: &DrawOptions);
+    fn bounding_boxes(&self) -> Vec<Rect>;
+    fn move_horizontally(&mut self, distance: i16);
+
+    fn layer(&self) -> Layer {
+        Layer::Entities
+    }
+
+    // Within a layer, higher z draws later, on top.
+    fn z_order(&self) -> i16 {
+        0
+    }
+
+    // Entities at the same z with the same key are drawn back to back, so the
+    // canvas keeps reading from one image. Usually the image's URL.
+    fn batch_key(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Entity for Image {
+    fn draw(&self, renderer: &Renderer, options: &DrawOptions) {
+        Image::draw(self, renderer, options);
+    }
+
+    fn bounding_boxes(&self) -> Vec<Rect> {
+        vec![*self.bounding_box()]
+    }
+
+    fn move_horizontally(&mut self, distance: i16) {
+        Image::move_horizontally(self, distance);
+    }
+
+    fn batch_key(&self) -> Option<String> {
+        Some(self.source().to_string())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EntityId(u32);
+
+// Everything in the world that scrolls and draws the same way.
+// Entities keep the order they were added in, within a z layer and batch.
+#[derive(Default)]
+pub struct Scene {
+    next_id: u32,
+    entities: Vec<(EntityId, Box<dyn Entity>)>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Scene::default()
+    }
+
+    pub fn add(&mut self, entity: impl Entity) -> EntityId {
+        let id = EntityId(self.next_id);
+        self.next_id += 1;
+        self.entities.push((id, Box::new(entity)));
+        self.entities.sort_by_cached_key(|(_, entity)| {
+            (entity.layer(), entity.z_order(), entity.batch_key())
+        });
+        id
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.entities
+            .iter_mut()
+            .for_each(|(_, entity)| entity.update(dt));
+    }
+
+    pub fn move_horizontally(&mut self, distance: i16) {
+        self.entities
+            .iter_mut()
+            .for_each(|(_, entity)| entity.move_horizontally(distance));
+    }
+
+    // Draws one layer, skipping entities with nothing inside the camera's
+    // view.
+    pub fn draw(&self, renderer: &Renderer, camera: &Camera, layer: Layer, options: &DrawOptions) {
+        self.entities
+            .iter()
+            .filter(|(_, entity)| entity.layer() == layer)
+            .filter(|(_, entity)| {
+                entity
+                    .bounding_boxes()
+                    .iter()
+                    .any(|bounding_box| camera.is_visible(bounding_box))
+            })
+            .for_each(|(_, entity)| entity.draw(renderer, options));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+use web_sys::{AudioBuffer, AudioBufferSourceNode, AudioContext, AudioContextState, GainNode};
+
+// Scheduled a little ahead so layers started together begin on the same
+// sample.
+const START_DELAY: f64 = 0.05;
+
+#[derive(Clone)]
+pub struct Sound {
+    buffer: AudioBuffer,
+}
+
+// The game's one AudioContext, with a master gain every sound plays through.
+// Clones share both.
+#[derive(Clone)]
+pub struct Mixer {
+    context: AudioContext,
+    master: GainNode,
+}
+
+impl Mixer {
+    pub fn new() -> Result<Self> {
+        let context = browser::create_audio_context()?;
+        let master = gain_node(&context, 1.0)?;
+        master
+            .connect_with_audio_node(&context.destination())
+            .map_err(|err| anyhow!("Could not connect the master gain {:#?}", err))?;
+        Ok(Mixer { context, master })
+    }
+
+    pub async fn decode(&self, data: &js_sys::ArrayBuffer) -> Result<Sound> {
+        let buffer = browser::decode_audio_data(&self.context, data).await?;
+        Ok(Sound { buffer })
+    }
+
+    // Browsers start the context suspended until the page has been
+    // interacted with, so this is called again once a run starts.
+    pub fn resume(&self) {
+        if self.context.state() == AudioContextState::Suspended {
+            if let Err(err) = self.context.resume() {
+                warn!("Could not resume audio {:#?}", err);
+            }
+        }
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.master.gain().set_value(volume);
+    }
+
+    pub fn play(&self, sound: &Sound) -> Result<()> {
+        let source = self.source(sound, &self.master)?;
+        source
+            .start()
+            .map_err(|err| anyhow!("Could not play sound {:#?}", err))
+    }
+
+    // Loops every sound in `layers` from the same moment, each through its
+    // own gain starting at the paired value.
+    pub fn loop_layers(&self, layers: &[(&Sound, f32)]) -> Result<Vec<Layer>> {
+        let when = self.context.current_time() + START_DELAY;
+        layers
+            .iter()
+            .map(|(sound, gain)| {
+                let gain = gain_node(&self.context, *gain)?;
+                gain.connect_with_audio_node(&self.master)
+                    .map_err(|err| anyhow!("Could not connect a layer {:#?}", err))?;
+                let source = self.source(sound, &gain)?;
+                source.set_loop(true);
+                source
+                    .start_with_when(when)
+                    .map_err(|err| anyhow!("Could not start a layer {:#?}", err))?;
+                Ok(Layer {
+                    context: self.context.clone(),
+                    gain,
+                })
+            })
+            .collect()
+    }
+
+    fn source(&self, sound: &Sound, output: &GainNode) -> Result<AudioBufferSourceNode> {
+        let source = self
+            .context
+            .create_buffer_source()
+            .map_err(|err| anyhow!("Could not create a buffer source {:#?}", err))?;
+        source.set_buffer(Some(&sound.buffer));
+        source
+            .connect_with_audio_node(output)
+            .map_err(|err| anyhow!("Could not connect a buffer source {:#?}", err))?;
+        Ok(source)
+    }
+}
+
+// One looping track of layered music, mixed in through its own gain.
+pub struct Layer {
+    context: AudioContext,
+    gain: GainNode,
+}
+
+impl Layer {
+    // Moves the gain to `gain` over `seconds` on the audio clock, so the
+    // change stays smooth however often it is called.
+    pub fn ramp_to(&self, gain: f32, seconds: f64) {
+        let now = self.context.current_time();
+        let param = self.gain.gain();
+        let result = param
+            .cancel_scheduled_values(now)
+            .and_then(|param| param.set_value_at_time(param.value(), now))
+            .and_then(|param| param.linear_ramp_to_value_at_time(gain, now + seconds));
+        if let Err(err) = result {
+            warn!("Could not ramp a music layer {:#?}", err);
+        }
+    }
+}
+
+fn gain_node(context: &AudioContext, gain: f32) -> Result<GainNode> {
+    let node = context
+        .create_gain()
+        .map_err(|err| anyhow!("Could not create a gain node {:#?}", err))?;
+    node.gain().set_value(gain);
+    Ok(node)
+}
@@ -0,0 +1,109 @@
+// The enum half of a typestate machine. Each state is a type `S` and the
+// machine holds a `$state<S>`; this generates the enum with one variant per
+// state, a `From` impl for each, a fieldless kind enum with the same variant
+// names plus a `kind()` getter, and methods that forward to whichever state is
+// current. Transitions stay hand-written on the machine.
+//
+//     state_machine! {
+//         #[derive(Copy, Clone)]
+//         enum DogStateMachine(DogState) {
+//             kind: #[derive(Clone, Copy, PartialEq, Eq)] DogKind,
+//             states: [Sitting, Running],
+//             forward: {
+//                 fn frame_name(&self) -> &str;
+//             }
+//         }
+//     }
+macro_rules! state_machine {
+    (
+        $(#[$machine_meta:meta])*
+        $vis:vis enum $machine:ident($state:ident) {
+            kind: $(#[$kind_meta:meta])* $kind:ident,
+            states: [$($variant:ident),+ $(,)?],
+            forward: { $($forward:tt)* }
+        }
+    ) => {
+        $(#[$machine_meta])*
+        $vis enum $machine {
+            $($variant($state<$variant>)),+
+        }
+
+        $(#[$kind_meta])*
+        $vis enum $kind {
+            $($variant),+
+        }
+
+        $(
+            impl From<$state<$variant>> for $machine {
+                fn from(state: $state<$variant>) -> Self {
+                    $machine::$variant(state)
+                }
+            }
+        )+
+
+        impl $machine {
+            fn kind(&self) -> $kind {
+                match self {
+                    $($machine::$variant(_) => $kind::$variant),+
+                }
+            }
+
+            $crate::engine::state_machine::state_machine!(
+                @forward $machine [$($variant),+] $($forward)*
+            );
+        }
+    };
+    (@forward $machine:ident $variants:tt) => {};
+    (
+        @forward $machine:ident $variants:tt
+        fn $method:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;
+        $($rest:tt)*
+    ) => {
+        #[allow(clippy::useless_conversion)]
+        fn $method(&self $(, $arg: $ty)*) -> $ret {
+            $crate::engine::state_machine::state_machine!(
+                @match self, $machine $variants $method($($arg),*)
+            )
+        }
+
+        $crate::engine::state_machine::state_machine!(@forward $machine $variants $($rest)*);
+    };
+    (
+        @forward $machine:ident $variants:tt
+        fn $method:ident(self $(, $arg:ident: $ty:ty)*) -> $ret:ty;
+        $($rest:tt)*
+    ) => {
+        #[allow(clippy::useless_conversion)]
+        fn $method(self $(, $arg: $ty)*) -> $ret {
+            $crate::engine::state_machine::state_machine!(
+                @match self, $machine $variants $method($($arg),*)
+            )
+        }
+
+        $crate::engine::state_machine::state_machine!(@forward $machine $variants $($rest)*);
+    };
+    (@match $value:expr, $machine:ident [$($variant:ident),+] $method:ident $args:tt) => {
+        match $value {
+            $($machine::$variant(state) => state.$method $args.into()),+
+        }
+    };
+}
+
+// `From` impls that fold a state's update result (an enum of the states it
+// can end up in) back into the machine.
+macro_rules! end_states {
+    ($machine:ident { $($end:ident { $($variant:ident),+ $(,)? }),+ $(,)? }) => {
+        $(
+            impl From<$end> for $machine {
+                fn from(end_state: $end) -> Self {
+                    match end_state {
+                        $($end::$variant(state) => state.into()),+
+                    }
+                }
+            }
+        )+
+    };
+}
+
+pub(crate) use end_states;
+pub(crate) use state_machine;
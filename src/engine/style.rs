@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use wasm_bindgen::JsValue;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(0xFF, 0xFF, 0xFF);
+    pub const RED: Color = Color::rgb(0xFF, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: f32) -> Self {
+        Color { r, g, b, a }
+    }
+
+    pub fn with_alpha(self, a: f32) -> Self {
+        Color { a, ..self }
+    }
+
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |from: u8, to: u8| {
+            (f32::from(from) + (f32::from(to) - f32::from(from)) * t).round() as u8
+        };
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+
+    pub fn to_css(self) -> String {
+        format!("rgba({}, {}, {}, {})", self.r, self.g, self.b, self.a)
+    }
+
+    pub(crate) fn to_js(self) -> JsValue {
+        JsValue::from_str(&self.to_css())
+    }
+}
+
+// Accepts "#RGB", "#RRGGBB" and "#RRGGBBAA".
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    fn from_str(hex: &str) -> Result<Self> {
+        let digits = hex
+            .strip_prefix('#')
+            .ok_or_else(|| anyhow!("Color {} does not start with '#'", hex))?;
+        let parse = |digits: &str| {
+            u8::from_str_radix(digits, 16).map_err(|err| anyhow!("Invalid color {}: {}", hex, err))
+        };
+        match digits.len() {
+            3 => {
+                let expand = |index: usize| parse(&digits[index..=index].repeat(2));
+                Ok(Color::rgb(expand(0)?, expand(1)?, expand(2)?))
+            }
+            6 | 8 => {
+                let alpha = if digits.len() == 8 {
+                    f32::from(parse(&digits[6..8])?) / 255.0
+                } else {
+                    1.0
+                };
+                Ok(Color::rgba(
+                    parse(&digits[0..2])?,
+                    parse(&digits[2..4])?,
+                    parse(&digits[4..6])?,
+                    alpha,
+                ))
+            }
+            _ => Err(anyhow!("Color {} must have 3, 6 or 8 hex digits", hex)),
+        }
+    }
+}
+
+// Colors for things that have to read whatever the player's color vision:
+// hitbox outlines, HUD text and warning flashes.
+#[derive(Clone, Copy)]
+pub struct Palette {
+    pub hitbox: Color,
+    // Drawn around HUD text for extra contrast against the world.
+    pub text_outline: Option<Color>,
+    pub warning: Color,
+}
+
+impl Palette {
+    pub const STANDARD: Palette = Palette {
+        hitbox: Color::RED,
+        text_outline: None,
+        warning: Color::RED,
+    };
+
+    // Okabe-Ito orange and blue, which stay apart under the common forms of
+    // color blindness.
+    pub const COLORBLIND: Palette = Palette {
+        hitbox: Color::rgb(0xE6, 0x9F, 0x00),
+        text_outline: Some(Color::WHITE),
+        warning: Color::rgb(0x00, 0x72, 0xB2),
+    };
+}
+
+#[derive(Clone, Copy)]
+pub struct StrokeStyle {
+    pub color: Color,
+    pub width: f64,
+}
+
+#[derive(Clone, Copy)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            TextAlign::Left => "left",
+            TextAlign::Center => "center",
+            TextAlign::Right => "right",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TextStyle {
+    pub font: &'static str,
+    pub color: Color,
+    pub align: TextAlign,
+}
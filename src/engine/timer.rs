@@ -0,0 +1,81 @@
+// Counts down a number of updates. A timer that has run out stays done, and
+// the default timer is already done.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct Timer {
+    remaining: u16,
+}
+
+impl Timer {
+    pub const fn after(ticks: u16) -> Self {
+        Timer { remaining: ticks }
+    }
+
+    // Returns whether the timer ran out on this tick.
+    pub fn tick(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        self.remaining == 0
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.remaining > 0
+    }
+
+    pub fn remaining(&self) -> u16 {
+        self.remaining
+    }
+
+    // Ticks gone by, for a timer started at `total`.
+    pub fn elapsed(&self, total: u16) -> u16 {
+        total.saturating_sub(self.remaining)
+    }
+
+    // How much of `total` is left, from 1 down to 0.
+    pub fn fraction_left(&self, total: u16) -> f32 {
+        f32::from(self.remaining) / f32::from(total.max(1))
+    }
+}
+
+// Hands back each id once its delay has passed, in the order they were
+// scheduled when several come due on the same tick.
+pub struct Scheduler<T> {
+    pending: Vec<(Timer, T)>,
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Scheduler<T> {
+    pub fn new() -> Self {
+        Scheduler { pending: vec![] }
+    }
+
+    pub fn after(&mut self, ticks: u16, id: T) {
+        self.pending.push((Timer::after(ticks), id));
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    // Call once per update.
+    pub fn tick(&mut self) -> Vec<T> {
+        let mut due = vec![];
+        let mut waiting = vec![];
+        for (mut timer, id) in self.pending.drain(..) {
+            timer.tick();
+            if !timer.is_running() {
+                due.push(id);
+            } else {
+                waiting.push((timer, id));
+            }
+        }
+        self.pending = waiting;
+        due
+    }
+}
@@ -0,0 +1,99 @@
+use super::timer::Timer;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    // Hits the end and bounces off it a few times, like a dropped ball.
+    Bounce,
+}
+
+impl Easing {
+    // Maps progress from 0 to 1 onto how far along the value is.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            Easing::Bounce => {
+                const N: f64 = 7.5625;
+                const D: f64 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let t = t - 1.5 / D;
+                    N * t * t + 0.75
+                } else if t < 2.5 / D {
+                    let t = t - 2.25 / D;
+                    N * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D;
+                    N * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+// A value moving from `from` to `to` over a number of updates. It holds at
+// `to` once done; the default tween sits at 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tween {
+    from: f64,
+    to: f64,
+    duration: u16,
+    timer: Timer,
+    easing: Easing,
+}
+
+impl Tween {
+    pub fn new(from: f64, to: f64, duration: u16, easing: Easing) -> Self {
+        Tween {
+            from,
+            to,
+            duration,
+            timer: Timer::after(duration),
+            easing,
+        }
+    }
+
+    // Sits at `value` without moving.
+    pub fn at(value: f64) -> Self {
+        Tween {
+            from: value,
+            to: value,
+            ..Tween::default()
+        }
+    }
+
+    // Starts again toward `to` from wherever the value is now, unless it is
+    // already heading there.
+    pub fn retarget(&mut self, to: f64, duration: u16, easing: Easing) {
+        if to != self.to {
+            *self = Tween::new(self.value(), to, duration, easing);
+        }
+    }
+
+    // Call once per update.
+    pub fn tick(&mut self) {
+        self.timer.tick();
+    }
+
+    pub fn value(&self) -> f64 {
+        if !self.timer.is_running() {
+            return self.to;
+        }
+        let t = 1.0 - f64::from(self.timer.fraction_left(self.duration));
+        self.from + (self.to - self.from) * self.easing.apply(t)
+    }
+
+    pub fn is_done(&self) -> bool {
+        !self.timer.is_running()
+    }
+}
@@ -0,0 +1,174 @@
+use crate::engine::{
+    style::{Color, TextAlign, TextStyle},
+    KeyState, Point, Rect, Renderer,
+};
+
+const HIGHLIGHT: Color = Color::rgb(0xDD, 0xDD, 0xDD);
+const TRACK: Color = Color::rgb(0xBB, 0xBB, 0xBB);
+const FILL: Color = Color::rgb(0x55, 0x55, 0x55);
+const KNOB_RADIUS: i16 = 8;
+
+enum Widget {
+    Label {
+        text: String,
+        position: Point,
+        style: TextStyle,
+    },
+    Button {
+        rect: Rect,
+        label: String,
+        style: TextStyle,
+        highlighted: bool,
+    },
+    Slider {
+        rect: Rect,
+        value: f32,
+    },
+}
+
+// Immediate-mode widgets for menus. Each update starts with `begin`, then the
+// screen declares its widgets in order and gets back what the mouse or a
+// touch did to each one this update; `draw` shows the widgets from the last
+// update. Widgets are identified by the order they were declared in.
+pub struct Ui {
+    pointer: Point,
+    down: bool,
+    pressed: bool,
+    // The slider being dragged, if the press started on one.
+    dragging: Option<usize>,
+    widgets: Vec<Widget>,
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Ui {
+            pointer: Point { x: 0, y: 0 },
+            down: true,
+            pressed: false,
+            dragging: None,
+            widgets: vec![],
+        }
+    }
+
+    pub fn begin(&mut self, keystate: &KeyState) {
+        let down = keystate.is_pointer_down();
+        self.pressed = down && !self.down;
+        self.down = down;
+        if !down {
+            self.dragging = None;
+        }
+        self.pointer = keystate.pointer();
+        self.widgets.clear();
+    }
+
+    pub fn label(&mut self, text: impl Into<String>, position: Point, style: TextStyle) {
+        self.widgets.push(Widget::Label {
+            text: text.into(),
+            position,
+            style,
+        });
+    }
+
+    // Returns whether the button was pressed this update. `selected` shows
+    // the same highlight as hovering, for keyboard focus.
+    pub fn button(
+        &mut self,
+        rect: Rect,
+        label: impl Into<String>,
+        style: TextStyle,
+        selected: bool,
+    ) -> bool {
+        let hovered = rect.contains(&self.pointer);
+        self.widgets.push(Widget::Button {
+            rect,
+            label: label.into(),
+            style,
+            highlighted: selected || hovered,
+        });
+        hovered && self.pressed
+    }
+
+    // Drags `value` between 0 and 1 along the track. Returns whether it
+    // changed.
+    pub fn slider(&mut self, rect: Rect, value: &mut f32) -> bool {
+        let index = self.widgets.len();
+        if self.pressed && rect.contains(&self.pointer) {
+            self.dragging = Some(index);
+        }
+        let before = *value;
+        if self.dragging == Some(index) && rect.width > 0 {
+            *value = (f32::from(self.pointer.x - rect.x) / f32::from(rect.width)).clamp(0.0, 1.0);
+        }
+        self.widgets.push(Widget::Slider {
+            rect,
+            value: *value,
+        });
+        *value != before
+    }
+
+    pub fn draw(&self, renderer: &Renderer) {
+        for widget in &self.widgets {
+            match widget {
+                Widget::Label {
+                    text,
+                    position,
+                    style,
+                } => renderer.draw_text(text, position, style),
+                Widget::Button {
+                    rect,
+                    label,
+                    style,
+                    highlighted,
+                } => {
+                    if *highlighted {
+                        renderer.fill_rect(rect, HIGHLIGHT);
+                    }
+                    let x = match style.align {
+                        TextAlign::Left => rect.x + 10,
+                        TextAlign::Center => rect.x + rect.width / 2,
+                        TextAlign::Right => rect.right() - 10,
+                    };
+                    renderer.draw_text(
+                        label,
+                        &Point {
+                            x,
+                            y: rect.y + rect.height * 2 / 3,
+                        },
+                        style,
+                    );
+                }
+                Widget::Slider { rect, value } => {
+                    let middle = rect.y + rect.height / 2;
+                    let track = Rect {
+                        y: middle - 2,
+                        height: 4,
+                        ..*rect
+                    };
+                    let filled = (f32::from(rect.width) * value) as i16;
+                    renderer.fill_rect(&track, TRACK);
+                    renderer.fill_rect(
+                        &Rect {
+                            width: filled,
+                            ..track
+                        },
+                        FILL,
+                    );
+                    renderer.fill_circle(
+                        &Point {
+                            x: rect.x + filled,
+                            y: middle,
+                        },
+                        KNOB_RADIUS,
+                        FILL,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,17 +1,229 @@
-use self::red_hat_boy_states::*;
+mod actions;
+mod audio;
+pub mod bench;
+mod bot;
+mod coins;
+mod combo;
+mod course;
+mod daily;
+mod editor;
+mod feedback;
+mod foliage;
+mod ghost;
+mod headless;
+mod history;
+mod hitboxes;
+mod hud;
+mod lifetime;
+mod milestones;
+mod minimap;
+mod obstacles;
+mod photo;
+mod power;
+mod race;
+mod run_stats;
+mod segments;
+mod settings;
+mod shop;
+mod signposts;
+pub mod simulation;
+mod stamina;
+mod telemetry;
+mod terrain;
+mod themes;
+mod tutorial;
+mod world;
+
+use self::{
+    actions::{Action, ActionState, Bindings, InputSource},
+    audio::{Cue, Cues, Music},
+    bench::HotPaths,
+    bot::{Bot, RESTART_FRAMES},
+    coins::Coins,
+    combo::{Combo, ComboEvent},
+    course::Course,
+    daily::{DailyBest, DailyMode},
+    editor::Editor,
+    feedback::Haptic,
+    foliage::Foliage,
+    ghost::{GhostTrace, Sample},
+    history::{RunHistory, MAX_RUNS},
+    hitboxes::{HitboxEditor, Hitboxes},
+    hud::{Anchor, HudLayout},
+    lifetime::LifetimeStats,
+    milestones::{Chime, FloatingTexts, MilestoneText, SpeedLines, MILESTONE_METERS},
+    obstacles::{Barrier, Cause, Hazard, Obstacle, Platform},
+    photo::{PhotoAction, PhotoMode},
+    power::BatteryWatch,
+    race::{Race, Track},
+    red_hat_boy_states::*,
+    run_stats::RunStats,
+    segments::SegmentLibrary,
+    settings::{MenuAction, Settings, SettingsMenu},
+    shop::{Shop, ShopMenu},
+    signposts::{SignpostConfig, Signposts},
+    stamina::Stamina,
+    telemetry::{GameEvent, Telemetry},
+    terrain::Terrain,
+    themes::Themes,
+    tutorial::Tutorial,
+    world::{Watch, World, WorldEvent},
+};
 use crate::{
-    browser,
-    engine::{self, Cell, Game, KeyState, Point, Rect, Renderer, Sheet},
+    browser::{self, logging, offline::OfflineCache, LaunchOptions, RetryPolicy},
+    engine::{
+        self,
+        assets::{AssetStore, Scope},
+        capture::{self, Recorder},
+        chart::Chart,
+        fixed::Fixed,
+        font::DigitStrip,
+        lighting::{Light, Lighting},
+        particles::{Burst, ParticleSystem},
+        rng::Rng,
+        scene::{Layer, Scene},
+        sound::Mixer,
+        state_machine::{end_states, state_machine},
+        style::{Color, TextAlign, TextStyle},
+        timer::{Scheduler, Timer},
+        tween::{Easing, Tween},
+        ui::Ui,
+        Camera, Cell, DrawOptions, Game, KeyState, Loader, Mirror, Point, Rect, Renderer, Sheet,
+        SourceSize, Texture, TimeScale, Transform, FIXED_DT,
+    },
+    i18n::Locale,
+    net::{peer::PeerStatus, Leaderboard, LeaderboardState},
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use web_sys::HtmlImageElement;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+const WIDTH: i16 = engine::LOGICAL_WIDTH;
+const HEIGHT: i16 = engine::LOGICAL_HEIGHT;
+
+#[derive(Clone, Copy, Debug)]
+pub struct AirControl {
+    pub acceleration: Fixed,
+    pub min_speed: i16,
+    pub max_speed: i16,
+}
+
+// The boy's top running speed grows by one every `meters_per_step` meters,
+// from `base` up to `max`.
+#[derive(Clone, Copy)]
+pub struct SpeedConfig {
+    pub base: i16,
+    pub meters_per_step: i32,
+    pub max: i16,
+}
+
+impl SpeedConfig {
+    // With both speeds multiplied by `factor`, as `?speed=` asks.
+    fn scaled(self, factor: f32) -> Self {
+        let scale = |speed: i16| ((f32::from(speed) * factor).round() as i16).max(1);
+        SpeedConfig {
+            base: scale(self.base),
+            max: scale(self.max),
+            ..self
+        }
+    }
+
+    fn top_speed(&self, meters: i32) -> i16 {
+        let steps = (meters / self.meters_per_step).clamp(0, i32::from(self.max - self.base));
+        self.base + steps as i16
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct CameraConfig {
+    pub base_speed: i16,
+    pub zoom_per_speed: f64,
+    pub min_zoom: f64,
+    pub max_zoom: f64,
+    pub zoom_ticks: u16,
+}
+
+#[derive(Clone, Copy)]
+pub struct LightingConfig {
+    pub cycle_meters: i32,
+    pub night: Color,
+    pub halo_radius: i16,
+}
+
+#[derive(Clone, Copy)]
+pub struct CheckpointConfig {
+    pub every: i32,
+    pub continue_cost: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct GameConfig {
+    pub air_control: AirControl,
+    pub speed: SpeedConfig,
+    pub camera: CameraConfig,
+    pub signposts: SignpostConfig,
+    pub lighting: LightingConfig,
+    pub checkpoints: CheckpointConfig,
+    pub lives: u8,
+    pub leaderboard_endpoint: &'static str,
+    pub race_endpoint: &'static str,
+    // STUN or TURN servers for connecting race peers.
+    pub ice_servers: &'static [&'static str],
+    pub service_worker: &'static str,
+    pub telemetry_endpoint: Option<&'static str>,
+}
 
-const HEIGHT: i16 = 600;
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            air_control: AirControl {
+                acceleration: Fixed::from_ratio(1, 4),
+                min_speed: 2,
+                max_speed: 6,
+            },
+            speed: SpeedConfig {
+                base: 4,
+                meters_per_step: 300,
+                max: 6,
+            },
+            camera: CameraConfig {
+                base_speed: 4,
+                zoom_per_speed: 0.03,
+                min_zoom: 0.9,
+                max_zoom: 1.05,
+                zoom_ticks: 40,
+            },
+            signposts: SignpostConfig {
+                every: 100,
+                messages: &["signpost.jump", "signpost.stones", "signpost.roll"],
+            },
+            lighting: LightingConfig {
+                cycle_meters: 1000,
+                night: Color::rgba(0x05, 0x08, 0x20, 0.7),
+                halo_radius: 140,
+            },
+            checkpoints: CheckpointConfig {
+                every: 200,
+                continue_cost: 10,
+            },
+            lives: 3,
+            leaderboard_endpoint: "/api/leaderboard",
+            race_endpoint: "/api/race",
+            ice_servers: &["stun:stun.l.google.com:19302"],
+            service_worker: "/sw.js",
+            telemetry_endpoint: None,
+        }
+    }
+}
 
 mod red_hat_boy_states {
-    use super::HEIGHT;
-    use crate::engine::Point;
+    use super::{AirControl, HEIGHT};
+    use crate::engine::{fixed::Fixed, timer::Timer, Point};
 
     const FLOOR: i16 = 479;
     const PLAYER_HEIGHT: i16 = HEIGHT - FLOOR;
@@ -21,6 +233,11 @@ mod red_hat_boy_states {
     const IDLE_FRAMES: u8 = 29;
     const RUNNING_FRAMES: u8 = 23;
     const RUNNING_SPEED: i16 = 4;
+    const START_SPEED: Fixed = Fixed::from_int(2);
+    const ACCELERATION: Fixed = Fixed::from_ratio(1, 16);
+    const SLIDE_DECELERATION: Fixed = Fixed::from_ratio(1, 32);
+    const MIN_SLIDE_SPEED: Fixed = Fixed::from_int(2);
+    const STUMBLE_SLOWDOWN: Fixed = Fixed::from_int(2);
     const SLIDING_FRAMES: u8 = 14;
     const SLIDING_FRAME_NAME: &str = "Slide";
     const JUMPING_FRAME_NAME: &str = "Jump";
@@ -30,6 +247,31 @@ mod red_hat_boy_states {
     const JUMP_SPEED: i16 = -25;
     const GRAVITY: i16 = 1;
     const TERMINAL_VELOCITY: i16 = 20;
+    const ROLL_WINDOW: u8 = 8;
+    const ROLLING_FRAMES: u8 = 14;
+    const FELL_OFF_FRAME_NAME: &str = "Hurt";
+    const FELL_OFF_FRAMES: u8 = 8 * 3 - 1;
+    const INVULNERABLE_FRAMES: u16 = 120;
+    const JUMP_BUFFER_TICKS: u16 = 6;
+    const COYOTE_TICKS: u8 = 6;
+    // The sheet has no dive cells, so a dive plays the jump cells, tilted
+    // forward when drawn.
+    const DIVING_FRAME_NAME: &str = "Jump";
+    const DIVING_FRAMES: u8 = 12 * 3 - 1;
+    const DIVE_SPEED: i16 = 18;
+    const DIVE_LANDING_LAG: u16 = 10;
+
+    // Every animation a state can ask for, as (frame name, frame count).
+    pub const ANIMATIONS: [(&str, u8); 8] = [
+        (IDLE_FRAME_NAME, IDLE_FRAMES),
+        (RUNNING_FRAME_NAME, RUNNING_FRAMES),
+        (SLIDING_FRAME_NAME, SLIDING_FRAMES),
+        (JUMPING_FRAME_NAME, JUMPING_FRAMES),
+        (SLIDING_FRAME_NAME, ROLLING_FRAMES),
+        (FALLING_FRAME_NAME, FALLING_FRAMES),
+        (FELL_OFF_FRAME_NAME, FELL_OFF_FRAMES),
+        (DIVING_FRAME_NAME, DIVING_FRAMES),
+    ];
 
     #[derive(Copy, Clone)]
     pub struct RedHatBoyState<S> {
@@ -42,8 +284,33 @@ mod red_hat_boy_states {
             &self.context
         }
 
-        fn update_context(&mut self, frames: u8) {
-            self.context = self.context.update(frames);
+        pub fn hurt(mut self) -> Self {
+            self.context = self.context.make_invulnerable().stumble();
+            self
+        }
+
+        // A spring throws the boy up from whatever he was doing.
+        pub fn bounce(self, velocity: i16) -> RedHatBoyState<Jumping> {
+            RedHatBoyState {
+                context: self.context.take_off(velocity),
+                _state: Jumping,
+            }
+        }
+
+        pub fn map_context(mut self, f: impl FnOnce(RedHatBoyContext) -> RedHatBoyContext) -> Self {
+            self.context = f(self.context);
+            self
+        }
+
+        // Remembers a jump pressed while it can't happen yet, so it still
+        // fires if the boy touches ground within JUMP_BUFFER_TICKS.
+        pub fn buffer_jump(mut self) -> Self {
+            self.context.jump_buffer = Timer::after(JUMP_BUFFER_TICKS);
+            self
+        }
+
+        fn update_context(&mut self, frames: u8, ground: i16) {
+            self.context = self.context.update(frames, ground);
         }
     }
 
@@ -52,10 +319,23 @@ mod red_hat_boy_states {
         pub frame: u8,
         pub position: Point,
         pub velocity: Point,
+        pub air_speed: Fixed,
+        pub roll_window: Option<u8>,
+        pub invulnerable: Timer,
+        pub jump_buffer: Timer,
+        pub airborne: u8,
+        // Ticks after a dive lands before the boy can jump again.
+        pub landing_lag: Timer,
+        pub speed: Fixed,
+        pub top_speed: Fixed,
     }
 
     impl RedHatBoyContext {
-        pub fn update(mut self, frame_count: u8) -> Self {
+        // `ground` is the terrain height under the boy this frame.
+        pub fn update(mut self, frame_count: u8, ground: i16) -> Self {
+            self.invulnerable.tick();
+            self.jump_buffer.tick();
+            self.landing_lag.tick();
             if self.velocity.y < TERMINAL_VELOCITY {
                 self.velocity.y += GRAVITY;
             }
@@ -65,8 +345,11 @@ mod red_hat_boy_states {
                 self.frame = 0;
             }
             self.position.y += self.velocity.y;
-            if self.position.y > FLOOR {
-                self.position.y = FLOOR;
+            if self.position.y >= ground - PLAYER_HEIGHT {
+                self.position.y = ground - PLAYER_HEIGHT;
+                self.airborne = 0;
+            } else {
+                self.airborne = self.airborne.saturating_add(1);
             }
             self
         }
@@ -77,7 +360,125 @@ mod red_hat_boy_states {
         }
 
         fn run_right(mut self) -> Self {
-            self.velocity.x = RUNNING_SPEED;
+            self.velocity.x = self.speed.round();
+            self
+        }
+
+        // Running speeds up to the current top speed; sliding bleeds speed
+        // off down to MIN_SLIDE_SPEED.
+        fn accelerate(mut self) -> Self {
+            self.speed = (self.speed + ACCELERATION).min(self.top_speed);
+            self.run_right()
+        }
+
+        fn decelerate(mut self) -> Self {
+            self.speed = (self.speed - SLIDE_DECELERATION)
+                .max(MIN_SLIDE_SPEED)
+                .min(self.top_speed);
+            self.run_right()
+        }
+
+        pub fn with_top_speed(mut self, top_speed: i16) -> Self {
+            self.top_speed = Fixed::from_int(top_speed);
+            self
+        }
+
+        // Moves the boy across the screen, for when he runs at a different
+        // speed than the world scrolls.
+        pub fn shift(mut self, distance: i16) -> Self {
+            self.position.x += distance;
+            self
+        }
+
+        fn take_off(self, velocity: i16) -> Self {
+            self.set_vertical_velocity(velocity)
+                .launch()
+                .reset_frame()
+                .clear_roll_window()
+                .clear_jump_buffer()
+        }
+
+        fn dive(mut self) -> Self {
+            self.velocity.y = self.velocity.y.max(DIVE_SPEED);
+            self
+        }
+
+        fn lag_landing(mut self) -> Self {
+            self.landing_lag = Timer::after(DIVE_LANDING_LAG);
+            self
+        }
+
+        fn launch(mut self) -> Self {
+            self.air_speed = Fixed::from_int(self.velocity.x);
+            self
+        }
+
+        fn steer(mut self, direction: i16, air_control: AirControl) -> Self {
+            self.air_speed =
+                (self.air_speed + air_control.acceleration * Fixed::from_int(direction)).clamp(
+                    Fixed::from_int(air_control.min_speed),
+                    Fixed::from_int(air_control.max_speed),
+                );
+            self.velocity.x = self.air_speed.round();
+            self
+        }
+
+        fn prime_roll(mut self) -> Self {
+            if self.roll_window.is_none() && self.velocity.y > 0 {
+                self.roll_window = Some(ROLL_WINDOW);
+            }
+            self
+        }
+
+        fn tick_roll_window(mut self) -> Self {
+            self.roll_window = self.roll_window.map(|ticks| ticks.saturating_sub(1));
+            self
+        }
+
+        fn clear_roll_window(mut self) -> Self {
+            self.roll_window = None;
+            self
+        }
+
+        fn clear_jump_buffer(mut self) -> Self {
+            self.jump_buffer = Timer::default();
+            self
+        }
+
+        // A hit knocks the pace out of him; he runs back up to speed.
+        fn stumble(mut self) -> Self {
+            self.speed = (self.speed - STUMBLE_SLOWDOWN).max(MIN_SLIDE_SPEED);
+            self
+        }
+
+        fn make_invulnerable(mut self) -> Self {
+            self.invulnerable = Timer::after(INVULNERABLE_FRAMES);
+            self
+        }
+
+        pub fn is_invulnerable(&self) -> bool {
+            self.invulnerable.is_running()
+        }
+
+        // Coyote time: running off an edge still allows a jump for a few
+        // ticks after the feet leave the ground.
+        fn can_jump(&self) -> bool {
+            self.airborne <= COYOTE_TICKS && !self.landing_lag.is_running()
+        }
+
+        fn jump_buffered(&self) -> bool {
+            self.jump_buffer.is_running()
+        }
+
+        fn roll_primed(&self) -> bool {
+            matches!(self.roll_window, Some(ticks) if ticks > 0)
+        }
+
+        // Pushes the boy `depth` pixels down out of a ceiling and stops him
+        // rising.
+        fn bump(mut self, depth: i16) -> Self {
+            self.position.y += depth;
+            self.velocity.y = self.velocity.y.max(0);
             self
         }
 
@@ -86,6 +487,11 @@ mod red_hat_boy_states {
             self
         }
 
+        fn stop_running(mut self) -> Self {
+            self.velocity.x = 0;
+            self
+        }
+
         fn stop(mut self) -> Self {
             self.velocity.x = 0;
             self.velocity.y = 0;
@@ -95,6 +501,7 @@ mod red_hat_boy_states {
         fn set_on(mut self, position: i16) -> Self {
             let position = position - PLAYER_HEIGHT;
             self.position.y = position;
+            self.airborne = 0;
             self
         }
     }
@@ -112,6 +519,14 @@ mod red_hat_boy_states {
                         y: FLOOR,
                     },
                     velocity: Point { x: 0, y: 0 },
+                    air_speed: Fixed::default(),
+                    roll_window: None,
+                    invulnerable: Timer::default(),
+                    jump_buffer: Timer::default(),
+                    airborne: 0,
+                    landing_lag: Timer::default(),
+                    speed: START_SPEED,
+                    top_speed: Fixed::from_int(RUNNING_SPEED),
                 },
                 _state: Idle {},
             }
@@ -127,8 +542,8 @@ mod red_hat_boy_states {
             IDLE_FRAME_NAME
         }
 
-        pub fn update(mut self) -> Self {
-            self.context = self.context.update(IDLE_FRAMES);
+        pub fn update(mut self, ground: i16) -> Self {
+            self.context = self.context.update(IDLE_FRAMES, ground);
             self
         }
     }
@@ -136,13 +551,27 @@ mod red_hat_boy_states {
     #[derive(Copy, Clone)]
     pub struct Running;
 
+    pub enum RunningEndState {
+        Running(RedHatBoyState<Running>),
+        Jumping(RedHatBoyState<Jumping>),
+    }
+
     impl RedHatBoyState<Running> {
         pub fn frame_name(&self) -> &str {
             RUNNING_FRAME_NAME
         }
-        pub fn update(mut self) -> Self {
-            self.update_context(RUNNING_FRAMES);
-            self
+        pub fn update(mut self, ground: i16) -> RunningEndState {
+            self.update_context(RUNNING_FRAMES, ground);
+            self.context = self.context.accelerate();
+            if self.context.jump_buffered() && self.context.airborne == 0 && self.can_jump() {
+                RunningEndState::Jumping(self.jump())
+            } else {
+                RunningEndState::Running(self)
+            }
+        }
+
+        pub fn can_jump(&self) -> bool {
+            self.context.can_jump()
         }
         pub fn slide(self) -> RedHatBoyState<Sliding> {
             RedHatBoyState {
@@ -152,7 +581,7 @@ mod red_hat_boy_states {
         }
         pub fn jump(self) -> RedHatBoyState<Jumping> {
             RedHatBoyState {
-                context: self.context.set_vertical_velocity(JUMP_SPEED).reset_frame(),
+                context: self.context.take_off(JUMP_SPEED),
                 _state: Jumping {},
             }
         }
@@ -170,6 +599,13 @@ mod red_hat_boy_states {
                 _state: Falling {},
             }
         }
+
+        pub fn fall(self) -> RedHatBoyState<FellOff> {
+            RedHatBoyState {
+                context: self.context.reset_frame().stop_running(),
+                _state: FellOff,
+            }
+        }
     }
 
     #[derive(Copy, Clone)]
@@ -184,8 +620,9 @@ mod red_hat_boy_states {
         pub fn frame_name(&self) -> &str {
             SLIDING_FRAME_NAME
         }
-        pub fn update(mut self) -> SlidingEndState {
-            self.update_context(SLIDING_FRAMES);
+        pub fn update(mut self, ground: i16) -> SlidingEndState {
+            self.update_context(SLIDING_FRAMES, ground);
+            self.context = self.context.decelerate();
             if self.context.frame >= SLIDING_FRAMES {
                 SlidingEndState::Complete(self.stand())
             } else {
@@ -200,6 +637,18 @@ mod red_hat_boy_states {
             }
         }
 
+        pub fn can_jump(&self) -> bool {
+            self.context.can_jump()
+        }
+
+        // Cuts the slide short.
+        pub fn jump(self) -> RedHatBoyState<Jumping> {
+            RedHatBoyState {
+                context: self.context.take_off(JUMP_SPEED),
+                _state: Jumping,
+            }
+        }
+
         pub fn land_on(self, position: i16) -> RedHatBoyState<Sliding> {
             RedHatBoyState {
                 context: self.context.set_on(position),
@@ -213,6 +662,13 @@ mod red_hat_boy_states {
                 _state: Falling {},
             }
         }
+
+        pub fn fall(self) -> RedHatBoyState<FellOff> {
+            RedHatBoyState {
+                context: self.context.reset_frame().stop_running(),
+                _state: FellOff,
+            }
+        }
     }
 
     #[derive(Copy, Clone)]
@@ -221,24 +677,142 @@ mod red_hat_boy_states {
     pub enum JumpingEndState {
         Jumping(RedHatBoyState<Jumping>),
         Landing(RedHatBoyState<Running>),
+        Rolling(RedHatBoyState<Rolling>),
     }
 
     impl RedHatBoyState<Jumping> {
         pub fn frame_name(&self) -> &str {
             JUMPING_FRAME_NAME
         }
-        pub fn update(mut self) -> JumpingEndState {
-            self.update_context(JUMPING_FRAMES);
-            if self.context.position.y >= FLOOR {
-                JumpingEndState::Landing(self.land_on(HEIGHT.into()))
+        pub fn update(mut self, ground: i16) -> JumpingEndState {
+            self.update_context(JUMPING_FRAMES, ground);
+            self.context = self.context.tick_roll_window();
+            if self.context.position.y >= ground - PLAYER_HEIGHT {
+                self.land_on(ground)
             } else {
                 JumpingEndState::Jumping(self)
             }
         }
-        pub fn land_on(self, position: i16) -> RedHatBoyState<Running> {
+
+        pub fn land_on(self, position: i16) -> JumpingEndState {
+            if self.context.jump_buffered() {
+                let landed: RedHatBoyState<Running> = RedHatBoyState {
+                    context: self.context.set_on(position).clear_roll_window(),
+                    _state: Running,
+                };
+                JumpingEndState::Jumping(landed.jump())
+            } else if self.context.roll_primed() {
+                JumpingEndState::Rolling(RedHatBoyState {
+                    context: self
+                        .context
+                        .reset_frame()
+                        .set_on(position)
+                        .clear_roll_window(),
+                    _state: Rolling,
+                })
+            } else {
+                JumpingEndState::Landing(RedHatBoyState {
+                    context: self
+                        .context
+                        .reset_frame()
+                        .set_on(position)
+                        .clear_roll_window()
+                        .run_right(),
+                    _state: Running,
+                })
+            }
+        }
+
+        pub fn bump_head(self, depth: i16) -> RedHatBoyState<Jumping> {
             RedHatBoyState {
-                context: self.context.reset_frame().set_on(position),
-                _state: Running,
+                context: self.context.bump(depth),
+                _state: Jumping,
+            }
+        }
+
+        // Down in the air. Pressed just before touchdown it still primes a
+        // roll.
+        pub fn dive(self) -> RedHatBoyState<Diving> {
+            RedHatBoyState {
+                context: self.context.prime_roll().dive().reset_frame(),
+                _state: Diving,
+            }
+        }
+
+        pub fn steer(self, direction: i16, air_control: AirControl) -> RedHatBoyState<Jumping> {
+            RedHatBoyState {
+                context: self.context.steer(direction, air_control),
+                _state: Jumping,
+            }
+        }
+
+        pub fn knock_out(self) -> RedHatBoyState<Falling> {
+            RedHatBoyState {
+                context: self.context.reset_frame().stop(),
+                _state: Falling {},
+            }
+        }
+
+        pub fn fall(self) -> RedHatBoyState<FellOff> {
+            RedHatBoyState {
+                context: self.context.reset_frame().stop_running(),
+                _state: FellOff,
+            }
+        }
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Diving;
+
+    pub enum DivingEndState {
+        Diving(RedHatBoyState<Diving>),
+        Sliding(RedHatBoyState<Sliding>),
+        Rolling(RedHatBoyState<Rolling>),
+    }
+
+    // A steep drop for placing a landing. It comes down in a slide, or a roll
+    // if it was started just above the ground, and can't jump for
+    // DIVE_LANDING_LAG ticks after.
+    impl RedHatBoyState<Diving> {
+        pub fn frame_name(&self) -> &str {
+            DIVING_FRAME_NAME
+        }
+
+        pub fn update(mut self, ground: i16) -> DivingEndState {
+            self.update_context(DIVING_FRAMES, ground);
+            self.context = self.context.tick_roll_window().dive();
+            if self.context.position.y >= ground - PLAYER_HEIGHT {
+                self.land_on(ground)
+            } else {
+                DivingEndState::Diving(self)
+            }
+        }
+
+        pub fn land_on(self, position: i16) -> DivingEndState {
+            let context = self
+                .context
+                .reset_frame()
+                .set_on(position)
+                .clear_roll_window()
+                .clear_jump_buffer()
+                .lag_landing();
+            if self.context.roll_primed() {
+                DivingEndState::Rolling(RedHatBoyState {
+                    context,
+                    _state: Rolling,
+                })
+            } else {
+                DivingEndState::Sliding(RedHatBoyState {
+                    context,
+                    _state: Sliding,
+                })
+            }
+        }
+
+        pub fn steer(self, direction: i16, air_control: AirControl) -> RedHatBoyState<Diving> {
+            RedHatBoyState {
+                context: self.context.steer(direction, air_control),
+                _state: Diving,
             }
         }
 
@@ -248,6 +822,57 @@ mod red_hat_boy_states {
                 _state: Falling {},
             }
         }
+
+        pub fn fall(self) -> RedHatBoyState<FellOff> {
+            RedHatBoyState {
+                context: self.context.reset_frame().stop_running(),
+                _state: FellOff,
+            }
+        }
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Rolling;
+
+    pub enum RollingEndState {
+        Complete(RedHatBoyState<Running>),
+        Rolling(RedHatBoyState<Rolling>),
+    }
+
+    impl RedHatBoyState<Rolling> {
+        pub fn frame_name(&self) -> &str {
+            SLIDING_FRAME_NAME
+        }
+
+        pub fn update(mut self, ground: i16) -> RollingEndState {
+            self.update_context(ROLLING_FRAMES, ground);
+            if self.context.frame >= ROLLING_FRAMES {
+                RollingEndState::Complete(self.stand())
+            } else {
+                RollingEndState::Rolling(self)
+            }
+        }
+
+        pub fn stand(self) -> RedHatBoyState<Running> {
+            RedHatBoyState {
+                context: self.context.reset_frame().run_right(),
+                _state: Running,
+            }
+        }
+
+        pub fn land_on(self, position: i16) -> RedHatBoyState<Rolling> {
+            RedHatBoyState {
+                context: self.context.set_on(position),
+                _state: Rolling,
+            }
+        }
+
+        pub fn fall(self) -> RedHatBoyState<FellOff> {
+            RedHatBoyState {
+                context: self.context.reset_frame().stop_running(),
+                _state: FellOff,
+            }
+        }
     }
 
     #[derive(Copy, Clone)]
@@ -270,8 +895,8 @@ mod red_hat_boy_states {
             }
         }
 
-        pub fn update(mut self) -> FallingEndState {
-            self.update_context(FALLING_FRAMES);
+        pub fn update(mut self, ground: i16) -> FallingEndState {
+            self.update_context(FALLING_FRAMES, ground);
             if self.context.frame >= FALLING_FRAMES {
                 FallingEndState::KnockedOut(self.knock_out())
             } else {
@@ -288,397 +913,2403 @@ mod red_hat_boy_states {
             FALLING_FRAME_NAME
         }
     }
-}
 
-#[derive(Copy, Clone)]
-enum RedHatBoyStateMachine {
-    Idle(RedHatBoyState<Idle>),
-    Running(RedHatBoyState<Running>),
-    Sliding(RedHatBoyState<Sliding>),
-    Jumping(RedHatBoyState<Jumping>),
-    Falling(RedHatBoyState<Falling>),
-    KnockedOut(RedHatBoyState<KnockedOut>),
-}
+    #[derive(Copy, Clone)]
+    pub struct FellOff;
 
-pub enum Event {
-    Run,
-    Slide,
-    Update,
-    Jump,
-    KnockOut,
-    Land(i16),
-}
+    impl RedHatBoyState<FellOff> {
+        pub fn frame_name(&self) -> &str {
+            FELL_OFF_FRAME_NAME
+        }
 
-impl RedHatBoyStateMachine {
-    fn transition(self, event: Event) -> Self {
-        match (self, event) {
-            (RedHatBoyStateMachine::Idle(state), Event::Run) => state.run().into(),
-            (RedHatBoyStateMachine::Running(state), Event::Slide) => state.slide().into(),
-            (RedHatBoyStateMachine::Running(state), Event::Jump) => state.jump().into(),
-            (RedHatBoyStateMachine::Running(state), Event::KnockOut) => state.knock_out().into(),
-            (RedHatBoyStateMachine::Running(state), Event::Land(position)) => {
-                state.land_on(position).into()
-            }
-            (RedHatBoyStateMachine::Idle(state), Event::Update) => state.update().into(),
-            (RedHatBoyStateMachine::Running(state), Event::Update) => state.update().into(),
-            (RedHatBoyStateMachine::Sliding(state), Event::Update) => state.update().into(),
-            (RedHatBoyStateMachine::Falling(state), Event::Update) => state.update().into(),
-            (RedHatBoyStateMachine::Sliding(state), Event::KnockOut) => state.knock_out().into(),
-            (RedHatBoyStateMachine::Sliding(state), Event::Land(position)) => {
-                state.land_on(position).into()
-            }
-            (RedHatBoyStateMachine::Jumping(state), Event::Update) => state.update().into(),
-            (RedHatBoyStateMachine::Jumping(state), Event::KnockOut) => state.knock_out().into(),
-            (RedHatBoyStateMachine::Jumping(state), Event::Land(position)) => {
-                state.land_on(position).into()
-            }
-            _ => self,
+        // Keeps dropping into the pit, playing the animation once and then
+        // holding its last frame.
+        pub fn update(mut self, ground: i16) -> Self {
+            let frame = self.context.frame;
+            self.update_context(FELL_OFF_FRAMES, ground);
+            self.context.frame = (frame + 1).min(FELL_OFF_FRAMES);
+            self
         }
     }
-    fn frame_name(&self) -> &str {
-        match self {
-            RedHatBoyStateMachine::Idle(state) => state.frame_name(),
-            RedHatBoyStateMachine::Running(state) => state.frame_name(),
-            RedHatBoyStateMachine::Sliding(state) => state.frame_name(),
-            RedHatBoyStateMachine::Jumping(state) => state.frame_name(),
-            RedHatBoyStateMachine::Falling(state) => state.frame_name(),
-            RedHatBoyStateMachine::KnockedOut(state) => state.frame_name(),
+
+    #[cfg(all(test, not(target_arch = "wasm32")))]
+    mod tests {
+        use super::*;
+        use proptest::prelude::*;
+
+        // (frame count, frame, y, vertical velocity) of a boy somewhere in
+        // the air or the ground, moving at any vertical speed up to
+        // terminal, partway through one of his animations.
+        fn start() -> impl Strategy<Value = (u8, u8, i16, i16)> {
+            (
+                prop::sample::select(&ANIMATIONS[..]),
+                any::<prop::sample::Index>(),
+                -2 * HEIGHT..2 * HEIGHT,
+                -2 * TERMINAL_VELOCITY..=TERMINAL_VELOCITY,
+            )
+                .prop_map(|((_, frames), frame, y, velocity_y)| {
+                    let frame = frame.index(usize::from(frames) + 1) as u8;
+                    (frames, frame, y, velocity_y)
+                })
         }
-    }
 
-    fn context(&self) -> &RedHatBoyContext {
-        match self {
-            RedHatBoyStateMachine::Idle(state) => &state.context(),
-            RedHatBoyStateMachine::Running(state) => &state.context(),
-            RedHatBoyStateMachine::Sliding(state) => &state.context(),
-            RedHatBoyStateMachine::Jumping(state) => &state.context(),
-            RedHatBoyStateMachine::Falling(state) => &state.context(),
-            RedHatBoyStateMachine::KnockedOut(state) => &state.context(),
+        fn context((_, frame, y, velocity_y): (u8, u8, i16, i16)) -> RedHatBoyContext {
+            let mut context = *RedHatBoyState::new().context();
+            context.frame = frame;
+            context.position.y = y;
+            context.velocity.y = velocity_y;
+            context
         }
-    }
 
-    fn update(self) -> Self {
-        self.transition(Event::Update)
-    }
-}
+        proptest! {
+            #[test]
+            fn never_exceeds_terminal_velocity(
+                start in start(),
+                grounds in prop::collection::vec(0..=HEIGHT * 3, 1..120),
+            ) {
+                let (frames, ..) = start;
+                let mut context = context(start);
+                for ground in grounds {
+                    context = context.update(frames, ground);
+                    prop_assert!(context.velocity.y <= TERMINAL_VELOCITY);
+                }
+            }
 
-impl From<RedHatBoyState<Idle>> for RedHatBoyStateMachine {
-    fn from(state: RedHatBoyState<Idle>) -> Self {
-        RedHatBoyStateMachine::Idle(state)
-    }
-}
+            #[test]
+            fn never_sinks_below_the_ground(
+                start in start(),
+                grounds in prop::collection::vec(0..=HEIGHT * 3, 1..120),
+            ) {
+                let (frames, ..) = start;
+                let mut context = context(start);
+                for ground in grounds {
+                    context = context.update(frames, ground);
+                    prop_assert!(context.position.y <= ground - PLAYER_HEIGHT);
+                }
+            }
 
-impl From<RedHatBoyState<Running>> for RedHatBoyStateMachine {
-    fn from(state: RedHatBoyState<Running>) -> Self {
-        RedHatBoyStateMachine::Running(state)
+            #[test]
+            fn frame_stays_within_the_animation(
+                start in start(),
+                grounds in prop::collection::vec(0..=HEIGHT * 3, 1..120),
+            ) {
+                let (frames, ..) = start;
+                let mut context = context(start);
+                for ground in grounds {
+                    context = context.update(frames, ground);
+                    prop_assert!(context.frame <= frames);
+                }
+            }
+        }
     }
 }
 
-impl From<RedHatBoyState<Sliding>> for RedHatBoyStateMachine {
-    fn from(state: RedHatBoyState<Sliding>) -> Self {
-        RedHatBoyStateMachine::Sliding(state)
-    }
-}
-impl From<SlidingEndState> for RedHatBoyStateMachine {
-    fn from(end_state: SlidingEndState) -> Self {
-        match end_state {
-            SlidingEndState::Complete(running_state) => running_state.into(),
-            SlidingEndState::Sliding(sliding_state) => sliding_state.into(),
+state_machine! {
+    #[derive(Copy, Clone)]
+    enum RedHatBoyStateMachine(RedHatBoyState) {
+        kind: #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)] StateKind,
+        states: [Idle, Running, Sliding, Jumping, Diving, Rolling, Falling, KnockedOut, FellOff],
+        forward: {
+            fn frame_name(&self) -> &str;
+            fn context(&self) -> &RedHatBoyContext;
+            // Changes the context without changing state.
+            fn map_context(self, f: impl FnOnce(RedHatBoyContext) -> RedHatBoyContext) -> Self;
         }
     }
 }
 
-impl From<RedHatBoyState<Jumping>> for RedHatBoyStateMachine {
-    fn from(state: RedHatBoyState<Jumping>) -> Self {
-        RedHatBoyStateMachine::Jumping(state)
+end_states!(RedHatBoyStateMachine {
+    RunningEndState { Running, Jumping },
+    SlidingEndState { Complete, Sliding },
+    JumpingEndState { Jumping, Landing, Rolling },
+    DivingEndState { Diving, Sliding, Rolling },
+    RollingEndState { Complete, Rolling },
+    FallingEndState { Falling, KnockedOut },
+});
+
+impl StateKind {
+    fn is_game_over(self) -> bool {
+        matches!(self, StateKind::KnockedOut | StateKind::FellOff)
     }
-}
 
-impl From<JumpingEndState> for RedHatBoyStateMachine {
-    fn from(state: JumpingEndState) -> Self {
-        match state {
-            JumpingEndState::Jumping(jumping) => jumping.into(),
-            JumpingEndState::Landing(landing) => landing.into(),
-        }
+    fn is_airborne(self) -> bool {
+        matches!(self, StateKind::Jumping | StateKind::Diving)
     }
 }
 
-impl From<RedHatBoyState<Falling>> for RedHatBoyStateMachine {
-    fn from(state: RedHatBoyState<Falling>) -> Self {
-        RedHatBoyStateMachine::Falling(state)
-    }
+// Lets systems outside the states (particles, audio, analytics) react to the
+// boy changing state without each state's methods having to call them.
+trait StateObserver {
+    fn on_enter(&mut self, _state: StateKind) {}
+    fn on_exit(&mut self, _state: StateKind) {}
+    // The run passed another MILESTONE_METERS.
+    fn on_milestone(&mut self, _meters: i32) {}
 }
 
-impl From<RedHatBoyState<KnockedOut>> for RedHatBoyStateMachine {
-    fn from(state: RedHatBoyState<KnockedOut>) -> Self {
-        RedHatBoyStateMachine::KnockedOut(state)
-    }
-}
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    Run,
+    Slide,
+    Jump,
+    KnockOut(Cause),
+    Hurt,
+    Fall(Cause),
+    Land(i16),
+    Bump(i16),
+    Bounce(i16),
+    Steer(i16, AirControl),
+}
+
+impl Event {
+    // Lower runs first when a frame queued several events: anything that
+    // ends the run beats damage, damage beats landing, and landing comes
+    // before bouncing and the inputs so a spring or a jump pressed on
+    // touchdown leaves the ground.
+    fn priority(&self) -> u8 {
+        match self {
+            Event::KnockOut(_) | Event::Fall(_) => 0,
+            Event::Hurt => 1,
+            Event::Land(_) | Event::Bump(_) => 2,
+            Event::Bounce(_) => 3,
+            Event::Jump => 4,
+            Event::Slide => 5,
+            Event::Steer(_, _) => 6,
+            Event::Run => 7,
+        }
+    }
+}
+
+impl RedHatBoyStateMachine {
+    fn transition(self, event: Event) -> Self {
+        let next: Self = match (self, event) {
+            (RedHatBoyStateMachine::Idle(state), Event::Run) => state.run().into(),
+            (RedHatBoyStateMachine::Running(state), Event::Slide) => state.slide().into(),
+            (RedHatBoyStateMachine::Running(state), Event::Jump) if state.can_jump() => {
+                state.jump().into()
+            }
+            (RedHatBoyStateMachine::Running(state), Event::Jump) => state.buffer_jump().into(),
+            (RedHatBoyStateMachine::Jumping(state), Event::Jump) => state.buffer_jump().into(),
+            (RedHatBoyStateMachine::Running(state), Event::KnockOut(_)) => state.knock_out().into(),
+            (RedHatBoyStateMachine::Running(state), Event::Land(position)) => {
+                state.land_on(position).into()
+            }
+            (RedHatBoyStateMachine::Sliding(state), Event::KnockOut(_)) => state.knock_out().into(),
+            (RedHatBoyStateMachine::Sliding(state), Event::Land(position)) => {
+                state.land_on(position).into()
+            }
+            (RedHatBoyStateMachine::Jumping(state), Event::KnockOut(_)) => state.knock_out().into(),
+            (RedHatBoyStateMachine::Jumping(state), Event::Land(position)) => {
+                state.land_on(position).into()
+            }
+            (RedHatBoyStateMachine::Running(state), Event::Bounce(velocity)) => {
+                state.bounce(velocity).into()
+            }
+            (RedHatBoyStateMachine::Sliding(state), Event::Bounce(velocity)) => {
+                state.bounce(velocity).into()
+            }
+            (RedHatBoyStateMachine::Jumping(state), Event::Bounce(velocity)) => {
+                state.bounce(velocity).into()
+            }
+            (RedHatBoyStateMachine::Rolling(state), Event::Bounce(velocity)) => {
+                state.bounce(velocity).into()
+            }
+            (RedHatBoyStateMachine::Jumping(state), Event::Bump(depth)) => {
+                state.bump_head(depth).into()
+            }
+            (RedHatBoyStateMachine::Jumping(state), Event::Steer(direction, air_control)) => {
+                state.steer(direction, air_control).into()
+            }
+            (RedHatBoyStateMachine::Jumping(state), Event::Slide) => state.dive().into(),
+            (RedHatBoyStateMachine::Diving(state), Event::Land(position)) => {
+                state.land_on(position).into()
+            }
+            (RedHatBoyStateMachine::Diving(state), Event::Steer(direction, air_control)) => {
+                state.steer(direction, air_control).into()
+            }
+            (RedHatBoyStateMachine::Diving(state), Event::Bounce(velocity)) => {
+                state.bounce(velocity).into()
+            }
+            (RedHatBoyStateMachine::Diving(state), Event::KnockOut(_)) => state.knock_out().into(),
+            (RedHatBoyStateMachine::Diving(state), Event::Hurt) => state.hurt().into(),
+            (RedHatBoyStateMachine::Diving(state), Event::Fall(_)) => state.fall().into(),
+            (RedHatBoyStateMachine::Sliding(state), Event::Jump) if state.can_jump() => {
+                state.jump().into()
+            }
+            (RedHatBoyStateMachine::Sliding(state), Event::Jump) => state.buffer_jump().into(),
+            (RedHatBoyStateMachine::Rolling(state), Event::Land(position)) => {
+                state.land_on(position).into()
+            }
+            (RedHatBoyStateMachine::Running(state), Event::Hurt) => state.hurt().into(),
+            (RedHatBoyStateMachine::Sliding(state), Event::Hurt) => state.hurt().into(),
+            (RedHatBoyStateMachine::Jumping(state), Event::Hurt) => state.hurt().into(),
+            (RedHatBoyStateMachine::Running(state), Event::Fall(_)) => state.fall().into(),
+            (RedHatBoyStateMachine::Sliding(state), Event::Fall(_)) => state.fall().into(),
+            (RedHatBoyStateMachine::Jumping(state), Event::Fall(_)) => state.fall().into(),
+            (RedHatBoyStateMachine::Rolling(state), Event::Fall(_)) => state.fall().into(),
+            _ => {
+                self.reject(event);
+                return self;
+            }
+        };
+        if cfg!(debug_assertions) {
+            trace!("{:?} + {:?} -> {:?}", self.kind(), event, next.kind());
+        }
+        next
+    }
+
+    // Debug builds log every event a state had no transition for, and with
+    // `?strict` panic on any the state does not ignore on purpose.
+    fn reject(self, event: Event) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        if ignores(self.kind(), event) {
+            trace!("{:?} ignored {:?}", self.kind(), event);
+            return;
+        }
+        debug!("{:?} rejected {:?}", self.kind(), event);
+        if STRICT_TRANSITIONS.load(Ordering::Relaxed) {
+            panic!("Unexpected transition: {:?} + {:?}", self.kind(), event);
+        }
+    }
+
+    // Steps the state's physics and animation. This happens every update
+    // before anything can collide, so it isn't an event and isn't queued.
+    fn update(self, ground: i16) -> Self {
+        match self {
+            RedHatBoyStateMachine::Idle(state) => state.update(ground).into(),
+            RedHatBoyStateMachine::Running(state) => state.update(ground).into(),
+            RedHatBoyStateMachine::Sliding(state) => state.update(ground).into(),
+            RedHatBoyStateMachine::Falling(state) => state.update(ground).into(),
+            RedHatBoyStateMachine::Jumping(state) => state.update(ground).into(),
+            RedHatBoyStateMachine::Diving(state) => state.update(ground).into(),
+            RedHatBoyStateMachine::Rolling(state) => state.update(ground).into(),
+            RedHatBoyStateMachine::FellOff(state) => state.update(ground).into(),
+            RedHatBoyStateMachine::KnockedOut(_) => self,
+        }
+    }
+
+    fn observe(self, next: Self, observer: &mut dyn StateObserver) -> Self {
+        if self.kind() != next.kind() {
+            observer.on_exit(self.kind());
+            observer.on_enter(next.kind());
+        }
+        next
+    }
+}
+
+static STRICT_TRANSITIONS: AtomicBool = AtomicBool::new(false);
+
+// Events a state drops by design, as opposed to ones that reached a state
+// that should never see them.
+fn ignores(kind: StateKind, event: Event) -> bool {
+    match (kind, event) {
+        // Input arrives every frame whatever the boy is doing.
+        (_, Event::Run | Event::Slide | Event::Jump | Event::Steer(..)) => true,
+        // Before the run and once he is down, the world can't touch him.
+        (StateKind::Idle | StateKind::Falling | StateKind::KnockedOut | StateKind::FellOff, _) => {
+            true
+        }
+        // The roll after a landing shrugs off hits.
+        (StateKind::Rolling, Event::KnockOut(_) | Event::Hurt) => true,
+        _ => false,
+    }
+}
+
+fn sprite_name(animation: &str, frame: u8) -> String {
+    format!("{} ({}).png", animation, (frame / 3) + 1)
+}
+
+#[derive(Clone)]
+pub struct RedHatBoy {
+    state_machine: RedHatBoyStateMachine,
+    events: Vec<Event>,
+    cause: Option<Cause>,
+    sprite_sheet: Sheet,
+    frame_size: SourceSize,
+    image: Texture,
+    hitboxes: Hitboxes,
+}
+
+impl RedHatBoy {
+    fn new(sprite_sheet: Sheet, image: Texture, hitboxes: Hitboxes) -> Result<Self> {
+        let required: Vec<String> = ANIMATIONS
+            .iter()
+            .flat_map(|(animation, frames)| {
+                (0..=*frames).map(move |frame| sprite_name(animation, frame))
+            })
+            .collect();
+        sprite_sheet
+            .validate(&image, &required)
+            .context("Red Hat Boy sheet")?;
+        Ok(RedHatBoy {
+            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new()),
+            events: vec![],
+            cause: None,
+            frame_size: sprite_sheet.frame_size(),
+            sprite_sheet,
+            image,
+            hitboxes,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.state_machine = RedHatBoyStateMachine::Idle(RedHatBoyState::new());
+        self.events.clear();
+        self.cause = None;
+    }
+
+    fn frame_name(&self) -> String {
+        sprite_name(
+            self.state_machine.frame_name(),
+            self.state_machine.context().frame,
+        )
+    }
+
+    fn current_sprite(&self) -> Result<&Cell> {
+        let frame_name = self.frame_name();
+        self.sprite_sheet
+            .frames
+            .get(&frame_name)
+            .ok_or_else(|| anyhow!("Cell {} not found in the Red Hat Boy sheet", frame_name))
+    }
+
+    fn draw(&self, renderer: &Renderer) -> Result<()> {
+        let sprite = self.current_sprite()?;
+
+        renderer.draw_image(
+            &self.image,
+            &Rect {
+                x: sprite.frame.x,
+                y: sprite.frame.y,
+                width: sprite.frame.w,
+                height: sprite.frame.h,
+            },
+            &self.destination_box()?,
+            &DrawOptions {
+                transform: Transform {
+                    rotation: self.tilt(),
+                    ..Transform::default()
+                },
+                tint: self.tint(),
+                alpha: self.alpha(),
+            },
+        );
+
+        renderer.draw_rect(&self.bounding_box()?);
+        Ok(())
+    }
+
+    // Blinks while invulnerable so the player can see the grace period.
+    fn alpha(&self) -> f64 {
+        const BLINK_FRAMES: u16 = 6;
+        let invulnerable = self.state_machine.context().invulnerable.remaining();
+        if invulnerable > 0 && (invulnerable / BLINK_FRAMES).is_multiple_of(2) {
+            0.3
+        } else {
+            1.0
+        }
+    }
+
+    // Leans the jump cells forward into a dive.
+    fn tilt(&self) -> f64 {
+        const DIVE_TILT: f64 = 0.5;
+        if self.kind() == StateKind::Diving {
+            DIVE_TILT
+        } else {
+            0.0
+        }
+    }
+
+    fn tint(&self) -> Option<Color> {
+        const HIT_FLASH_FRAMES: u8 = 6;
+        match self.state_machine {
+            RedHatBoyStateMachine::Falling(state) if state.context().frame < HIT_FLASH_FRAMES => {
+                Some(Color::WHITE.with_alpha(0.8))
+            }
+            _ => None,
+        }
+    }
+
+    fn destination_box(&self) -> Result<Rect> {
+        Ok(self
+            .current_sprite()?
+            .destination(&self.state_machine.context().position, self.frame_size))
+    }
+
+    fn ghost_sample(&self, distance: i32) -> Sample {
+        let animation = ANIMATIONS
+            .iter()
+            .position(|(name, _)| *name == self.state_machine.frame_name())
+            .unwrap_or_default();
+        let context = self.state_machine.context();
+        Sample(distance, context.position.y, animation as u8, context.frame)
+    }
+
+    // Draws a translucent copy of the boy where a recorded run had him,
+    // `offset` pixels ahead of (or behind) the live boy.
+    fn draw_ghost(&self, renderer: &Renderer, sample: &Sample, offset: i16) -> Result<()> {
+        const GHOST_ALPHA: f64 = 0.35;
+        let (animation, _) = ANIMATIONS
+            .get(sample.animation())
+            .ok_or_else(|| anyhow!("Ghost animation {} is unknown", sample.animation()))?;
+        let frame_name = sprite_name(animation, sample.frame());
+        let sprite = self
+            .sprite_sheet
+            .frames
+            .get(&frame_name)
+            .ok_or_else(|| anyhow!("Cell {} not found in the Red Hat Boy sheet", frame_name))?;
+        renderer.draw_image(
+            &self.image,
+            &Rect {
+                x: sprite.frame.x,
+                y: sprite.frame.y,
+                width: sprite.frame.w,
+                height: sprite.frame.h,
+            },
+            &sprite.destination(
+                &Point {
+                    x: self.state_machine.context().position.x + offset,
+                    y: sample.y(),
+                },
+                self.frame_size,
+            ),
+            &DrawOptions {
+                alpha: GHOST_ALPHA,
+                ..DrawOptions::default()
+            },
+        );
+        Ok(())
+    }
+
+    fn bounding_box(&self) -> Result<Rect> {
+        Ok(self
+            .hitboxes
+            .bounding_box(self.kind(), &self.frame_name(), self.destination_box()?))
+    }
+
+    fn kind(&self) -> StateKind {
+        self.state_machine.kind()
+    }
+
+    fn feet(&self) -> Result<Point> {
+        let bounding_box = self.bounding_box()?;
+        Ok(Point {
+            x: bounding_box.x + bounding_box.width / 2,
+            y: bounding_box.bottom(),
+        })
+    }
+
+    fn walking_speed(&self) -> i16 {
+        self.state_machine.context().velocity.x
+    }
+
+    fn pos_x(&self) -> i16 {
+        self.state_machine.context().position.x
+    }
+
+    fn pos_y(&self) -> i16 {
+        self.state_machine.context().position.y
+    }
+
+    fn velocity_y(&self) -> i16 {
+        self.state_machine.context().velocity.y
+    }
+
+    fn update(&mut self, ground: i16, observer: &mut dyn StateObserver) {
+        self.state_machine = self
+            .state_machine
+            .observe(self.state_machine.update(ground), observer);
+    }
+
+    // Inputs and collisions only queue events; they reach the state
+    // machine together, in priority order, when the frame is done.
+    fn process_events(&mut self, observer: &mut dyn StateObserver) {
+        let mut events = std::mem::take(&mut self.events);
+        events.sort_by_key(Event::priority);
+        self.state_machine = events.into_iter().fold(self.state_machine, |state, event| {
+            let next = state.observe(state.transition(event), observer);
+            if let Event::KnockOut(cause) | Event::Fall(cause) = event {
+                if next.kind() != state.kind() && self.cause.is_none() {
+                    self.cause = Some(cause);
+                }
+            }
+            next
+        });
+    }
+
+    fn run_right(&mut self) {
+        self.events.push(Event::Run);
+    }
+
+    fn slide(&mut self) {
+        self.events.push(Event::Slide);
+    }
+
+    fn jump(&mut self) {
+        self.events.push(Event::Jump);
+    }
+
+    fn knock_out(&mut self, cause: Cause) {
+        if self.state_machine.context().is_invulnerable() {
+            return;
+        }
+        self.events.push(Event::KnockOut(cause));
+    }
+
+    fn hurt(&mut self) {
+        self.events.push(Event::Hurt);
+    }
+
+    fn is_vulnerable(&self) -> bool {
+        !self.state_machine.context().is_invulnerable()
+            && !self
+                .events
+                .iter()
+                .any(|event| matches!(event, Event::Hurt | Event::KnockOut(_)))
+            && matches!(
+                self.kind(),
+                StateKind::Running | StateKind::Sliding | StateKind::Jumping | StateKind::Diving
+            )
+    }
+
+    fn fall(&mut self, cause: Cause) {
+        self.events.push(Event::Fall(cause));
+    }
+
+    // What ended his run, once something has.
+    fn cause(&self) -> Option<Cause> {
+        self.cause
+    }
+
+    pub fn shift(&mut self, distance: i16) {
+        self.state_machine = self
+            .state_machine
+            .map_context(|context| context.shift(distance));
+    }
+
+    fn set_top_speed(&mut self, top_speed: i16) {
+        self.state_machine = self
+            .state_machine
+            .map_context(|context| context.with_top_speed(top_speed));
+    }
+
+    fn land_on(&mut self, position: i16) {
+        self.events.push(Event::Land(position));
+    }
+
+    fn bump_head(&mut self, depth: i16) {
+        self.events.push(Event::Bump(depth));
+    }
+
+    fn bounce(&mut self, velocity: i16) {
+        self.events.push(Event::Bounce(velocity));
+    }
+
+    fn steer(&mut self, direction: i16, air_control: AirControl) {
+        self.events.push(Event::Steer(direction, air_control));
+    }
+}
+
+const PLAYER_TWO_OFFSET: i16 = 90;
+
+struct Player {
+    boy: RedHatBoy,
+    inputs: Vec<Box<dyn InputSource>>,
+    bindings: Bindings,
+    actions: ActionState,
+    lives: u8,
+    // Where the boy stands on screen when he keeps up with the scroll. He
+    // runs a little faster or slower to get back to it.
+    home: i16,
+}
+
+impl Player {
+    fn new(boy: RedHatBoy, settings: &Settings, index: usize, lives: u8) -> Self {
+        Player {
+            home: boy.pos_x(),
+            boy,
+            inputs: actions::player_inputs(index),
+            bindings: Bindings::for_player(settings.layout, index),
+            actions: ActionState::default(),
+            lives,
+        }
+    }
+
+    // Each hit costs a life and buys a short invulnerability window; only
+    // the last life ends the run. False when the boy could not be hurt.
+    fn take_hit(&mut self, cause: Cause) -> bool {
+        if !self.boy.is_vulnerable() {
+            return false;
+        }
+        self.lives = self.lives.saturating_sub(1);
+        if self.lives == 0 {
+            self.boy.knock_out(cause);
+        } else {
+            self.boy.hurt();
+        }
+        true
+    }
+
+    // A wall carried the boy off the left edge. That ends his run whatever
+    // lives he has left.
+    fn push_off(&mut self, distance: i32) {
+        self.lives = 0;
+        let meters = (distance + i32::from(self.boy.pos_x())) / PIXELS_PER_METER;
+        self.boy.fall(Cause {
+            hazard: Hazard::Wall,
+            meters,
+        });
+    }
+
+    fn control(&mut self, controls: &Controls, air_control: AirControl) {
+        if controls.slide {
+            self.boy.slide();
+        }
+        if controls.run_right {
+            self.boy.run_right();
+        }
+        if controls.steer != 0 {
+            self.boy.steer(controls.steer, air_control);
+        }
+        if controls.jump {
+            self.boy.jump();
+        }
+    }
+}
+
+pub struct Walk {
+    players: Vec<Player>,
+    backgrounds: [engine::Image; 2],
+    scene: Scene,
+    obstacles: Vec<Box<dyn Obstacle>>,
+    segments: SegmentLibrary,
+    course: Course,
+    scroll: Fixed,
+    particles: ParticleSystem,
+    floating_texts: FloatingTexts,
+    speed_lines: SpeedLines,
+    signposts: Signposts,
+    coins: Coins,
+    coins_collected: u32,
+    // How many of them are already in the wardrobe. They go in when the run
+    // ends, and a continue pays for itself out of them.
+    banked: u32,
+    combo: Combo,
+    stamina: Stamina,
+    score: u32,
+    // The HUD counts up to the score rather than jumping.
+    shown_score: Tween,
+    photo: Option<PhotoMode>,
+    digits: DigitStrip,
+    hud: HudLayout,
+    music: Option<Music>,
+    cues: Option<Cues>,
+    game_over_panel: Tween,
+    daily: DailyMode,
+    // What `rng` started from this run, for challenge links.
+    seed: u32,
+    rng: Rng,
+    // Whether the challenge link for this run is on the clipboard.
+    shared: bool,
+    tick: u32,
+    leaderboard: Leaderboard,
+    race: Option<Race>,
+    ghost: Option<GhostTrace>,
+    recording: Option<GhostTrace>,
+    checkpoint: Option<Checkpoint>,
+    terrain: Terrain,
+    themes: Themes,
+    // Kept for what is loaded during play, like upcoming theme backgrounds.
+    assets: Rc<AssetStore>,
+    battery: BatteryWatch,
+    lighting: Lighting,
+    camera: Camera,
+    distance: i32,
+    locale: Locale,
+    history: RunHistory,
+    lifetime: LifetimeStats,
+    // How far this run had got when it went into the history, so a continue
+    // updates that entry instead of adding another.
+    recorded: Option<i32>,
+    showing_lifetime: bool,
+    tutorial: Tutorial,
+    showing_stats: bool,
+    run_stats: RunStats,
+    recorder: Option<Recorder>,
+    settings: Settings,
+    launch: LaunchOptions,
+    paused: bool,
+    settings_menu: Option<SettingsMenu>,
+    shop: Shop,
+    shop_menu: Option<ShopMenu>,
+    ui: Ui,
+    flash: Timer,
+    scheduled: Scheduler<Scheduled>,
+    time_scale: TimeScale,
+    hitbox_editor: Option<HitboxEditor>,
+    editor: Option<Editor>,
+    bot: Option<Bot>,
+    spectator: Option<Mirror>,
+    profiling: bool,
+    offline: Option<OfflineCache>,
+    telemetry: Box<dyn Telemetry>,
+    config: GameConfig,
+}
+
+// The world scrolls at its own pace once the run is on: it matches the lead
+// as he sets off, then keeps climbing to the top speed whatever he does, so
+// anything that slows him loses him ground.
+// Where the world was when the boy last ran past a checkpoint, so a run can
+// continue from there after a game over.
+struct Checkpoint {
+    distance: i32,
+    backgrounds: [i16; 2],
+    obstacles: Vec<Box<dyn Obstacle>>,
+    course: Course,
+    rng: Rng,
+    signposts: Signposts,
+    coins: Coins,
+}
+
+// Things the walk does a while after something else happened.
+enum Scheduled {
+    Restart,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum GameOverAction {
+    Stats,
+    Restart,
+    Clip,
+    Share,
+    Continue,
+}
+
+// A full-width row for one centered prompt with its baseline at `y`.
+fn prompt_button(y: i16) -> Rect {
+    Rect {
+        x: WIDTH / 2 - 250,
+        y: y - 22,
+        width: 500,
+        height: 30,
+    }
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+struct Controls {
+    slide: bool,
+    run_right: bool,
+    steer: i16,
+    jump: bool,
+    boost: bool,
+}
+
+impl Controls {
+    fn from_actions(actions: &ActionState) -> Self {
+        let steer = match (
+            actions.is_held(Action::Left),
+            actions.is_held(Action::Right),
+        ) {
+            (true, false) => -1,
+            (false, true) => 1,
+            _ => 0,
+        };
+        Controls {
+            slide: actions.was_just_pressed(Action::Slide),
+            run_right: actions.is_held(Action::Right),
+            steer,
+            jump: actions.was_just_pressed(Action::Jump),
+            boost: actions.is_held(Action::Boost),
+        }
+    }
+}
+
+const PIXELS_PER_METER: i32 = 20;
+// Far enough for any testing, and short of overflowing the distance.
+const MAX_START_METERS: i32 = 1_000_000;
+const SPECTATOR_CANVAS: &str = "spectator";
+const FOLLOW_CAM_ZOOM: i16 = 2;
+const SCORE_MILESTONE: u32 = 1000;
+const HIT_SHAKE: f64 = 8.0;
+const HIT_FLASH_FRAMES: u16 = 12;
+const HIT_FLASH_ALPHA: f32 = 0.35;
+// With reduced motion the wash is faint and fades in as well as out, so it
+// never jumps the whole screen from clear to bright.
+const REDUCED_HIT_FLASH_ALPHA: f32 = 0.1;
+// (scale, unscaled frames) of the slow motion after a near miss and after the
+// final knockout.
+const NEAR_MISS_SLOW_MOTION: (f32, u16) = (0.4, 24);
+const KNOCK_OUT_SLOW_MOTION: (f32, u16) = (0.3, 60);
+// (offset, ticks) of the game-over panel dropping into place.
+const GAME_OVER_DROP: (f64, u16) = (-240.0, 45);
+const SCORE_COUNT_TICKS: u16 = 20;
+
+const HUD_TEXT: TextStyle = TextStyle {
+    font: "bold 20px sans-serif",
+    color: Color::BLACK,
+    align: TextAlign::Left,
+};
+
+const HUD_SCORE: TextStyle = TextStyle {
+    align: TextAlign::Right,
+    ..HUD_TEXT
+};
+
+const HUD_CAPTION: TextStyle = TextStyle {
+    align: TextAlign::Center,
+    ..HUD_TEXT
+};
+
+const RUN_STATS_TEXT: TextStyle = TextStyle {
+    font: "16px sans-serif",
+    ..HUD_CAPTION
+};
+
+const HUD_NOTE: TextStyle = TextStyle {
+    font: "12px sans-serif",
+    ..HUD_TEXT
+};
+
+const PROFILE_TEXT: TextStyle = TextStyle {
+    font: "12px monospace",
+    align: TextAlign::Right,
+    ..HUD_TEXT
+};
+
+const LEADERBOARD_TEXT: TextStyle = TextStyle {
+    font: "14px sans-serif",
+    ..HUD_CAPTION
+};
+
+const STATS_BAR: Color = Color::rgb(0x7F, 0xB2, 0xE5);
+const STATS_BEST: Color = Color::rgb(0xD4, 0xA0, 0x17);
+const STATS_TREND: Color = Color::rgb(0xE5, 0x70, 0x2E);
+
+const DUST: Burst = Burst {
+    count: 6,
+    color: Color::rgb(0xC8, 0xB8, 0x9A),
+    fade_to: Color::rgb(0xF0, 0xEA, 0xDE),
+    size: 4,
+    lifetime: 20,
+    spread: 1.5,
+    lift: 1.0,
+    gravity: 0.05,
+};
+
+const SPARKS: Burst = Burst {
+    count: 8,
+    color: Color::rgb(0xFF, 0xE0, 0x6A),
+    fade_to: Color::rgb(0xFF, 0xFF, 0xFF),
+    size: 3,
+    lifetime: 16,
+    spread: 2.0,
+    lift: 2.5,
+    gravity: 0.1,
+};
+
+const DEBRIS: Burst = Burst {
+    count: 12,
+    color: Color::rgb(0x5A, 0x46, 0x32),
+    fade_to: Color::rgb(0x5A, 0x46, 0x32),
+    size: 5,
+    lifetime: 40,
+    spread: 3.0,
+    lift: 5.0,
+    gravity: 0.3,
+};
+
+// Kicks up dust on landings and slides, and debris when the boy is knocked
+// down, at his feet as they were before the transition.
+struct TransitionParticles<'a> {
+    particles: &'a mut ParticleSystem,
+    feet: Point,
+    exited: Option<StateKind>,
+}
+
+impl<'a> TransitionParticles<'a> {
+    fn new(particles: &'a mut ParticleSystem, boy: &RedHatBoy) -> Result<Self> {
+        Ok(TransitionParticles {
+            particles,
+            feet: boy.feet()?,
+            exited: None,
+        })
+    }
+}
+
+// Lets one state change feed several observers.
+impl<A: StateObserver, B: StateObserver> StateObserver for (A, B) {
+    fn on_exit(&mut self, state: StateKind) {
+        self.0.on_exit(state);
+        self.1.on_exit(state);
+    }
+
+    fn on_enter(&mut self, state: StateKind) {
+        self.0.on_enter(state);
+        self.1.on_enter(state);
+    }
+
+    fn on_milestone(&mut self, meters: i32) {
+        self.0.on_milestone(meters);
+        self.1.on_milestone(meters);
+    }
+}
+
+// Lets an observer be switched off without changing the observer type.
+impl<T: StateObserver> StateObserver for Option<T> {
+    fn on_exit(&mut self, state: StateKind) {
+        if let Some(observer) = self {
+            observer.on_exit(state);
+        }
+    }
+
+    fn on_enter(&mut self, state: StateKind) {
+        if let Some(observer) = self {
+            observer.on_enter(state);
+        }
+    }
+
+    fn on_milestone(&mut self, meters: i32) {
+        if let Some(observer) = self {
+            observer.on_milestone(meters);
+        }
+    }
+}
+
+struct TransitionTelemetry<'a> {
+    telemetry: &'a dyn Telemetry,
+    exited: Option<StateKind>,
+}
+
+impl<'a> TransitionTelemetry<'a> {
+    fn new(telemetry: &'a dyn Telemetry) -> Self {
+        TransitionTelemetry {
+            telemetry,
+            exited: None,
+        }
+    }
+}
+
+// Watches each boy's state changes for the dust, telemetry and run stats.
+struct Transitions<'a> {
+    particles: &'a mut ParticleSystem,
+    telemetry: &'a dyn Telemetry,
+    run_stats: &'a mut RunStats,
+    reduced_motion: bool,
+}
+
+impl Watch for Transitions<'_> {
+    fn watch(&mut self, boy: &RedHatBoy) -> Result<Box<dyn StateObserver + '_>> {
+        let particles = if self.reduced_motion {
+            None
+        } else {
+            Some(TransitionParticles::new(self.particles, boy)?)
+        };
+        Ok(Box::new((
+            particles,
+            (
+                TransitionTelemetry::new(self.telemetry),
+                &mut *self.run_stats,
+            ),
+        )))
+    }
+}
+
+impl StateObserver for TransitionTelemetry<'_> {
+    fn on_exit(&mut self, state: StateKind) {
+        self.exited = Some(state);
+    }
+
+    fn on_enter(&mut self, state: StateKind) {
+        if let Some(from) = self.exited.take() {
+            self.telemetry
+                .record(GameEvent::Transition { from, to: state });
+        }
+    }
+}
+
+impl StateObserver for TransitionParticles<'_> {
+    fn on_exit(&mut self, state: StateKind) {
+        self.exited = Some(state);
+    }
+
+    fn on_enter(&mut self, state: StateKind) {
+        match (self.exited, state) {
+            (
+                Some(StateKind::Jumping | StateKind::Diving),
+                StateKind::Running | StateKind::Rolling,
+            )
+            | (_, StateKind::Sliding) => self.particles.spawn(self.feet, &DUST),
+            (_, StateKind::Falling) => self.particles.spawn(self.feet, &DEBRIS),
+            _ => {}
+        }
+    }
+}
+
+impl Walk {
+    fn lead(&self) -> &RedHatBoy {
+        world::lead(&self.players)
+    }
+
+    fn is_game_over(&self) -> bool {
+        world::is_game_over(&self.players)
+    }
+
+    fn velocity(&self) -> i16 {
+        -self.scroll.round()
+    }
+
+    // How hard the run is going, for the music: mostly the lead's speed,
+    // with the combo on top.
+    fn intensity(&self) -> f32 {
+        const PACE_SHARE: f32 = 0.7;
+        let lead = self.lead();
+        if lead.kind() == StateKind::Idle || self.is_game_over() {
+            return 0.0;
+        }
+        let speed = self.config.speed;
+        let pace = f32::from(lead.walking_speed() - speed.base)
+            / f32::from((speed.max - speed.base).max(1));
+        pace.clamp(0.0, 1.0) * PACE_SHARE + self.combo.heat() * (1.0 - PACE_SHARE)
+    }
+
+    fn update_camera(&mut self) {
+        let camera = self.config.camera;
+        let speed_delta = f64::from(self.lead().walking_speed() - camera.base_speed);
+        self.camera.zoom_to(
+            (1.0 - speed_delta * camera.zoom_per_speed).clamp(camera.min_zoom, camera.max_zoom),
+        );
+        self.camera.update();
+    }
+
+    // Darkness follows a cosine over `cycle_meters`: full day at the start of
+    // each cycle and the configured night color halfway through.
+    fn draw_lighting(&self, renderer: &Renderer, camera: &Camera) -> Result<()> {
+        let lighting = self.config.lighting;
+        let meters = self.distance / PIXELS_PER_METER;
+        let phase =
+            f64::from(meters.rem_euclid(lighting.cycle_meters)) / f64::from(lighting.cycle_meters);
+        let night = (1.0 - (phase * std::f64::consts::TAU).cos()) / 2.0;
+        let darkness = lighting.night.with_alpha(lighting.night.a * night as f32);
+        let lights = self
+            .players
+            .iter()
+            .map(|player| {
+                let boy = player.boy.bounding_box()?;
+                Ok(Light {
+                    center: Point {
+                        x: boy.x + boy.width / 2,
+                        y: boy.y + boy.height / 2,
+                    },
+                    radius: lighting.halo_radius,
+                    intensity: night as f32,
+                })
+            })
+            .collect::<Result<Vec<Light>>>()?;
+        self.lighting
+            .draw(renderer, &camera.view(), darkness, &lights);
+        Ok(())
+    }
+
+    // The last few log lines, shown with `?debug`.
+    fn draw_log_panel(&self, renderer: &Renderer, lines: &[String]) {
+        for (row, line) in lines.iter().rev().enumerate() {
+            renderer.draw_text(
+                line,
+                &Point {
+                    x: 10,
+                    y: HEIGHT - 26 - row as i16 * 14,
+                },
+                &HUD_NOTE,
+            );
+        }
+    }
+
+    // Image draws made and skipped this frame, shown with `?profile`.
+    fn draw_profile(&self, renderer: &Renderer) {
+        let stats = renderer.draw_stats();
+        renderer.draw_text(
+            &format!("draws {} culled {}", stats.drawn, stats.culled),
+            &Point {
+                x: WIDTH - 10,
+                y: HEIGHT - 10,
+            },
+            &PROFILE_TEXT,
+        );
+    }
+
+    fn draw_hud(&self, renderer: &Renderer) {
+        let label = self.locale.tr("hud.score_label");
+        let label_at = self.hud.place(Anchor::TopLeft, Point { x: 10, y: 30 });
+        let label_width = match self
+            .digits
+            .draw_label(renderer, label, &HUD_TEXT, &label_at)
+        {
+            Ok(width) => width,
+            Err(err) => {
+                debug!("Drawing the score label as text: {:?}", err);
+                renderer.draw_text(label, &label_at, &HUD_TEXT);
+                renderer.measure_text(label, &HUD_TEXT)
+            }
+        };
+        let score = self.shown_score.value().round() as u32;
+        self.digits.draw(
+            renderer,
+            score,
+            &Point {
+                x: label_at.x + label_width + 8 + self.digits.measure(score),
+                y: label_at.y - self.digits.height(),
+            },
+        );
+        renderer.draw_text(
+            &self.locale.tr("hud.distance").replace(
+                "{distance}",
+                &(self.distance / PIXELS_PER_METER).to_string(),
+            ),
+            &self.hud.place(Anchor::TopLeft, Point { x: 10, y: 56 }),
+            &HUD_TEXT,
+        );
+        renderer.draw_text(
+            &self
+                .locale
+                .tr("hud.coins")
+                .replace("{coins}", &self.coins_collected.to_string()),
+            &self.hud.place(Anchor::TopRight, Point { x: 10, y: 30 }),
+            &HUD_SCORE,
+        );
+        for (index, player) in self.players.iter().enumerate() {
+            let lives = self
+                .locale
+                .tr("hud.lives")
+                .replace("{lives}", &player.lives.to_string());
+            renderer.draw_text(
+                &if self.players.len() > 1 {
+                    format!("P{} {}", index + 1, lives)
+                } else {
+                    lives
+                },
+                &self.hud.place(
+                    Anchor::TopLeft,
+                    Point {
+                        x: 10,
+                        y: 82 + index as i16 * 26,
+                    },
+                ),
+                &HUD_TEXT,
+            );
+        }
+        self.stamina.draw(
+            renderer,
+            &self.hud.place(
+                Anchor::TopLeft,
+                Point {
+                    x: 10,
+                    y: 70 + self.players.len() as i16 * 26,
+                },
+            ),
+        );
+        if let Some(daily) = self.daily.best() {
+            renderer.draw_text(
+                &self
+                    .locale
+                    .tr("hud.daily")
+                    .replace("{date}", daily.date())
+                    .replace("{score}", &daily.score().to_string()),
+                &self.hud.place(Anchor::TopRight, Point { x: 10, y: 56 }),
+                &HUD_SCORE,
+            );
+        }
+        self.combo.draw(
+            renderer,
+            &self.hud.place(Anchor::TopRight, Point { x: 10, y: 66 }),
+        );
+        if let Some(prompt) = self.tutorial.prompt() {
+            renderer.draw_text(
+                self.locale.tr(prompt),
+                &Point {
+                    x: WIDTH / 2,
+                    y: 160,
+                },
+                &HUD_CAPTION,
+            );
+        }
+        if self.offline.as_ref().is_some_and(OfflineCache::is_ready) {
+            renderer.draw_text(
+                self.locale.tr("hud.offline_ready"),
+                &self.hud.place(Anchor::BottomLeft, Point { x: 10, y: 10 }),
+                &HUD_NOTE,
+            );
+        }
+        if self.bot.is_some() {
+            return;
+        }
+        if self.settings.minimap && self.lead().kind() != StateKind::Idle && !self.is_game_over() {
+            self.draw_minimap(renderer);
+        }
+        let kind = if self.is_game_over() {
+            StateKind::KnockedOut
+        } else {
+            self.players[0].boy.kind()
+        };
+        let (heading, prompts) = match kind {
+            StateKind::Idle if self.race.is_some() => ("title", vec![self.race_status()]),
+            StateKind::Idle if self.players.len() == 1 => (
+                "title",
+                vec![
+                    self.locale.tr("prompt.start").to_string(),
+                    self.locale.tr("prompt.daily").to_string(),
+                    self.locale.tr("prompt.coop").to_string(),
+                    self.locale.tr("prompt.shop").to_string(),
+                    self.locale.tr("prompt.lifetime").to_string(),
+                ],
+            ),
+            StateKind::Idle => ("title", vec![self.locale.tr("prompt.start").to_string()]),
+            // The heading and prompts are widgets from `game_over_menu`.
+            StateKind::KnockedOut | StateKind::FellOff => {
+                self.draw_leaderboard(renderer);
+                return;
+            }
+            _ => return,
+        };
+        renderer.draw_text(
+            self.locale.tr(heading),
+            &Point {
+                x: WIDTH / 2,
+                y: 200,
+            },
+            &HUD_CAPTION,
+        );
+        for (line, prompt) in prompts.iter().rev().enumerate() {
+            renderer.draw_text(
+                prompt,
+                &self.hud.place(
+                    Anchor::BottomCenter,
+                    Point {
+                        x: 0,
+                        y: 40 + line as i16 * 30,
+                    },
+                ),
+                &HUD_CAPTION,
+            );
+        }
+    }
+
+    // The part of the screen the spectator canvas shows: the lead boy,
+    // magnified, kept inside the screen edges.
+    fn follow_cam(&self) -> Result<Rect> {
+        let boy = self.lead().bounding_box()?;
+        let width = WIDTH / FOLLOW_CAM_ZOOM;
+        let height = HEIGHT / FOLLOW_CAM_ZOOM;
+        Ok(Rect {
+            x: (boy.x + boy.width / 2 - width / 2).clamp(0, WIDTH - width),
+            y: (boy.y + boy.height / 2 - height / 2).clamp(0, HEIGHT - height),
+            width,
+            height,
+        })
+    }
+
+    // P saves a screenshot at any time; G saves the last few seconds once the
+    // run is over. Holding either key only captures once.
+    // Escape pauses a run in progress and Enter opens the settings from the
+    // pause screen. Returns whether the world should stay frozen.
+    fn update_pause(&mut self, keystate: &KeyState) -> bool {
+        let pause = self.action_pressed(Action::Pause);
+        let confirm = self.action_pressed(Action::Confirm);
+        if let Some(menu) = &mut self.settings_menu {
+            let actions = &self.players[0].actions;
+            match menu.update(actions, &mut self.ui, &self.locale, &mut self.settings) {
+                MenuAction::None => {}
+                MenuAction::Changed => self.apply_settings(),
+                MenuAction::Close => self.settings_menu = None,
+            }
+            return true;
+        }
+        if let Some(editor) = self.hitbox_editor.as_mut().filter(|_| self.paused) {
+            let boy = &mut self.players[0].boy;
+            if editor.update(keystate, boy.kind(), &mut boy.hitboxes) {
+                let hitboxes = boy.hitboxes.clone();
+                for player in self.players.iter_mut().skip(1) {
+                    player.boy.hitboxes = hitboxes.clone();
+                }
+            }
+        }
+        let running = self.lead().kind() != StateKind::Idle && !self.is_game_over();
+        if running && !keystate.has_focus() {
+            self.paused = true;
+        }
+        let (resume, open_settings, open_photo) = if self.paused {
+            self.pause_menu()
+        } else {
+            (false, false, false)
+        };
+        if (pause && (self.paused || running)) || resume {
+            self.paused = !self.paused;
+        } else if (confirm && self.paused) || open_settings {
+            self.settings_menu = Some(SettingsMenu::new());
+        } else if (self.action_pressed(Action::Photo) && self.paused) || open_photo {
+            self.photo = Some(PhotoMode::new(&self.camera));
+        }
+        self.paused
+    }
+
+    // Photo mode takes over from the pause menu until Escape. Returns whether
+    // it is open.
+    fn update_photo(&mut self) -> Result<bool> {
+        let Some(photo) = &mut self.photo else {
+            return Ok(false);
+        };
+        match photo.update(&self.players[0].actions) {
+            PhotoAction::None => {}
+            PhotoAction::Save => capture::save_screenshot(
+                &browser::canvas()?,
+                &format!(
+                    "walk-the-dog-photo-{}m.png",
+                    self.distance / PIXELS_PER_METER
+                ),
+            )?,
+            PhotoAction::Close => self.photo = None,
+        }
+        Ok(true)
+    }
+
+    fn action_pressed(&self, action: Action) -> bool {
+        self.players
+            .iter()
+            .any(|player| player.actions.was_just_pressed(action))
+    }
+
+    // The shop is open from the title screen until Escape closes it. Returns
+    // whether it is open.
+    fn update_shop(&mut self) -> bool {
+        let Some(menu) = &mut self.shop_menu else {
+            return false;
+        };
+        let actions = &self.players[0].actions;
+        match menu.update(actions, &mut self.ui, &self.locale, &mut self.shop) {
+            MenuAction::None => {}
+            MenuAction::Changed => self.apply_wardrobe(),
+            MenuAction::Close => self.shop_menu = None,
+        }
+        true
+    }
+
+    fn apply_wardrobe(&mut self) {
+        let skin = self.shop.skin();
+        for player in self.players.iter_mut() {
+            player.boy.image = skin.clone();
+        }
+        // The boys and the shop hold on to what they need, so the store can
+        // let go of the files the old look was loaded from.
+        self.assets.unload(Scope::Character);
+        if let Err(err) = self.shop.save() {
+            warn!("Could not save the wardrobe: {:?}", err);
+        }
+    }
+
+    // Returns whether Resume, Settings and Photo mode were clicked.
+    fn pause_menu(&mut self) -> (bool, bool, bool) {
+        self.ui.label(
+            self.locale.tr("pause.title"),
+            Point {
+                x: WIDTH / 2,
+                y: 240,
+            },
+            HUD_CAPTION,
+        );
+        let [resume, settings, photo] = [
+            ("pause.resume", 270),
+            ("pause.settings", 300),
+            ("pause.photo", 330),
+        ]
+        .map(|(key, y)| {
+            self.ui
+                .button(prompt_button(y), self.locale.tr(key), HUD_CAPTION, false)
+        });
+        (resume, settings, photo)
+    }
+
+    // The game-over heading and prompts, with each prompt a button that does
+    // the same as its key.
+    fn game_over_menu(&mut self) -> Option<GameOverAction> {
+        let offset = self.game_over_panel.value().round() as i16;
+        self.ui.label(
+            self.locale.tr("game_over"),
+            Point {
+                x: WIDTH / 2,
+                y: 200 + offset,
+            },
+            HUD_CAPTION,
+        );
+        let mut prompts = vec![
+            (
+                GameOverAction::Stats,
+                self.locale.tr("prompt.stats").to_string(),
+            ),
+            (
+                GameOverAction::Restart,
+                self.locale.tr("prompt.restart").to_string(),
+            ),
+        ];
+        if self.recorder.is_some() {
+            prompts.push((
+                GameOverAction::Clip,
+                self.locale.tr("prompt.clip").to_string(),
+            ));
+        }
+        prompts.push((
+            GameOverAction::Share,
+            self.locale
+                .tr(if self.shared {
+                    "prompt.share.copied"
+                } else {
+                    "prompt.share"
+                })
+                .to_string(),
+        ));
+        if let Some(checkpoint) = self.checkpoint.as_ref().filter(|_| self.can_continue()) {
+            prompts.push((
+                GameOverAction::Continue,
+                self.locale
+                    .tr("prompt.continue")
+                    .replace(
+                        "{distance}",
+                        &(checkpoint.distance / PIXELS_PER_METER).to_string(),
+                    )
+                    .replace("{cost}", &self.config.checkpoints.continue_cost.to_string()),
+            ));
+        }
+        let summary = 240 + offset + prompts.len() as i16 * 30 + 20;
+        self.ui.label(
+            self.locale
+                .tr("run.score")
+                .replace("{score}", &self.score.to_string()),
+            Point {
+                x: WIDTH / 2,
+                y: summary,
+            },
+            HUD_CAPTION,
+        );
+        let mut line_y = summary + 26;
+        if let Some(cause) = self.cause() {
+            self.ui.label(
+                self.locale
+                    .tr(cause.hazard.cause())
+                    .replace("{distance}", &cause.meters.to_string()),
+                Point {
+                    x: WIDTH / 2,
+                    y: line_y,
+                },
+                RUN_STATS_TEXT,
+            );
+            line_y += 24;
+        }
+        for (line, (key, count)) in self.run_stats.lines().into_iter().enumerate() {
+            self.ui.label(
+                self.locale.tr(key).replace("{count}", &count.to_string()),
+                Point {
+                    x: WIDTH / 2,
+                    y: line_y + line as i16 * 20,
+                },
+                RUN_STATS_TEXT,
+            );
+        }
+        let mut chosen = None;
+        for (line, (action, prompt)) in prompts.into_iter().enumerate() {
+            if self.ui.button(
+                prompt_button(240 + offset + line as i16 * 30),
+                prompt,
+                HUD_CAPTION,
+                false,
+            ) {
+                chosen = Some(action);
+            }
+        }
+        chosen
+    }
 
-impl From<FallingEndState> for RedHatBoyStateMachine {
-    fn from(state: FallingEndState) -> Self {
-        match state {
-            FallingEndState::Falling(state) => state.into(),
-            FallingEndState::KnockedOut(state) => state.into(),
+    fn apply_settings(&mut self) {
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.bindings = Bindings::for_player(self.settings.layout, index);
+        }
+        if let Err(err) = self.settings.save() {
+            warn!("Could not save settings: {:?}", err);
         }
     }
-}
-
-struct RedHatBoy {
-    state_machine: RedHatBoyStateMachine,
-    sprite_sheet: Sheet,
-    image: HtmlImageElement,
-}
 
-impl RedHatBoy {
-    fn new(sprite_sheet: Sheet, image: HtmlImageElement) -> Self {
-        RedHatBoy {
-            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new()),
-            sprite_sheet,
-            image,
+    // A warning wash over the world for a moment after a boy gets hit.
+    fn draw_flash(&self, renderer: &Renderer) {
+        if !self.flash.is_running() {
+            return;
         }
+        let left = self.flash.fraction_left(HIT_FLASH_FRAMES);
+        let strength = if self.settings.reduced_motion {
+            REDUCED_HIT_FLASH_ALPHA * (1.0 - (2.0 * left - 1.0).abs())
+        } else {
+            HIT_FLASH_ALPHA * left
+        };
+        renderer.fill_rect(
+            &Rect {
+                x: 0,
+                y: 0,
+                width: WIDTH,
+                height: HEIGHT,
+            },
+            renderer.palette().warning.with_alpha(strength),
+        );
     }
 
-    fn frame_name(&self) -> String {
-        format!(
-            "{} ({}).png",
-            self.state_machine.frame_name(),
-            (self.state_machine.context().frame / 3) + 1
-        )
+    // Back to an ordinary run, with whatever only the editor read let go of.
+    fn leave_editor(&mut self) {
+        self.editor = None;
+        self.assets.unload(Scope::Editor);
+        self.restart();
     }
 
-    fn current_sprite(&self) -> Option<&Cell> {
-        self.sprite_sheet.frames.get(&self.frame_name())
+    fn draw_editor(&self, renderer: &Renderer, editor: &Editor) {
+        let lines = [
+            format!(
+                "Editor: {} at {}m",
+                editor.tool_name(),
+                self.distance / PIXELS_PER_METER
+            ),
+            "Left/Right pan, click places, drag moves, Q changes tool".to_string(),
+            "Delete removes, E copies JSON, Escape leaves".to_string(),
+        ];
+        for (line, text) in lines.iter().enumerate() {
+            renderer.draw_text(
+                text,
+                &Point {
+                    x: 10,
+                    y: HEIGHT - 50 + line as i16 * 16,
+                },
+                &HUD_NOTE,
+            );
+        }
     }
 
-    fn draw(&self, renderer: &Renderer) {
-        let sprite = self.current_sprite().expect("Cell not found");
-
-        renderer.draw_image(
-            &self.image,
-            &Rect {
-                x: sprite.frame.x,
-                y: sprite.frame.y,
-                width: sprite.frame.w,
-                height: sprite.frame.h,
-            },
+    fn draw_pause(&self, renderer: &Renderer) {
+        if let Some(menu) = &self.settings_menu {
+            menu.draw(renderer, &self.locale);
+            return;
+        }
+        renderer.fill_rect(
             &Rect {
-                x: (self.state_machine.context().position.x + sprite.sprite_source_size.x as i16)
-                    .into(),
-                y: (self.state_machine.context().position.y + sprite.sprite_source_size.y as i16)
-                    .into(),
-                width: sprite.frame.w.into(),
-                height: sprite.frame.h.into(),
+                x: 0,
+                y: 0,
+                width: WIDTH,
+                height: HEIGHT,
             },
+            Color::WHITE.with_alpha(0.6),
         );
+        if self.hitbox_editor.is_some() {
+            let boy = &self.players[0].boy;
+            let offsets = boy.hitboxes.for_state(boy.kind());
+            renderer.draw_text(
+                &format!(
+                    "{:?}: x {} y {} width {}",
+                    boy.kind(),
+                    offsets.x,
+                    offsets.y,
+                    offsets.width
+                ),
+                &Point {
+                    x: WIDTH / 2,
+                    y: 360,
+                },
+                &HUD_CAPTION,
+            );
+            renderer.draw_text(
+                "Arrows move, Shift+Left/Right resize, E copies JSON",
+                &Point {
+                    x: WIDTH / 2,
+                    y: 386,
+                },
+                &LEADERBOARD_TEXT,
+            );
+        }
+    }
 
-        renderer.draw_rect(&self.bounding_box());
+    fn capture(&mut self, keystate: &KeyState) -> Result<()> {
+        let screenshot = keystate.was_just_released("KeyP");
+        let clip = keystate.was_just_released("KeyG") && self.is_game_over();
+        if screenshot || clip {
+            let meters = self.distance / PIXELS_PER_METER;
+            if screenshot {
+                capture::save_screenshot(
+                    &browser::canvas()?,
+                    &format!("walk-the-dog-{}m.png", meters),
+                )?;
+            }
+            if clip {
+                self.save_clip()?;
+            }
+        }
+        Ok(())
     }
 
-    fn destination_box(&self) -> Rect {
-        let sprite = self.current_sprite().expect("Cell not found");
-        Rect {
-            x: (self.state_machine.context().position.x + sprite.sprite_source_size.x as i16)
-                .into(),
-            y: (self.state_machine.context().position.y + sprite.sprite_source_size.y as i16)
-                .into(),
-            width: sprite.frame.w.into(),
-            height: sprite.frame.h.into(),
+    fn save_clip(&self) -> Result<()> {
+        if let Some(recorder) = &self.recorder {
+            let meters = self.distance / PIXELS_PER_METER;
+            recorder.export(&format!("walk-the-dog-{}m.webm", meters))?;
         }
+        Ok(())
     }
 
-    fn bounding_box(&self) -> Rect {
-        const X_OFFSET: i16 = 18;
-        const Y_OFFSET: i16 = 14;
-        const WIDTH_OFFSET: i16 = 28;
-        let mut bounding_box = self.destination_box();
-        bounding_box.x += X_OFFSET;
-        bounding_box.width -= WIDTH_OFFSET;
-        bounding_box.y += Y_OFFSET;
-        bounding_box.height -= Y_OFFSET;
-        bounding_box
+    fn draw_world(&self, renderer: &Renderer, camera: &Camera) -> Result<()> {
+        renderer.with_camera(camera, |renderer| -> Result<()> {
+            for layer in Layer::ALL.into_iter().filter(|layer| layer.in_world()) {
+                self.draw_layer(renderer, camera, layer)?;
+                self.scene.draw(
+                    renderer,
+                    camera,
+                    layer,
+                    &DrawOptions {
+                        tint: self.themes.tile_tint(),
+                        ..DrawOptions::default()
+                    },
+                );
+            }
+            if let Some(editor) = &self.editor {
+                editor.draw(renderer);
+            }
+            self.draw_lighting(renderer, camera)
+        })
     }
 
-    fn walking_speed(&self) -> i16 {
-        self.state_machine.context().velocity.x
+    // What the Walk draws itself on each layer, under that layer's scene
+    // entities.
+    fn draw_layer(&self, renderer: &Renderer, camera: &Camera, layer: Layer) -> Result<()> {
+        match layer {
+            Layer::Background => self
+                .backgrounds
+                .iter()
+                .filter(|background| camera.is_visible(background.bounding_box()))
+                .for_each(|background| {
+                    // A background bought in the shop goes over each theme's
+                    // own, taking on its tint as the themes fade.
+                    for (theme, alpha) in self.themes.layers() {
+                        let options = DrawOptions {
+                            alpha,
+                            tint: theme.tint(),
+                            ..DrawOptions::default()
+                        };
+                        for image in [theme.background(), self.shop.background()]
+                            .into_iter()
+                            .flatten()
+                        {
+                            renderer.draw_entire_image(image, &background.position(), &options);
+                        }
+                    }
+                }),
+            Layer::Terrain => {
+                self.terrain.draw(renderer, &camera.view(), self.distance);
+                self.signposts.draw(renderer, camera);
+            }
+            Layer::Entities => {
+                let options = DrawOptions {
+                    tint: self.themes.tile_tint(),
+                    ..DrawOptions::default()
+                };
+                self.obstacles
+                    .iter()
+                    .filter(|obstacle| {
+                        obstacle
+                            .bounding_boxes()
+                            .iter()
+                            .any(|bounding_box| camera.is_visible(bounding_box))
+                    })
+                    .for_each(|obstacle| obstacle.draw(renderer, &options));
+                self.coins.draw(renderer, camera);
+                self.draw_ghost(renderer)?;
+                for player in &self.players {
+                    player.boy.draw(renderer)?;
+                }
+            }
+            Layer::Foreground => {
+                self.particles.draw(renderer);
+                self.floating_texts.draw(renderer);
+                self.speed_lines.draw(renderer);
+            }
+            Layer::Hud => {
+                self.draw_flash(renderer);
+                self.draw_hud(renderer);
+            }
+        }
+        Ok(())
     }
 
-    fn pos_y(&self) -> i16 {
-        self.state_machine.context().position.y
+    fn race_status(&self) -> String {
+        let key = match self.race.as_ref().map(Race::status) {
+            Some(PeerStatus::Closed) => "race.offline",
+            _ => "race.waiting",
+        };
+        self.locale.tr(key).to_string()
     }
 
-    fn velocity_y(&self) -> i16 {
-        self.state_machine.context().velocity.y
+    fn draw_leaderboard(&self, renderer: &Renderer) {
+        let lines = match &*self.leaderboard.state() {
+            LeaderboardState::Idle => return,
+            LeaderboardState::Loading => vec![self.locale.tr("leaderboard.loading").to_string()],
+            LeaderboardState::Offline => vec![self
+                .locale
+                .tr("leaderboard.offline")
+                .replace("{distance}", &self.history.personal_best().to_string())],
+            LeaderboardState::Loaded(entries) => {
+                std::iter::once(self.locale.tr("leaderboard.title").to_string())
+                    .chain(entries.iter().enumerate().map(|(rank, entry)| {
+                        format!("{}. {}  {}", rank + 1, entry.name, entry.score)
+                    }))
+                    .collect()
+            }
+        };
+        for (line, text) in lines.iter().enumerate() {
+            renderer.draw_text(
+                text,
+                &Point {
+                    x: WIDTH / 2,
+                    y: 350 + line as i16 * 20,
+                },
+                &LEADERBOARD_TEXT,
+            );
+        }
     }
 
-    fn update(&mut self) {
-        self.state_machine = self.state_machine.update();
+    fn reset_players(&mut self) {
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.boy.reset();
+            player.boy.shift(index as i16 * PLAYER_TWO_OFFSET);
+            player.home = player.boy.pos_x();
+            player.lives = self.config.lives;
+        }
     }
 
-    fn run_right(&mut self) {
-        self.state_machine = self.state_machine.transition(Event::Run);
+    fn run_players(&mut self) {
+        self.players
+            .iter_mut()
+            .for_each(|player| player.boy.run_right());
     }
 
-    fn slide(&mut self) {
-        self.state_machine = self.state_machine.transition(Event::Slide);
+    fn start_coop(&mut self) {
+        if self.players.len() == 1 {
+            self.players.push(Player::new(
+                self.players[0].boy.clone(),
+                &self.settings,
+                1,
+                self.config.lives,
+            ));
+        }
+        self.reset_run();
+        self.run_players();
     }
 
-    fn jump(&mut self) {
-        self.state_machine = self.state_machine.transition(Event::Jump);
+    // Back to the title screen for an ordinary one-player run, or in a race
+    // for the next one.
+    fn restart(&mut self) {
+        self.daily.leave();
+        self.players.truncate(1);
+        if let Some(race) = &mut self.race {
+            race.rematch();
+        }
+        self.reset_run();
     }
 
-    fn knock_out(&mut self) {
-        self.state_machine = self.state_machine.transition(Event::KnockOut);
+    fn reset_run(&mut self) {
+        self.scheduled.clear();
+        self.reset_players();
+        let background_width = self.backgrounds[0].bounding_box().width;
+        self.backgrounds[0].set_x(0);
+        self.backgrounds[1].set_x(background_width);
+        self.particles = ParticleSystem::new();
+        self.floating_texts = FloatingTexts::default();
+        self.speed_lines = SpeedLines::default();
+        self.signposts = Signposts::new(&self.config.signposts);
+        self.coins = Coins::new();
+        self.obstacles.clear();
+        self.scroll = Fixed::default();
+        self.open_course();
+        self.coins_collected = 0;
+        self.banked = 0;
+        self.combo = Combo::new();
+        self.stamina = Stamina::new();
+        self.time_scale.reset();
+        self.score = 0;
+        self.shown_score = Tween::default();
+        self.seed = self.run_seed();
+        self.rng = Rng::new(self.seed);
+        self.shared = false;
+        self.tick = 0;
+        self.leaderboard.reset();
+        self.recording = Some(GhostTrace::default());
+        self.checkpoint = None;
+        self.showing_stats = false;
+        self.run_stats = RunStats::default();
+        self.recorded = None;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.resume();
+        }
+        self.distance = self.start_distance();
     }
 
-    fn land_on(&mut self, position: i16) {
-        self.state_machine = self.state_machine.transition(Event::Land(position));
+    fn start_distance(&self) -> i32 {
+        self.launch.start.unwrap_or(0).min(MAX_START_METERS) * PIXELS_PER_METER
     }
-}
 
-struct Platform {
-    sheet: Sheet,
-    image: HtmlImageElement,
-    position: Point,
-}
+    fn update_tutorial(&mut self) -> Result<()> {
+        let boy = &self.players[0].boy;
+        if boy.kind() == StateKind::Idle || self.is_game_over() {
+            return Ok(());
+        }
+        let front = boy.bounding_box()?.right();
+        self.tutorial.update(
+            self.gap_to::<Barrier>(front),
+            self.gap_to::<Platform>(front),
+            boy.kind().is_airborne(),
+        );
+        Ok(())
+    }
 
-impl Platform {
-    fn new(sheet: Sheet, image: HtmlImageElement, position: Point) -> Self {
-        Platform {
-            sheet,
-            image,
-            position,
+    // From `front` to the left edge of the nearest obstacle of type `T` at or
+    // past it.
+    fn gap_to<T: Obstacle>(&self, front: i16) -> i16 {
+        self.obstacles
+            .iter()
+            .filter(|obstacle| obstacle.as_any().is::<T>())
+            .flat_map(|obstacle| obstacle.bounding_boxes())
+            .map(|bounding_box| bounding_box.x - front)
+            .filter(|gap| *gap >= 0)
+            .min()
+            .unwrap_or(i16::MAX)
+    }
+
+    fn update_checkpoint(&mut self) {
+        let every = self.config.checkpoints.every * PIXELS_PER_METER;
+        let last = self
+            .checkpoint
+            .as_ref()
+            .map_or(0, |checkpoint| checkpoint.distance);
+        if self.distance / every > last / every && self.lead().kind() == StateKind::Running {
+            self.checkpoint = Some(Checkpoint {
+                distance: self.distance,
+                backgrounds: [
+                    self.backgrounds[0].position().x,
+                    self.backgrounds[1].position().x,
+                ],
+                obstacles: self.obstacles.clone(),
+                course: self.course.clone(),
+                rng: self.rng,
+                signposts: self.signposts.clone(),
+                coins: self.coins.clone(),
+            });
         }
     }
 
-    fn draw(&self, renderer: &Renderer) {
-        let platform = self
-            .sheet
-            .frames
-            .get("13.png")
-            .expect("13.png does not exist");
+    // What the walk does about something that happened in the world this
+    // tick, beyond the rules themselves.
+    fn react(&mut self, event: WorldEvent) -> Result<()> {
+        match event {
+            WorldEvent::Exhausted => {
+                if let Some(cues) = &self.cues {
+                    cues.play(Cue::Exhausted, &self.settings);
+                }
+            }
+            WorldEvent::Bounced => {
+                if let Some(cues) = &self.cues {
+                    cues.play(Cue::Bounce, &self.settings);
+                }
+            }
+            WorldEvent::Hit {
+                player,
+                knocked_out,
+            } => self.hit(player, knocked_out),
+            WorldEvent::NearMiss { player } => {
+                self.flourish_near_miss(player)?;
+                self.run_stats.record_near_miss();
+                self.award(ComboEvent::NearMiss);
+                let (scale, frames) = NEAR_MISS_SLOW_MOTION;
+                self.time_scale.slow(scale, frames);
+            }
+            WorldEvent::Landed { player } => {
+                self.run_stats.record_landing();
+                feedback::trigger(Haptic::Landing, player, &self.settings);
+                self.award(ComboEvent::Landing);
+            }
+        }
+        Ok(())
+    }
 
-        renderer.draw_image(
-            &self.image,
-            &Rect {
-                x: platform.frame.x.into(),
-                y: platform.frame.y.into(),
-                width: (platform.frame.w * 3).into(),
-                height: platform.frame.h.into(),
-            },
-            &self.destination_box(),
+    fn hit(&mut self, index: usize, knocked_out: bool) {
+        feedback::trigger(Haptic::Collision, index, &self.settings);
+        if knocked_out {
+            feedback::trigger(Haptic::Knockout, index, &self.settings);
+            let (scale, frames) = KNOCK_OUT_SLOW_MOTION;
+            self.time_scale.slow(scale, frames);
+        }
+        self.combo.break_chain();
+        self.flash = Timer::after(HIT_FLASH_FRAMES);
+        if self.settings.screen_shake && !self.settings.reduced_motion {
+            self.camera.shake(HIT_SHAKE);
+        }
+    }
+
+    fn share_challenge(&mut self) {
+        match self
+            .launch
+            .challenge_url(self.seed)
+            .and_then(|url| browser::copy_to_clipboard(&url))
+        {
+            Ok(()) => self.shared = true,
+            Err(err) => warn!("Could not share the run: {:?}", err),
+        }
+    }
+
+    fn run_seed(&self) -> u32 {
+        if let Some(seed) = self.race.as_ref().and_then(Race::seed) {
+            return seed;
+        }
+        self.daily
+            .seed()
+            .or(self.launch.seed)
+            .unwrap_or_else(browser::random_seed)
+    }
+
+    fn start_daily(&mut self) {
+        let today = browser::today_utc();
+        let best = DailyBest::load(&today).unwrap_or_else(|err| {
+            warn!("Could not load the daily best: {:?}", err);
+            DailyBest::fresh(&today)
+        });
+        self.daily.start(best);
+        self.reset_run();
+        self.run_players();
+    }
+
+    // Chimes, shows the distance over the lead and sends speed lines across
+    // the screen together.
+    fn reach_milestone(&mut self, meters: i32) -> Result<()> {
+        const TEXT_ABOVE_FEET: i16 = 130;
+        let feet = self.lead().feet()?;
+        (
+            self.cues.as_ref().map(|cues| Chime {
+                cues,
+                settings: &self.settings,
+            }),
+            (
+                MilestoneText {
+                    texts: &mut self.floating_texts,
+                    origin: Point {
+                        x: feet.x,
+                        y: feet.y - TEXT_ABOVE_FEET,
+                    },
+                },
+                (!self.settings.reduced_motion).then_some(&mut self.speed_lines),
+            ),
+        )
+            .on_milestone(meters);
+        Ok(())
+    }
+
+    fn open_course(&mut self) {
+        self.course = Course::open(&self.segments);
+        self.spawn_due_segments();
+    }
+
+    fn spawn_due_segments(&mut self) {
+        let obstacles = self.course.spawn_due(
+            &self.segments,
+            self.distance,
+            &self.terrain,
+            &mut self.coins,
         );
+        self.obstacles.extend(obstacles);
+    }
+
+    fn draw_minimap(&self, renderer: &Renderer) {
+        let ahead = self.course.ahead(&self.segments);
+        minimap::draw(
+            renderer,
+            &self.hud.place(Anchor::BottomCenter, Point { x: 0, y: 10 }),
+            &ahead,
+        );
+    }
 
-        for bounding_box in &self.bounding_boxes() {
-            renderer.draw_rect(bounding_box);
+    // Only uninterrupted runs that beat the current ghost replace it.
+    fn keep_ghost_if_best(&mut self) {
+        if self.bot.is_some() {
+            return;
+        }
+        let best = self.ghost.as_ref().map_or(0, GhostTrace::distance);
+        if let Some(recording) = self.recording.take() {
+            if recording.distance() > best {
+                if let Err(err) = recording.save() {
+                    warn!("Could not save the ghost: {:?}", err);
+                }
+                self.ghost = Some(recording);
+            }
         }
     }
 
-    fn destination_box(&self) -> Rect {
-        let platform = self
-            .sheet
-            .frames
-            .get("13.png")
-            .expect("13.png does not exist");
-
-        Rect {
-            x: self.position.x.into(),
-            y: self.position.y.into(),
-            width: (platform.frame.w * 3).into(),
-            height: platform.frame.h.into(),
-        }
-    }
-
-    fn bounding_boxes(&self) -> Vec<Rect> {
-        const X_OFFSET: i16 = 60;
-        const END_HEIGHT: i16 = 54;
-        let destination_box = self.destination_box();
-        let bounding_box_one = Rect {
-            x: destination_box.x,
-            y: destination_box.y,
-            width: X_OFFSET,
-            height: END_HEIGHT,
-        };
-        let bounding_box_two = Rect {
-            x: destination_box.x + X_OFFSET,
-            y: destination_box.y,
-            width: destination_box.width - (X_OFFSET * 2),
-            height: destination_box.height,
-        };
-        let bounding_box_three = Rect {
-            x: destination_box.x + destination_box.width - X_OFFSET,
-            y: destination_box.y,
-            width: X_OFFSET,
-            height: END_HEIGHT,
+    fn draw_ghost(&self, renderer: &Renderer) -> Result<()> {
+        let rival = self.race.as_ref().and_then(Race::rival);
+        let recorded = self
+            .ghost
+            .as_ref()
+            .and_then(|ghost| ghost.at(self.tick))
+            .copied();
+        for sample in rival.into_iter().chain(recorded) {
+            let offset = (sample.distance() - self.distance)
+                .clamp(i32::from(-WIDTH), i32::from(WIDTH)) as i16;
+            self.players[0].boy.draw_ghost(renderer, &sample, offset)?;
+        }
+        Ok(())
+    }
+
+    fn award(&mut self, event: ComboEvent) {
+        self.score += self.combo.register(event);
+        self.run_stats.record_combo(self.combo.chain());
+    }
+
+    fn flourish_near_miss(&mut self, index: usize) -> Result<()> {
+        let boy = self.players[index].boy.bounding_box()?;
+        self.floating_texts.spawn(
+            self.locale.tr("near_miss").to_string(),
+            Point {
+                x: boy.x + boy.width / 2,
+                y: boy.y,
+            },
+        );
+        if !self.settings.reduced_motion {
+            self.particles.spawn(
+                Point {
+                    x: boy.right(),
+                    y: boy.y + boy.height / 2,
+                },
+                &SPARKS,
+            );
+        }
+        Ok(())
+    }
+
+    // Not in a race, where the rival's copy of the run couldn't follow.
+    fn can_continue(&self) -> bool {
+        self.race.is_none()
+            && self.checkpoint.is_some()
+            && self.coins_collected >= self.config.checkpoints.continue_cost
+    }
+
+    fn continue_from_checkpoint(&mut self) {
+        let Some(checkpoint) = self.checkpoint.as_ref().filter(|_| self.can_continue()) else {
+            return;
         };
+        let cost = self.config.checkpoints.continue_cost;
+        self.coins_collected -= cost;
+        if self.bot.is_none() {
+            self.banked -= cost;
+            self.shop.withdraw(cost);
+        }
+        self.distance = checkpoint.distance;
+        self.backgrounds[0].set_x(checkpoint.backgrounds[0]);
+        self.backgrounds[1].set_x(checkpoint.backgrounds[1]);
+        self.obstacles = checkpoint.obstacles.clone();
+        self.course = checkpoint.course.clone();
+        self.rng = checkpoint.rng;
+        self.signposts = checkpoint.signposts.clone();
+        self.coins = checkpoint.coins.clone();
+        self.particles = ParticleSystem::new();
+        self.showing_stats = false;
+        self.combo.break_chain();
+        self.recording = None;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.resume();
+        }
+        self.reset_players();
+        self.run_players();
+    }
 
-        vec![bounding_box_one, bounding_box_two, bounding_box_three]
+    // Whatever stopped the player who got furthest.
+    fn cause(&self) -> Option<Cause> {
+        self.players
+            .iter()
+            .filter_map(|player| player.boy.cause())
+            .max_by_key(|cause| cause.meters)
     }
-}
 
-pub struct Walk {
-    boy: RedHatBoy,
-    backgrounds: [engine::Image; 2],
-    stone: engine::Image,
-    platform: Platform,
-}
+    fn finish_run(&mut self) {
+        self.telemetry.record(GameEvent::KnockedOut {
+            meters: self.distance / PIXELS_PER_METER,
+            score: self.score,
+        });
+        // Runs bent by ?start, ?speed or ?seed can't be compared with the
+        // others, so they stay out of every record.
+        if self.launch.is_practice() {
+            self.recording = None;
+        } else {
+            self.record_run();
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.knock_out();
+        }
+        if self.bot.is_none() {
+            self.shop.deposit(self.coins_collected - self.banked);
+            self.banked = self.coins_collected;
+        }
+        if let Err(err) = self.shop.save() {
+            warn!("Could not save the wardrobe: {:?}", err);
+        }
+    }
 
-impl Walk {
-    fn velocity(&self) -> i16 {
-        -self.boy.walking_speed()
+    // The daily best, the leaderboard, run history, lifetime stats and the
+    // ghost.
+    fn record_run(&mut self) {
+        if let Some(daily) = self.daily.best_mut() {
+            if daily.record(self.score) {
+                if let Err(err) = daily.save() {
+                    warn!("Could not save the daily best: {:?}", err);
+                }
+            }
+        }
+        let meters = self.distance / PIXELS_PER_METER;
+        let cause = self.cause().map(|cause| cause.hazard);
+        // A run goes on the leaderboard once, as it stood when first knocked
+        // out; continuing it costs coins and doesn't send it again.
+        if self.bot.is_none() && self.recorded.is_none() {
+            self.leaderboard.submit(self.score, meters);
+        }
+        let meters = match self.recorded {
+            None => {
+                self.history.record(meters);
+                self.lifetime.record(meters, cause);
+                meters
+            }
+            Some(before) => {
+                let meters = meters.max(before);
+                self.history.record_continued(meters);
+                self.lifetime.record_continued(meters - before, cause);
+                meters
+            }
+        };
+        self.recorded = Some(meters);
+        self.keep_ghost_if_best();
+        if let Err(err) = self.history.save() {
+            warn!("Could not save run history: {:?}", err);
+        }
+        if let Err(err) = self.lifetime.save() {
+            warn!("Could not save lifetime stats: {:?}", err);
+        }
+    }
+
+    fn draw_stats(&self, renderer: &Renderer) {
+        let best = self.history.personal_best();
+        let distances: Vec<f64> = self
+            .history
+            .distances()
+            .iter()
+            .map(|distance| f64::from(*distance))
+            .collect();
+        let trend = self.history.trend();
+        let chart = Chart::new(
+            Rect {
+                x: 50,
+                y: 80,
+                width: WIDTH - 100,
+                height: 360,
+            },
+            MAX_RUNS,
+            f64::from(best) * 1.1,
+        );
+
+        renderer.draw_text(
+            &self
+                .locale
+                .tr("stats.title")
+                .replace("{count}", &MAX_RUNS.to_string()),
+            &Point { x: 50, y: 50 },
+            &HUD_TEXT,
+        );
+        chart.draw_axes(renderer);
+        chart.draw_bars(renderer, &distances, STATS_BAR);
+        chart.draw_reference(renderer, f64::from(best), STATS_BEST);
+        chart.draw_line(renderer, &trend, STATS_TREND);
+        if let Some(best_index) = self.history.personal_best_index() {
+            chart.highlight(renderer, best_index, f64::from(best), STATS_BEST);
+        }
+        renderer.draw_text(
+            &self
+                .locale
+                .tr("stats.best")
+                .replace("{distance}", &best.to_string()),
+            &Point { x: 50, y: 490 },
+            &HUD_TEXT,
+        );
+        if let Some(average) = trend.last() {
+            renderer.draw_text(
+                &self
+                    .locale
+                    .tr("stats.average")
+                    .replace("{distance}", &(*average as i32).to_string()),
+                &Point { x: 50, y: 520 },
+                &HUD_TEXT,
+            );
+        }
+        renderer.draw_text(
+            self.locale.tr("prompt.back"),
+            &Point { x: 50, y: 570 },
+            &HUD_TEXT,
+        );
     }
 }
 
 pub enum WalkTheDog {
-    Loading,
-    Loaded(Walk),
+    // Whatever has loaded is kept, so retrying after a failure only fetches
+    // what failed.
+    Loading(LaunchOptions, RefCell<Option<Rc<AssetStore>>>),
+    Loaded(Box<Walk>),
 }
 
 impl WalkTheDog {
-    pub fn new() -> Self {
-        WalkTheDog::Loading
+    pub fn new(options: LaunchOptions) -> Self {
+        WalkTheDog::Loading(options, RefCell::default())
     }
 }
 
-const LOW_PLATFORM: i16 = 420;
-const HIGH_PLATFORM: i16 = 375;
-const FIRST_PLATFORM: i16 = 370;
+const PIT_BOTTOM: i16 = HEIGHT * 3;
 
 #[async_trait(?Send)]
 impl Game for WalkTheDog {
     async fn initialize(&self) -> Result<Box<dyn Game>> {
         match self {
-            WalkTheDog::Loading => {
-                let sheet = Some(
-                    serde_wasm_bindgen::from_value(browser::fetch_json("/static/rhb.json").await?)
-                        .unwrap(),
-                );
-                let platform_sheet = Some(
-                    serde_wasm_bindgen::from_value(
-                        browser::fetch_json("/static/tiles.json").await?,
-                    )
-                    .unwrap(),
-                );
-                let platform = Platform::new(
-                    platform_sheet
-                        .clone()
-                        .ok_or_else(|| anyhow!("No Platform Sheet Present"))?,
-                    engine::load_image("/static/tiles.png").await?,
-                    Point {
-                        x: FIRST_PLATFORM,
-                        y: LOW_PLATFORM,
-                    },
-                );
-                let background = engine::load_image("/static/BG.png").await?;
-                let stone = engine::load_image("static/Stone.png").await?;
-                let image = Some(engine::load_image("/static/rhb.png").await?);
+            WalkTheDog::Loading(options, loaded) => {
+                let cached = loaded.borrow().clone();
+                let assets = match cached {
+                    Some(assets) => assets,
+                    None => {
+                        let assets = Rc::new(
+                            AssetStore::load(
+                                Loader::new(RetryPolicy::default())?,
+                                "/static/assets.json",
+                            )
+                            .await?,
+                        );
+                        *loaded.borrow_mut() = Some(Rc::clone(&assets));
+                        assets
+                    }
+                };
+                let segments = SegmentLibrary::load(&assets).await?;
+                let background = assets.image("background").await?;
+                let mut scene = Scene::new();
+                scene.add(Foliage::new());
+                let mut shop =
+                    Shop::load(&assets, assets.image_in(Scope::Character, "rhb").await?).await?;
+                if let Some(character) = &options.character {
+                    if let Err(err) = shop.try_on(character) {
+                        warn!("Could not wear ?character={}: {:?}", character, err);
+                    }
+                }
                 let rhb = RedHatBoy::new(
-                    sheet.clone().ok_or_else(|| anyhow!("No Sheet Present"))?,
-                    image.clone().ok_or_else(|| anyhow!("No Image Present"))?,
-                );
+                    assets.sheet_in(Scope::Character, "rhb").await?,
+                    shop.skin(),
+                    Hitboxes::load(&assets, Scope::Character, "rhb").await?,
+                )?;
                 let background_width = background.width() as i16;
-                Ok(Box::new(WalkTheDog::Loaded(Walk {
-                    boy: rhb,
+                let seed = options.seed.unwrap_or_else(browser::random_seed);
+                let mut config = GameConfig::default();
+                if let Some(speed) = options.speed {
+                    config.speed = config.speed.scaled(speed);
+                }
+                let locale = Locale::load(&assets).await;
+                let themes = Themes::load(&assets).await?;
+                let digits = DigitStrip::new(assets.image("digits").await?);
+                let (music, cues) = match Mixer::new() {
+                    Ok(mixer) => (
+                        Music::load(&assets, mixer.clone())
+                            .await
+                            .map_err(|err| warn!("Playing without music: {:#}", err))
+                            .ok(),
+                        Cues::load(&assets, mixer)
+                            .await
+                            .map_err(|err| warn!("Playing without sound effects: {:#}", err))
+                            .ok(),
+                    ),
+                    Err(err) => {
+                        warn!("Playing without sound: {:#}", err);
+                        (None, None)
+                    }
+                };
+                let terrain = Terrain::load(&assets).await?;
+                if browser::has_query_flag("bench").unwrap_or(false) {
+                    bench::log_browser_timings(&mut HotPaths::new(&rhb, &segments, &terrain))?;
+                }
+                let bot = browser::has_query_flag("embed")
+                    .unwrap_or(false)
+                    .then(Bot::new);
+                let race = match browser::query_value("race") {
+                    Ok(Some(room)) if bot.is_none() => {
+                        Some(Race::join(config.race_endpoint, config.ice_servers, room))
+                    }
+                    Ok(_) => None,
+                    Err(err) => {
+                        warn!("Could not read the race room: {:?}", err);
+                        None
+                    }
+                };
+                let mut settings = Settings::load().unwrap_or_else(|err| {
+                    warn!("Could not load settings: {:?}", err);
+                    Settings::default()
+                });
+                settings.muted = options.mute;
+                let offline = bot
+                    .is_none()
+                    .then(|| OfflineCache::register(config.service_worker));
+                let editor = if bot.is_none() && browser::has_query_flag("editor").unwrap_or(false)
+                {
+                    Some(Editor::open(&assets, &segments).await)
+                } else {
+                    assets.unload(Scope::Editor);
+                    None
+                };
+                let recorder = if bot.is_some() {
+                    None
+                } else {
+                    Recorder::start(&browser::canvas()?)
+                        .map_err(|err| {
+                            warn!("Clips are unavailable: {:?}", err);
+                        })
+                        .ok()
+                };
+                let (history, lifetime, ghost, tutorial) = if bot.is_some() {
+                    (
+                        RunHistory::default(),
+                        LifetimeStats::default(),
+                        None,
+                        Tutorial::done(),
+                    )
+                } else {
+                    (
+                        RunHistory::load().unwrap_or_else(|err| {
+                            warn!("Could not load run history: {:?}", err);
+                            RunHistory::default()
+                        }),
+                        LifetimeStats::load().unwrap_or_else(|err| {
+                            warn!("Could not load lifetime stats: {:?}", err);
+                            LifetimeStats::default()
+                        }),
+                        GhostTrace::load().unwrap_or_else(|err| {
+                            warn!("Could not load the ghost: {:?}", err);
+                            None
+                        }),
+                        Tutorial::load().unwrap_or_else(|err| {
+                            warn!("Could not load the tutorial: {:?}", err);
+                            Tutorial::done()
+                        }),
+                    )
+                };
+                if cfg!(debug_assertions) {
+                    STRICT_TRANSITIONS.store(
+                        browser::has_query_flag("strict").unwrap_or(false),
+                        Ordering::Relaxed,
+                    );
+                }
+                let mut walk = Walk {
+                    players: vec![Player::new(rhb, &settings, 0, config.lives)],
                     backgrounds: [
                         engine::Image::new(background.clone(), Point { x: 0, y: 0 }),
                         engine::Image::new(
@@ -689,73 +3320,435 @@ impl Game for WalkTheDog {
                             },
                         ),
                     ],
-                    stone: engine::Image::new(stone, Point { x: 150, y: 546 }),
-                    platform,
-                })))
+                    scene,
+                    obstacles: vec![],
+                    segments,
+                    course: Course::default(),
+                    scroll: Fixed::default(),
+                    particles: ParticleSystem::new(),
+                    floating_texts: FloatingTexts::default(),
+                    speed_lines: SpeedLines::default(),
+                    signposts: Signposts::new(&config.signposts),
+                    coins: Coins::new(),
+                    coins_collected: 0,
+                    banked: 0,
+                    combo: Combo::new(),
+                    stamina: Stamina::new(),
+                    shown_score: Tween::default(),
+                    photo: None,
+                    digits,
+                    hud: HudLayout::new(),
+                    music,
+                    cues,
+                    game_over_panel: Tween::default(),
+                    score: 0,
+                    daily: DailyMode::default(),
+                    seed,
+                    rng: Rng::new(seed),
+                    shared: false,
+                    tick: 0,
+                    leaderboard: Leaderboard::new(config.leaderboard_endpoint),
+                    race,
+                    ghost,
+                    recording: Some(GhostTrace::default()),
+                    checkpoint: None,
+                    terrain,
+                    themes,
+                    assets: Rc::clone(&assets),
+                    battery: BatteryWatch::new(),
+                    lighting: Lighting::new()?,
+                    camera: Camera::new(
+                        Rect {
+                            x: 0,
+                            y: 0,
+                            width: WIDTH,
+                            height: HEIGHT,
+                        },
+                        config.camera.zoom_ticks,
+                    ),
+                    distance: options.start.unwrap_or(0) * PIXELS_PER_METER,
+                    locale,
+                    history,
+                    lifetime,
+                    recorded: None,
+                    showing_lifetime: false,
+                    tutorial,
+                    showing_stats: false,
+                    run_stats: RunStats::default(),
+                    recorder,
+                    settings,
+                    launch: options.clone(),
+                    paused: false,
+                    settings_menu: None,
+                    shop,
+                    shop_menu: None,
+                    ui: Ui::new(),
+                    flash: Timer::default(),
+                    scheduled: Scheduler::new(),
+                    time_scale: TimeScale::default(),
+                    editor,
+                    hitbox_editor: browser::has_query_flag("hitboxes")
+                        .unwrap_or(false)
+                        .then(HitboxEditor::new),
+                    bot,
+                    spectator: browser::find_canvas(SPECTATOR_CANVAS)?
+                        .map(|canvas| Mirror::new(&canvas))
+                        .transpose()?,
+                    profiling: browser::has_query_flag("profile").unwrap_or(false),
+                    offline,
+                    telemetry: telemetry::from_config(config.telemetry_endpoint),
+                    config,
+                };
+                walk.open_course();
+                assets.finish_loading();
+                Ok(Box::new(WalkTheDog::Loaded(Box::new(walk))))
             }
             WalkTheDog::Loaded(_) => Err(anyhow!("Error: Game is already initialized!")),
         }
     }
-    fn update(&mut self, keystate: &KeyState) {
+    fn update(&mut self, keystate: &KeyState) -> Result<()> {
         if let WalkTheDog::Loaded(walk) = self {
-            if keystate.is_pressed("ArrowDown") {
-                walk.boy.slide();
+            walk.ui.begin(keystate);
+            walk.hud.update();
+            walk.battery.update();
+            for player in &mut walk.players {
+                player
+                    .actions
+                    .update(keystate, &player.inputs, &player.bindings);
+            }
+            let was_game_over = walk.is_game_over();
+            let was_idle = walk.lead().kind() == StateKind::Idle;
+            let score_before = walk.score;
+            if walk.bot.is_none() {
+                if let Err(err) = walk.capture(keystate) {
+                    warn!("Could not capture: {:?}", err);
+                }
+            }
+            if walk.editor.is_some() && walk.action_pressed(Action::Pause) {
+                walk.leave_editor();
+                return Ok(());
+            }
+            if let Some(editor) = &mut walk.editor {
+                editor.update(
+                    keystate,
+                    &walk.camera,
+                    &mut walk.distance,
+                    &walk.segments,
+                    &walk.terrain,
+                );
+                walk.obstacles = editor.obstacles();
+                return Ok(());
+            }
+            if walk.update_shop() {
+                return Ok(());
+            }
+            if walk.showing_lifetime {
+                if walk.action_pressed(Action::Pause) {
+                    walk.showing_lifetime = false;
+                }
+                return Ok(());
             }
-            if keystate.is_pressed("ArrowRight") {
-                walk.boy.run_right();
+            if walk.update_photo()? {
+                return Ok(());
             }
-            if keystate.is_pressed("Space") {
-                walk.boy.jump();
+            if walk.bot.is_none() && walk.update_pause(keystate) {
+                return Ok(());
             }
-            walk.boy.update();
-            walk.platform.position.x += walk.velocity();
-            walk.stone.move_horizontally(walk.velocity());
+            let track = Track {
+                boy: &walk.players[0].boy,
+                segments: &walk.segments,
+                terrain: &walk.terrain,
+                config: &walk.config,
+            };
+            if walk
+                .race
+                .as_mut()
+                .and_then(|race| race.update(&track))
+                .is_some()
+            {
+                walk.reset_run();
+                walk.run_players();
+            }
+            let waiting_for_race = walk.race.as_ref().is_some_and(|race| race.seed().is_none());
+            let mut boost = false;
+            for index in (0..walk.players.len()).filter(|_| !waiting_for_race) {
+                let controls = match walk.bot {
+                    Some(_) => Bot::controls(walk)?,
+                    None => Controls::from_actions(&walk.players[index].actions),
+                };
+                let air_control = walk.config.air_control;
+                walk.players[index].control(&controls, air_control);
+                boost |= controls.boost;
+                if let Some(race) = &mut walk.race {
+                    race.report(controls);
+                }
+            }
+            if walk.bot.is_none()
+                && walk.race.is_none()
+                && walk.players.len() == 1
+                && walk.players[0].boy.kind() == StateKind::Idle
+            {
+                if walk.action_pressed(Action::Daily) {
+                    walk.start_daily();
+                }
+                if walk.action_pressed(Action::Coop) {
+                    walk.start_coop();
+                }
+                if walk.action_pressed(Action::Shop) {
+                    walk.shop_menu = Some(ShopMenu::new());
+                }
+                if walk.action_pressed(Action::Lifetime) {
+                    walk.showing_lifetime = true;
+                }
+            }
+            let meters_before = walk.distance / PIXELS_PER_METER;
+            let events = World {
+                players: &mut walk.players,
+                obstacles: &mut walk.obstacles,
+                course: &mut walk.course,
+                rng: &mut walk.rng,
+                coins: &mut walk.coins,
+                stamina: &mut walk.stamina,
+                scroll: &mut walk.scroll,
+                distance: &mut walk.distance,
+                segments: &walk.segments,
+                terrain: &walk.terrain,
+                config: &walk.config,
+            }
+            .step(
+                boost,
+                &mut Transitions {
+                    particles: &mut walk.particles,
+                    telemetry: walk.telemetry.as_ref(),
+                    run_stats: &mut walk.run_stats,
+                    reduced_motion: walk.settings.reduced_motion,
+                },
+            )?;
             let velocity = walk.velocity();
+            walk.scene.move_horizontally(velocity);
+            walk.scene.update(FIXED_DT);
             let [first_background, second_background] = &mut walk.backgrounds;
-            first_background.move_horizontally(velocity);
-            second_background.move_horizontally(velocity);
+            if !walk.settings.reduced_motion {
+                first_background.move_horizontally(velocity);
+                second_background.move_horizontally(velocity);
+            }
             if first_background.right() < 0 {
                 first_background.set_x(second_background.right());
             }
             if second_background.right() < 0 {
                 second_background.set_x(first_background.right());
             }
+            for event in events {
+                walk.react(event)?;
+            }
 
-            for bounding_box in &walk.platform.bounding_boxes() {
-                if walk.boy.bounding_box().intersects(bounding_box) {
-                    if walk.boy.velocity_y() > 0 && walk.boy.pos_y() < walk.platform.position.y {
-                        walk.boy.land_on(bounding_box.y);
-                    } else {
-                        walk.boy.knock_out();
+            if let Some(bot) = &walk.bot {
+                if !was_game_over && walk.is_game_over() {
+                    walk.scheduled.after(RESTART_FRAMES, Scheduled::Restart);
+                }
+                if bot.should_restart(walk.distance) {
+                    walk.restart();
+                    return Ok(());
+                }
+            } else if walk.is_game_over() {
+                if !was_game_over {
+                    walk.finish_run();
+                    if let Some(music) = &mut walk.music {
+                        music.knockout();
+                    }
+                    let (offset, ticks) = GAME_OVER_DROP;
+                    walk.game_over_panel = Tween::new(offset, 0.0, ticks, Easing::Bounce);
+                }
+                let clicked = if walk.showing_stats {
+                    None
+                } else {
+                    walk.game_over_menu()
+                };
+                if walk.action_pressed(Action::Stats) || clicked == Some(GameOverAction::Stats) {
+                    walk.showing_stats = true;
+                }
+                if walk.action_pressed(Action::Pause) {
+                    walk.showing_stats = false;
+                }
+                if clicked == Some(GameOverAction::Clip) {
+                    if let Err(err) = walk.save_clip() {
+                        warn!("Could not save the clip: {:?}", err);
                     }
                 }
+                if walk.action_pressed(Action::Share) || clicked == Some(GameOverAction::Share) {
+                    walk.share_challenge();
+                }
+                if walk.action_pressed(Action::Continue)
+                    || clicked == Some(GameOverAction::Continue)
+                {
+                    walk.continue_from_checkpoint();
+                }
+                if walk.action_pressed(Action::Restart) || clicked == Some(GameOverAction::Restart)
+                {
+                    walk.restart();
+                }
             }
 
-            if walk
-                .boy
-                .bounding_box()
-                .intersects(walk.stone.bounding_box())
-            {
-                walk.boy.knock_out();
+            let meters_gained = (walk.distance / PIXELS_PER_METER - meters_before).max(0);
+            walk.score +=
+                meters_gained as u32 * walk.combo.multiplier() * walk.stamina.score_multiplier();
+            let meters = walk.distance / PIXELS_PER_METER;
+            if meters / MILESTONE_METERS > meters_before / MILESTONE_METERS {
+                walk.reach_milestone(meters / MILESTONE_METERS * MILESTONE_METERS)?;
+            }
+            walk.update_camera();
+            walk.themes
+                .update(walk.distance / PIXELS_PER_METER, &walk.assets);
+            walk.signposts.update(
+                walk.distance,
+                walk.lead().feet()?.x,
+                walk.velocity(),
+                &walk.config.signposts,
+                &walk.locale,
+            );
+            walk.coins
+                .update(walk.distance, walk.velocity(), &walk.terrain);
+            for index in 0..walk.players.len() {
+                let collected = walk.coins.collect(&walk.players[index].boy.bounding_box()?);
+                walk.coins_collected += collected;
+                for _ in 0..collected {
+                    walk.award(ComboEvent::Coin);
+                }
+            }
+            walk.combo.update();
+            let intensity = walk.intensity();
+            if let Some(music) = &mut walk.music {
+                music.update(intensity, &walk.settings);
+            }
+            walk.update_checkpoint();
+            walk.update_tutorial()?;
+            if was_idle && walk.lead().kind() == StateKind::Running {
+                if let Some(music) = &mut walk.music {
+                    music.start_run();
+                }
+                walk.telemetry.record(GameEvent::RunStarted {
+                    players: walk.players.len(),
+                    daily: walk.daily.best().is_some(),
+                });
+            }
+            if walk.score / SCORE_MILESTONE > score_before / SCORE_MILESTONE {
+                walk.telemetry.record(GameEvent::ScoreMilestone {
+                    score: walk.score / SCORE_MILESTONE * SCORE_MILESTONE,
+                });
+            }
+            let runner = &walk.players[0].boy;
+            if runner.kind() != StateKind::Idle && !runner.kind().is_game_over() {
+                let sample = runner.ghost_sample(walk.distance);
+                if let Some(recording) = &mut walk.recording {
+                    recording.record(walk.tick, sample);
+                }
+            }
+            if walk.lead().kind() != StateKind::Idle && !walk.is_game_over() {
+                walk.tick += 1;
+            }
+            walk.particles.move_horizontally(walk.velocity());
+            walk.particles.update();
+            walk.floating_texts.update();
+            walk.speed_lines.update();
+            walk.shown_score
+                .retarget(f64::from(walk.score), SCORE_COUNT_TICKS, Easing::EaseOut);
+            walk.shown_score.tick();
+            walk.game_over_panel.tick();
+            if let Some(recorder) = &mut walk.recorder {
+                if let Err(err) = recorder.update() {
+                    warn!("Clips are unavailable: {:?}", err);
+                    walk.recorder = None;
+                }
+            }
+            for scheduled in walk.scheduled.tick() {
+                match scheduled {
+                    Scheduled::Restart => walk.restart(),
+                }
             }
         }
+        Ok(())
     }
 
-    fn draw(&self, renderer: &Renderer) {
-        renderer.clear(&Rect {
-            x: 0,
-            y: 0,
-            width: 600,
-            height: 600,
-        });
+    fn time_scale(&self) -> f32 {
+        match self {
+            WalkTheDog::Loaded(walk) if !walk.paused => walk.time_scale.get(),
+            _ => 1.0,
+        }
+    }
+
+    fn debug_controls(&self) -> bool {
+        matches!(self, WalkTheDog::Loaded(walk) if walk.launch.debug)
+    }
+
+    fn half_rate(&self) -> bool {
+        matches!(self, WalkTheDog::Loaded(walk) if walk.settings.saves_battery(walk.battery.is_low()))
+    }
 
+    fn update_unscaled(&mut self) {
         if let WalkTheDog::Loaded(walk) = self {
-            walk.backgrounds.iter().for_each(|background| {
-                background.draw(renderer);
-            });
-            walk.boy.draw(renderer);
-            walk.stone.draw(renderer);
-            walk.platform.draw(renderer);
+            if walk.paused {
+                return;
+            }
+            walk.time_scale.tick();
+            walk.flash.tick();
+        }
+    }
+
+    fn draw(&self, renderer: &Renderer) -> Result<()> {
+        renderer.clear(&renderer.viewport());
+
+        if let WalkTheDog::Loaded(walk) = self {
+            if walk.showing_stats {
+                walk.draw_stats(renderer);
+                return Ok(());
+            }
+            if walk.showing_lifetime {
+                walk.lifetime.draw(renderer, &walk.locale);
+                return Ok(());
+            }
+            renderer.set_hitboxes(
+                walk.settings.hitboxes || (walk.paused && walk.hitbox_editor.is_some()),
+            );
+            renderer.set_palette(walk.settings.palette());
+            renderer.set_scaling(walk.settings.scaling);
+            if let Some(photo) = &walk.photo {
+                renderer.with_filter(photo.filter().css(), |renderer| {
+                    walk.draw_world(renderer, photo.camera())
+                })?;
+                if photo.shows_hint() {
+                    renderer.draw_text(
+                        walk.locale.tr("photo.hint"),
+                        &Point {
+                            x: WIDTH / 2,
+                            y: HEIGHT - 20,
+                        },
+                        &HUD_CAPTION,
+                    );
+                }
+                return Ok(());
+            }
+            walk.draw_world(renderer, &walk.camera)?;
+            if let Some(spectator) = &walk.spectator {
+                renderer.mirror(spectator, &walk.follow_cam()?);
+            }
+            walk.draw_layer(renderer, &walk.camera, Layer::Hud)?;
+            if let Some(editor) = &walk.editor {
+                walk.draw_editor(renderer, editor);
+            }
+            if walk.paused {
+                walk.draw_pause(renderer);
+            }
+            if let Some(menu) = &walk.shop_menu {
+                menu.draw(renderer, &walk.locale, &walk.shop);
+            }
+            walk.ui.draw(renderer);
+            if walk.profiling {
+                walk.draw_profile(renderer);
+            }
+            if let Some(lines) = logging::panel() {
+                walk.draw_log_panel(renderer, &lines);
+            }
         }
+        Ok(())
     }
 }
@@ -0,0 +1,231 @@
+use super::{settings::KeyLayout, WIDTH};
+use crate::{browser, engine::KeyState};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Left,
+    Right,
+    Jump,
+    Slide,
+    Boost,
+    // Also backs out of menus.
+    Pause,
+    Confirm,
+    // Menu rows, and the photo mode camera along with Left and Right.
+    Up,
+    Down,
+    // Shortcuts on the title screen.
+    Daily,
+    Coop,
+    Shop,
+    Lifetime,
+    // Shortcuts on the game-over screen.
+    Stats,
+    Continue,
+    Restart,
+    Share,
+    // From the pause menu, and inside photo mode.
+    Photo,
+    ZoomIn,
+    ZoomOut,
+    Filter,
+}
+
+pub type KeyBindings = [(Action, &'static str)];
+
+pub const PLAYER_ONE_KEYS: &KeyBindings = &[
+    (Action::Left, "ArrowLeft"),
+    (Action::Right, "ArrowRight"),
+    (Action::Slide, "ArrowDown"),
+    (Action::Jump, "Space"),
+    (Action::Boost, "ShiftRight"),
+    (Action::Pause, "Escape"),
+    (Action::Confirm, "Enter"),
+    (Action::Up, "ArrowUp"),
+    (Action::Down, "ArrowDown"),
+];
+
+pub const PLAYER_TWO_KEYS: &KeyBindings = &[
+    (Action::Left, "KeyA"),
+    (Action::Right, "KeyD"),
+    (Action::Slide, "KeyS"),
+    (Action::Jump, "KeyW"),
+    (Action::Boost, "ShiftLeft"),
+    (Action::Pause, "Escape"),
+    (Action::Confirm, "Enter"),
+    (Action::Up, "KeyW"),
+    (Action::Down, "KeyS"),
+];
+
+// Only the lead player drives the title, game-over and pause screens, so
+// only they get these. The title screen's stay off both halves of the
+// keyboard, since running right starts a run from there; the rest are read
+// on screens where the boy isn't steered, so sharing a key is harmless.
+const SHORTCUT_KEYS: &KeyBindings = &[
+    (Action::Daily, "Digit1"),
+    (Action::Coop, "Digit2"),
+    (Action::Shop, "KeyB"),
+    (Action::Lifetime, "KeyH"),
+    (Action::Stats, "KeyS"),
+    (Action::Continue, "KeyC"),
+    (Action::Restart, "KeyR"),
+    (Action::Share, "KeyL"),
+    (Action::Photo, "KeyC"),
+    (Action::ZoomIn, "Equal"),
+    (Action::ZoomOut, "Minus"),
+    (Action::Filter, "KeyF"),
+];
+
+// Button indices in the browser's standard gamepad layout. Each button does
+// one thing, so confirming in a menu never also jumps.
+const GAMEPAD_BUTTONS: [(Action, u32); 10] = [
+    (Action::Jump, 0),
+    (Action::Slide, 1),
+    (Action::Confirm, 2),
+    (Action::Photo, 3),
+    (Action::Boost, 7),
+    (Action::Pause, 9),
+    (Action::Up, 12),
+    (Action::Down, 13),
+    (Action::Left, 14),
+    (Action::Right, 15),
+];
+const STICK_DEAD_ZONE: f64 = 0.5;
+
+// The key and gamepad button each action is on for one player. An action
+// has at most one of each; rebinding it moves it rather than adding another.
+#[derive(Clone, Default)]
+pub struct Bindings {
+    keys: HashMap<Action, String>,
+    buttons: HashMap<Action, u32>,
+}
+
+impl Bindings {
+    // The defaults for player `index` on `layout`: their half of the
+    // keyboard, the standard gamepad buttons and, for the lead, the shortcuts.
+    pub fn for_player(layout: KeyLayout, index: usize) -> Self {
+        let mut bindings = Bindings::default();
+        let shortcuts: &KeyBindings = if index == 0 { SHORTCUT_KEYS } else { &[] };
+        for (action, code) in layout.player_keys(index).iter().chain(shortcuts) {
+            bindings.rebind(*action, code);
+        }
+        for (action, button) in GAMEPAD_BUTTONS {
+            bindings.rebind_button(action, button);
+        }
+        bindings
+    }
+
+    pub fn rebind(&mut self, action: Action, code: &str) {
+        self.keys.insert(action, code.to_string());
+    }
+
+    pub fn rebind_button(&mut self, action: Action, button: u32) {
+        self.buttons.insert(action, button);
+    }
+}
+
+// Anything that can hold actions down for a player. A new device only needs
+// to say which actions it holds and be added in `player_inputs`.
+pub trait InputSource {
+    fn held(&self, keystate: &KeyState, bindings: &Bindings) -> Vec<Action>;
+}
+
+pub struct Keyboard;
+
+impl InputSource for Keyboard {
+    fn held(&self, keystate: &KeyState, bindings: &Bindings) -> Vec<Action> {
+        bindings
+            .keys
+            .iter()
+            .filter(|(_, code)| keystate.is_pressed(code))
+            .map(|(action, _)| *action)
+            .collect()
+    }
+}
+
+pub struct Gamepad {
+    index: u32,
+}
+
+impl InputSource for Gamepad {
+    fn held(&self, _keystate: &KeyState, bindings: &Bindings) -> Vec<Action> {
+        let Some(pad) = browser::gamepad(self.index) else {
+            return vec![];
+        };
+        let mut held: Vec<Action> = bindings
+            .buttons
+            .iter()
+            .filter(|(_, button)| pad.buttons.get(**button as usize) == Some(&true))
+            .map(|(action, _)| *action)
+            .collect();
+        if pad.stick_x < -STICK_DEAD_ZONE {
+            held.push(Action::Left);
+        } else if pad.stick_x > STICK_DEAD_ZONE {
+            held.push(Action::Right);
+        }
+        held
+    }
+}
+
+// A finger on the right half of the screen jumps and one on the left half
+// slides.
+pub struct Touch;
+
+impl InputSource for Touch {
+    fn held(&self, keystate: &KeyState, _bindings: &Bindings) -> Vec<Action> {
+        if !keystate.is_touching() {
+            vec![]
+        } else if keystate.pointer().x < WIDTH / 2 {
+            vec![Action::Slide]
+        } else {
+            vec![Action::Jump]
+        }
+    }
+}
+
+// The sources playing for player `index`: the keyboard, the gamepad with
+// their number and, for player one, the touch screen.
+pub fn player_inputs(index: usize) -> Vec<Box<dyn InputSource>> {
+    let mut inputs: Vec<Box<dyn InputSource>> = vec![
+        Box::new(Keyboard),
+        Box::new(Gamepad {
+            index: index as u32,
+        }),
+    ];
+    if index == 0 {
+        inputs.push(Box::new(Touch));
+    }
+    inputs
+}
+
+// Which actions a player's sources hold, and which started this update.
+#[derive(Default)]
+pub struct ActionState {
+    held: HashSet<Action>,
+    previous: HashSet<Action>,
+}
+
+impl ActionState {
+    // Call once per update.
+    pub fn update(
+        &mut self,
+        keystate: &KeyState,
+        inputs: &[Box<dyn InputSource>],
+        bindings: &Bindings,
+    ) {
+        self.previous = std::mem::take(&mut self.held);
+        self.held = inputs
+            .iter()
+            .flat_map(|input| input.held(keystate, bindings))
+            .collect();
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    pub fn was_just_pressed(&self, action: Action) -> bool {
+        self.is_held(action) && !self.previous.contains(&action)
+    }
+}
@@ -0,0 +1,124 @@
+use super::settings::Settings;
+use crate::engine::{
+    assets::AssetStore,
+    sound::{Layer, Mixer, Sound},
+};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+// How long the intensity layer takes to follow the run, and to drop out
+// when the boy is knocked out.
+const RAMP_SECONDS: f64 = 0.5;
+const CUT_SECONDS: f64 = 0.05;
+// Smaller changes than this aren't worth scheduling another ramp for.
+const LEVEL_STEP: f32 = 0.02;
+// Leaves room under the master volume for the cues.
+const MUSIC_GAIN: f32 = 0.6;
+
+// Sound effects the game asks for at the moment they happen.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Cue {
+    Bounce,
+    Milestone,
+    Exhausted,
+}
+
+const CUE_SOUNDS: [(Cue, &str); 3] = [
+    (Cue::Bounce, "cue.bounce"),
+    (Cue::Milestone, "cue.milestone"),
+    (Cue::Exhausted, "cue.exhausted"),
+];
+
+// The cue sounds, played once each through the music's mixer.
+pub struct Cues {
+    mixer: Mixer,
+    sounds: HashMap<Cue, Sound>,
+}
+
+impl Cues {
+    pub async fn load(assets: &AssetStore, mixer: Mixer) -> Result<Self> {
+        let mut sounds = HashMap::new();
+        for (cue, name) in CUE_SOUNDS {
+            sounds.insert(cue, assets.sound(name, &mixer).await?);
+        }
+        Ok(Cues { mixer, sounds })
+    }
+
+    pub fn play(&self, cue: Cue, settings: &Settings) {
+        let volume = settings.audible_volume();
+        if volume <= 0.0 {
+            return;
+        }
+        let Some(sound) = self.sounds.get(&cue) else {
+            return;
+        };
+        self.mixer.set_volume(volume);
+        self.mixer.resume();
+        if let Err(err) = self.mixer.play(sound) {
+            warn!("Could not play the {:?} cue: {:?}", cue, err);
+        }
+    }
+}
+
+// A base loop that always plays with an intensity layer over it, in step,
+// whose gain follows how hard the run is going. A knockout cuts both to a
+// sting until the next run starts.
+pub struct Music {
+    mixer: Mixer,
+    base: Layer,
+    intensity: Layer,
+    sting: Sound,
+    level: f32,
+    knocked_out: bool,
+}
+
+impl Music {
+    pub async fn load(assets: &AssetStore, mixer: Mixer) -> Result<Self> {
+        let base = assets.sound("music.base", &mixer).await?;
+        let intensity = assets.sound("music.intensity", &mixer).await?;
+        let sting = assets.sound("music.sting", &mixer).await?;
+        let mut layers = mixer
+            .loop_layers(&[(&base, MUSIC_GAIN), (&intensity, 0.0)])?
+            .into_iter();
+        let (Some(base), Some(intensity)) = (layers.next(), layers.next()) else {
+            return Err(anyhow!("The music layers did not start"));
+        };
+        Ok(Music {
+            mixer,
+            base,
+            intensity,
+            sting,
+            level: 0.0,
+            knocked_out: false,
+        })
+    }
+
+    // Call when a run starts, which also unlocks audio in browsers that keep
+    // it suspended until the player has done something.
+    pub fn start_run(&mut self) {
+        self.mixer.resume();
+        self.base.ramp_to(MUSIC_GAIN, RAMP_SECONDS);
+        self.level = 0.0;
+        self.knocked_out = false;
+    }
+
+    // `intensity` runs from 0 for a gentle start to 1 at top speed with the
+    // combo maxed out.
+    pub fn update(&mut self, intensity: f32, settings: &Settings) {
+        self.mixer.set_volume(settings.audible_volume());
+        let intensity = intensity.clamp(0.0, 1.0);
+        if !self.knocked_out && (intensity - self.level).abs() >= LEVEL_STEP {
+            self.level = intensity;
+            self.intensity.ramp_to(intensity * MUSIC_GAIN, RAMP_SECONDS);
+        }
+    }
+
+    pub fn knockout(&mut self) {
+        self.base.ramp_to(0.0, CUT_SECONDS);
+        self.intensity.ramp_to(0.0, CUT_SECONDS);
+        self.knocked_out = true;
+        if let Err(err) = self.mixer.play(&self.sting) {
+            warn!("Could not play the knockout sting: {:?}", err);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use super::{
+    coins::Coins,
+    course::Course,
+    obstacles::{Contact, Obstacle},
+    segments::SegmentLibrary,
+    terrain::Terrain,
+    RedHatBoy, WIDTH,
+};
+use crate::{browser, engine::rng::Rng};
+use anyhow::Result;
+use std::hint::black_box;
+
+// Screens of course planned and built per `generate`.
+const SCREENS: i16 = 20;
+const BROWSER_CALLS: u32 = 1000;
+
+// The per-frame work worth keeping fast, set up on the real course: every
+// segment's obstacles built at once around the boy, so the collision checks
+// see as many contacts as the busiest frames.
+pub struct HotPaths<'a> {
+    boy: RedHatBoy,
+    segments: &'a SegmentLibrary,
+    terrain: &'a Terrain,
+    obstacles: Vec<Box<dyn Obstacle>>,
+}
+
+impl<'a> HotPaths<'a> {
+    pub fn new(boy: &RedHatBoy, segments: &'a SegmentLibrary, terrain: &'a Terrain) -> Self {
+        let mut coins = Coins::new();
+        let obstacles = (0..segments.count())
+            .flat_map(|index| segments.spawn(index, 0, 0, terrain, &mut coins))
+            .collect();
+        HotPaths {
+            boy: boy.clone(),
+            segments,
+            terrain,
+            obstacles,
+        }
+    }
+
+    // Every obstacle against the boy, as each player gets every frame. The
+    // events the contacts queue are dropped so each call starts the same.
+    pub fn collide(&mut self) -> Result<usize> {
+        let mut touching = 0;
+        for obstacle in &self.obstacles {
+            if obstacle.check_intersection(&mut self.boy)? != Contact::None {
+                touching += 1;
+            }
+        }
+        self.boy.events.clear();
+        Ok(touching)
+    }
+
+    // Plans and builds a stretch of course from `seed`, returning how many
+    // obstacles it built.
+    pub fn generate(&self, seed: u32) -> usize {
+        let mut rng = Rng::new(seed);
+        let mut coins = Coins::new();
+        let mut course = Course::open(self.segments);
+        let mut built = 0;
+        for _ in 0..SCREENS {
+            built += course
+                .spawn_due(self.segments, 0, self.terrain, &mut coins)
+                .len();
+            course.extend(-WIDTH, self.segments, &mut rng);
+        }
+        built
+    }
+
+    pub fn frame_name(&self) -> String {
+        self.boy.frame_name()
+    }
+}
+
+// Times each hot path where it matters, in the browser, and logs the mean
+// per call. `?bench` runs it once the assets have loaded.
+pub fn log_browser_timings(hot_paths: &mut HotPaths) -> Result<()> {
+    time("collide", || {
+        black_box(hot_paths.collide()?);
+        Ok(())
+    })?;
+    time("generate", || {
+        black_box(hot_paths.generate(black_box(7)));
+        Ok(())
+    })?;
+    time("frame_name", || {
+        black_box(hot_paths.frame_name());
+        Ok(())
+    })
+}
+
+fn time(name: &str, mut run: impl FnMut() -> Result<()>) -> Result<()> {
+    let start = browser::now()?;
+    for _ in 0..BROWSER_CALLS {
+        run()?;
+    }
+    let micros = (browser::now()? - start) * 1000.0 / f64::from(BROWSER_CALLS);
+    log!("{}: {:.2}µs per call", name, micros);
+    Ok(())
+}
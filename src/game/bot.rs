@@ -0,0 +1,57 @@
+use super::{obstacles::Obstacle, terrain::Terrain, Controls, Walk, PIXELS_PER_METER};
+use crate::engine::Rect;
+use anyhow::Result;
+
+const JUMP_DISTANCE: i16 = 40;
+pub const RESTART_FRAMES: u16 = 90;
+const RUN_METERS: i32 = 800;
+
+// Drives attract runs in embed mode: start running, jump whatever is just
+// ahead, and ask for a fresh run once the run has gone on long enough. The
+// walk schedules its own restart a little while after a knockout.
+pub struct Bot;
+
+impl Bot {
+    pub fn new() -> Self {
+        Bot
+    }
+
+    pub fn controls(walk: &Walk) -> Result<Controls> {
+        Ok(Bot::decide(
+            &walk.players[0].boy.bounding_box()?,
+            &walk.obstacles,
+            &walk.terrain,
+            walk.distance,
+        ))
+    }
+
+    // What to press for a boy at `boy` on screen, with the screen's left
+    // edge at world x `distance`. Right stays held, as a player's would, so
+    // he keeps up with the scroll in the air too.
+    pub fn decide(
+        boy: &Rect,
+        obstacles: &[Box<dyn Obstacle>],
+        terrain: &Terrain,
+        distance: i32,
+    ) -> Controls {
+        let obstacle_ahead = obstacles
+            .iter()
+            .flat_map(|obstacle| obstacle.bounding_boxes())
+            .any(|obstacle| (0..JUMP_DISTANCE).contains(&(obstacle.x - boy.right())));
+        let pit_ahead = (0..JUMP_DISTANCE).any(|ahead| {
+            terrain
+                .ground_at(distance + i32::from(boy.right() + ahead))
+                .is_none()
+        });
+        Controls {
+            run_right: true,
+            steer: 1,
+            jump: obstacle_ahead || pit_ahead,
+            ..Controls::default()
+        }
+    }
+
+    pub fn should_restart(&self, distance: i32) -> bool {
+        distance / PIXELS_PER_METER >= RUN_METERS
+    }
+}
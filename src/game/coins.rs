@@ -0,0 +1,74 @@
+use super::{terrain::Terrain, WIDTH};
+use crate::engine::{style::Color, Camera, Point, Rect, Renderer};
+
+const ROW_EVERY: i32 = 900;
+const ROW_LENGTH: i32 = 5;
+const SPACING: i32 = 40;
+const HOVER: i16 = 90;
+const RADIUS: i16 = 10;
+// Just past the right edge, so rows never pop in on screen.
+const SPAWN_AHEAD: i32 = WIDTH as i32 + 100;
+const COIN_COLOR: Color = Color::rgb(0xF5, 0xC2, 0x1B);
+
+// Rows of coins hovering over the ground. Positions are in screen space and
+// scroll with the world like every other prop.
+#[derive(Clone)]
+pub struct Coins {
+    coins: Vec<Point>,
+    next_row: i32,
+}
+
+impl Coins {
+    pub fn new() -> Self {
+        Coins {
+            coins: vec![],
+            next_row: ROW_EVERY,
+        }
+    }
+
+    pub fn update(&mut self, distance: i32, velocity: i16, terrain: &Terrain) {
+        self.coins.iter_mut().for_each(|coin| coin.x += velocity);
+        self.coins.retain(|coin| coin.x + RADIUS > 0);
+
+        while distance + SPAWN_AHEAD >= self.next_row {
+            for index in 0..ROW_LENGTH {
+                let world_x = self.next_row + index * SPACING;
+                if let Some(ground) = terrain.ground_at(world_x) {
+                    self.coins.push(Point {
+                        x: (world_x - distance) as i16,
+                        y: ground - HOVER,
+                    });
+                }
+            }
+            self.next_row += ROW_EVERY;
+        }
+    }
+
+    // A coin laid out by a course segment rather than the regular rows.
+    pub fn place(&mut self, coin: Point) {
+        self.coins.push(coin);
+    }
+
+    pub fn collect(&mut self, collector: &Rect) -> u32 {
+        let before = self.coins.len();
+        self.coins
+            .retain(|coin| !collector.intersects(&Self::bounding_box(coin)));
+        (before - self.coins.len()) as u32
+    }
+
+    pub fn draw(&self, renderer: &Renderer, camera: &Camera) {
+        self.coins
+            .iter()
+            .filter(|coin| camera.is_visible(&Self::bounding_box(coin)))
+            .for_each(|coin| renderer.fill_circle(coin, RADIUS, COIN_COLOR));
+    }
+
+    fn bounding_box(coin: &Point) -> Rect {
+        Rect {
+            x: coin.x - RADIUS,
+            y: coin.y - RADIUS,
+            width: RADIUS * 2,
+            height: RADIUS * 2,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::engine::{
+    style::{Color, TextAlign, TextStyle},
+    timer::Timer,
+    Point, Rect, Renderer,
+};
+
+const DECAY_FRAMES: u16 = 180;
+const CHAIN_PER_LEVEL: u32 = 3;
+const MAX_MULTIPLIER: u32 = 5;
+const METER_WIDTH: i16 = 120;
+const METER_HEIGHT: i16 = 8;
+const METER_COLOR: Color = Color::rgb(0xE8, 0x5D, 0x2C);
+const METER_BACKGROUND: Color = Color::rgba(0, 0, 0, 0.2);
+const MULTIPLIER_TEXT: TextStyle = TextStyle {
+    font: "bold 16px sans-serif",
+    color: METER_COLOR,
+    align: TextAlign::Right,
+};
+
+#[derive(Clone, Copy)]
+pub enum ComboEvent {
+    NearMiss,
+    Landing,
+    Coin,
+}
+
+impl ComboEvent {
+    fn points(self) -> u32 {
+        match self {
+            ComboEvent::NearMiss => 50,
+            ComboEvent::Landing => 25,
+            ComboEvent::Coin => 10,
+        }
+    }
+}
+
+// Every event extends the chain and refills the meter; the chain is lost
+// when the meter runs dry or the boy gets hit. Each CHAIN_PER_LEVEL events
+// in a row raise the multiplier by one.
+pub struct Combo {
+    chain: u32,
+    meter: Timer,
+}
+
+impl Combo {
+    pub fn new() -> Self {
+        Combo {
+            chain: 0,
+            meter: Timer::default(),
+        }
+    }
+
+    pub fn chain(&self) -> u32 {
+        self.chain
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.chain / CHAIN_PER_LEVEL).min(MAX_MULTIPLIER)
+    }
+
+    // From 0 with no chain up to 1 at the top multiplier.
+    pub fn heat(&self) -> f32 {
+        (self.multiplier() - 1) as f32 / (MAX_MULTIPLIER - 1) as f32
+    }
+
+    pub fn register(&mut self, event: ComboEvent) -> u32 {
+        self.chain += 1;
+        self.meter = Timer::after(DECAY_FRAMES);
+        event.points() * self.multiplier()
+    }
+
+    pub fn break_chain(&mut self) {
+        self.chain = 0;
+        self.meter = Timer::default();
+    }
+
+    pub fn update(&mut self) {
+        if self.meter.tick() {
+            self.chain = 0;
+        }
+    }
+
+    // `top_right` is the top-right corner of the meter.
+    pub fn draw(&self, renderer: &Renderer, top_right: &Point) {
+        if self.chain == 0 {
+            return;
+        }
+        let background = Rect {
+            x: top_right.x - METER_WIDTH,
+            y: top_right.y,
+            width: METER_WIDTH,
+            height: METER_HEIGHT,
+        };
+        renderer.fill_rect(&background, METER_BACKGROUND);
+        renderer.fill_rect(
+            &Rect {
+                width: (f32::from(METER_WIDTH) * self.meter.fraction_left(DECAY_FRAMES)) as i16,
+                ..background
+            },
+            METER_COLOR,
+        );
+        renderer.draw_text(
+            &format!("x{}", self.multiplier()),
+            &Point {
+                x: top_right.x,
+                y: top_right.y + METER_HEIGHT + 18,
+            },
+            &MULTIPLIER_TEXT,
+        );
+    }
+}
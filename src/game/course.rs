@@ -0,0 +1,107 @@
+use super::{
+    coins::Coins,
+    minimap,
+    obstacles::Obstacle,
+    segments::{ObstacleDef, SegmentLibrary},
+    terrain::Terrain,
+    WIDTH,
+};
+use crate::engine::rng::Rng;
+use std::collections::VecDeque;
+
+const GAP: (i16, i16) = (150, 500);
+
+// A segment of the course picked ahead of time, with its left edge at
+// screen x `start`. Its obstacles are built once it gets close, and it is
+// kept until it scrolls out of the minimap's reach.
+#[derive(Clone, Copy)]
+struct Upcoming {
+    start: i16,
+    index: usize,
+    spawned: bool,
+}
+
+// The segments laid out ahead of the boy. They are picked far enough ahead
+// to fill the minimap, spaced by the run's RNG so the same seed always
+// builds the same course, and built a screen before they scroll into view.
+#[derive(Clone, Default)]
+pub struct Course {
+    // Where the course planned so far ends, in screen x.
+    timeline: i16,
+    upcoming: VecDeque<Upcoming>,
+}
+
+impl Course {
+    // Starts with the library's opening segment at the left edge.
+    pub fn open(segments: &SegmentLibrary) -> Self {
+        let mut course = Course::default();
+        course.plan(segments, segments.opening());
+        course
+    }
+
+    pub fn extend(&mut self, velocity: i16, segments: &SegmentLibrary, rng: &mut Rng) {
+        self.timeline += velocity;
+        for upcoming in &mut self.upcoming {
+            upcoming.start += velocity;
+        }
+        while self
+            .upcoming
+            .front()
+            .is_some_and(|upcoming| upcoming.start + segments.width(upcoming.index) < WIDTH)
+        {
+            self.upcoming.pop_front();
+        }
+        while self.timeline < WIDTH * (1 + minimap::PREVIEW_SCREENS) {
+            self.timeline = self.timeline.max(WIDTH) + rng.range(GAP.0, GAP.1);
+            self.plan(segments, segments.pick(rng));
+        }
+    }
+
+    fn plan(&mut self, segments: &SegmentLibrary, index: usize) {
+        self.upcoming.push_back(Upcoming {
+            start: self.timeline,
+            index,
+            spawned: false,
+        });
+        self.timeline += segments.width(index);
+    }
+
+    // The obstacles of the segments that just came within a screen of view.
+    pub fn spawn_due(
+        &mut self,
+        segments: &SegmentLibrary,
+        distance: i32,
+        terrain: &Terrain,
+        coins: &mut Coins,
+    ) -> Vec<Box<dyn Obstacle>> {
+        let mut obstacles = vec![];
+        for upcoming in self
+            .upcoming
+            .iter_mut()
+            .filter(|upcoming| !upcoming.spawned && upcoming.start < WIDTH * 2)
+        {
+            upcoming.spawned = true;
+            obstacles.extend(segments.spawn(
+                upcoming.index,
+                upcoming.start,
+                distance,
+                terrain,
+                coins,
+            ));
+        }
+        obstacles
+    }
+
+    // Every obstacle planned, built or not, with its screen x.
+    pub fn ahead<'a>(&self, segments: &'a SegmentLibrary) -> Vec<(i16, &'a ObstacleDef)> {
+        self.upcoming
+            .iter()
+            .flat_map(|upcoming| {
+                segments
+                    .obstacles(upcoming.index)
+                    .iter()
+                    .map(move |obstacle| (upcoming.start + obstacle.x(), obstacle))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,115 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "walk-the-dog.daily-best";
+
+// Everyone playing on the same UTC day gets the same seed, and with it the
+// same obstacles. FNV-1a over the date string keeps it stable everywhere.
+pub fn seed_for(date: &str) -> u32 {
+    date.bytes().fold(0x811C_9DC5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DailyBest {
+    date: String,
+    score: u32,
+}
+
+impl DailyBest {
+    // A best stored for an earlier day doesn't count for today.
+    pub fn load(date: &str) -> Result<Self> {
+        let stored = match browser::local_storage()?
+            .get_item(STORAGE_KEY)
+            .map_err(|err| anyhow!("Could not read {}: {:#?}", STORAGE_KEY, err))?
+        {
+            Some(json) => Some(
+                serde_json::from_str::<DailyBest>(&json)
+                    .map_err(|err| anyhow!("Could not parse {}: {}", STORAGE_KEY, err))?,
+            ),
+            None => None,
+        };
+        Ok(stored
+            .filter(|best| best.date == date)
+            .unwrap_or_else(|| DailyBest::fresh(date)))
+    }
+
+    pub fn fresh(date: &str) -> Self {
+        DailyBest {
+            date: date.to_string(),
+            score: 0,
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        browser::local_storage()?
+            .set_item(STORAGE_KEY, &json)
+            .map_err(|err| anyhow!("Could not write {}: {:#?}", STORAGE_KEY, err))
+    }
+
+    pub fn record(&mut self, score: u32) -> bool {
+        if score > self.score {
+            self.score = score;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+}
+
+// Today's challenge while it is being played. Starting it from the title
+// screen fixes the seed until the walk restarts, which goes back to the
+// title and to ordinary runs.
+#[derive(Default)]
+pub struct DailyMode {
+    best: Option<DailyBest>,
+}
+
+impl DailyMode {
+    pub fn start(&mut self, best: DailyBest) {
+        self.best = Some(best);
+    }
+
+    pub fn leave(&mut self) {
+        self.best = None;
+    }
+
+    pub fn best(&self) -> Option<&DailyBest> {
+        self.best.as_ref()
+    }
+
+    pub fn best_mut(&mut self) -> Option<&mut DailyBest> {
+        self.best.as_mut()
+    }
+
+    pub fn seed(&self) -> Option<u32> {
+        self.best.as_ref().map(|best| seed_for(best.date()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaving_goes_back_to_ordinary_runs() {
+        let mut daily = DailyMode::default();
+        daily.start(DailyBest::fresh("2024-05-01"));
+        assert_eq!(daily.seed(), Some(seed_for("2024-05-01")));
+
+        daily.leave();
+        assert_eq!(daily.seed(), None);
+        assert!(daily.best().is_none());
+    }
+}
@@ -0,0 +1,231 @@
+use super::{
+    obstacles::Obstacle,
+    segments::{ObstacleDef, SegmentDef, SegmentLibrary, SegmentsFile},
+    terrain::Terrain,
+    HEIGHT,
+};
+use crate::{
+    browser,
+    engine::{
+        assets::{AssetStore, Scope},
+        style::{Color, StrokeStyle},
+        Camera, KeyState, Point, Renderer,
+    },
+};
+use anyhow::{anyhow, Result};
+
+const PAN_SPEED: i32 = 12;
+const MAX_PAN: i32 = 20_000;
+const EDGE_KEYS: [&str; 4] = ["KeyQ", "Delete", "Backspace", "KeyE"];
+const SELECTION: StrokeStyle = StrokeStyle {
+    color: Color::rgb(0x1E, 0x88, 0xE5),
+    width: 2.0,
+};
+
+// Level editor, on with `?editor`, or `?editor=<name>` to start from that
+// segment of `segments.json`. The world stands still and Left/Right pan
+// along it. Clicking empty space places the current tool (Q cycles through
+// what the segment library can draw), dragging moves an obstacle, Delete
+// removes the selected one and E copies the segment as JSON for
+// `segments.json`. Escape leaves it for good. The segment starts at world
+// x 0.
+pub struct Editor {
+    segment: SegmentDef,
+    palette: Vec<ObstacleDef>,
+    tool: usize,
+    selected: Option<usize>,
+    // Where on the selected obstacle the mouse grabbed it, while dragging.
+    grab: Option<Point>,
+    built: Vec<Option<Box<dyn Obstacle>>>,
+    held: [bool; 4],
+    pointer_held: bool,
+}
+
+impl Editor {
+    pub async fn open(assets: &AssetStore, library: &SegmentLibrary) -> Self {
+        let mut editor = Editor::new(library);
+        let name = browser::query_value("editor")
+            .ok()
+            .flatten()
+            .filter(|name| !name.is_empty());
+        if let Some(name) = name {
+            match Self::find(assets, &name).await {
+                Ok(segment) => editor.segment = segment,
+                Err(err) => warn!("Starting a new segment: {:?}", err),
+            }
+        }
+        editor
+    }
+
+    async fn find(assets: &AssetStore, name: &str) -> Result<SegmentDef> {
+        let file: SegmentsFile = assets.data_in(Scope::Editor, "segments").await?;
+        file.segments
+            .into_iter()
+            .find(|segment| segment.name == name)
+            .ok_or_else(|| anyhow!("No segment named '{}' in segments.json", name))
+    }
+
+    fn new(library: &SegmentLibrary) -> Self {
+        Editor {
+            segment: SegmentDef {
+                name: "custom".to_string(),
+                width: 0,
+                obstacles: vec![],
+                collectibles: vec![],
+            },
+            palette: library.palette(),
+            tool: 0,
+            selected: None,
+            grab: None,
+            built: vec![],
+            held: [true; 4],
+            pointer_held: true,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        keystate: &KeyState,
+        camera: &Camera,
+        distance: &mut i32,
+        library: &SegmentLibrary,
+        terrain: &Terrain,
+    ) {
+        let pan = i32::from(keystate.is_pressed("ArrowRight"))
+            - i32::from(keystate.is_pressed("ArrowLeft"));
+        *distance = (*distance + pan * PAN_SPEED).clamp(0, MAX_PAN);
+        let start = -*distance as i16;
+        self.build(library, *distance, terrain);
+
+        let pressed = EDGE_KEYS.map(|key| keystate.is_pressed(key));
+        let [next_tool, delete, backspace, export]: [bool; 4] =
+            std::array::from_fn(|index| pressed[index] && !self.held[index]);
+        self.held = pressed;
+        if next_tool {
+            self.tool = (self.tool + 1) % self.palette.len();
+        }
+        if let Some(selected) = self.selected.filter(|_| delete || backspace) {
+            self.segment.obstacles.remove(selected);
+            self.selected = None;
+            self.grab = None;
+        }
+        if export {
+            if let Err(err) = self.export(start) {
+                warn!("Could not export the segment: {:?}", err);
+            }
+        }
+
+        let pointer = camera.to_world(keystate.pointer());
+        let down = keystate.is_pointer_down();
+        if down && !self.pointer_held {
+            self.press(pointer, start, *distance, terrain);
+        } else if !down {
+            self.grab = None;
+        }
+        self.pointer_held = down;
+        if let (Some(selected), Some(grab)) = (self.selected, self.grab) {
+            let x = pointer.x - grab.x;
+            let ground = terrain
+                .ground_at(*distance + i32::from(x))
+                .unwrap_or(HEIGHT);
+            self.segment.obstacles[selected].move_to(x - start, ground - (pointer.y - grab.y));
+        }
+        self.build(library, *distance, terrain);
+    }
+
+    // Selects whatever is under the mouse, or places the current tool there.
+    fn press(&mut self, pointer: Point, start: i16, distance: i32, terrain: &Terrain) {
+        let under = self.built.iter().rposition(|obstacle| {
+            obstacle.as_ref().is_some_and(|obstacle| {
+                obstacle
+                    .bounding_boxes()
+                    .iter()
+                    .any(|bounding_box| bounding_box.contains(&pointer))
+            })
+        });
+        let (selected, grab) = match under {
+            Some(index) => {
+                let bounding_box = self.built[index]
+                    .as_ref()
+                    .and_then(|obstacle| obstacle.bounding_boxes().first().copied());
+                let top = bounding_box.map_or(pointer.y, |bounding_box| bounding_box.y);
+                let left = start + self.segment.obstacles[index].x();
+                (
+                    index,
+                    Point {
+                        x: pointer.x - left,
+                        y: pointer.y - top,
+                    },
+                )
+            }
+            None => {
+                let mut obstacle = self.palette[self.tool].clone();
+                let ground = terrain
+                    .ground_at(distance + i32::from(pointer.x))
+                    .unwrap_or(HEIGHT);
+                obstacle.move_to(pointer.x - start, ground - pointer.y);
+                self.segment.obstacles.push(obstacle);
+                (self.segment.obstacles.len() - 1, Point { x: 0, y: 0 })
+            }
+        };
+        self.selected = Some(selected);
+        self.grab = Some(grab);
+    }
+
+    fn build(&mut self, library: &SegmentLibrary, distance: i32, terrain: &Terrain) {
+        let start = -distance as i16;
+        self.built = self
+            .segment
+            .obstacles
+            .iter()
+            .map(|obstacle| library.build(obstacle, start, distance, terrain))
+            .collect();
+    }
+
+    pub fn obstacles(&self) -> Vec<Box<dyn Obstacle>> {
+        self.built.iter().flatten().cloned().collect()
+    }
+
+    fn export(&mut self, start: i16) -> Result<()> {
+        self.segment.width = self
+            .built
+            .iter()
+            .flatten()
+            .map(|obstacle| obstacle.right() - start)
+            .max()
+            .unwrap_or(0);
+        let json = serde_json::to_string_pretty(&self.segment)?;
+        log!("Segment:\n{}", json);
+        browser::copy_to_clipboard(&json)
+    }
+
+    // Outlines the selection; call inside the camera.
+    pub fn draw(&self, renderer: &Renderer) {
+        let selected = self
+            .selected
+            .and_then(|selected| self.built.get(selected))
+            .and_then(Option::as_ref);
+        for bounding_box in selected
+            .iter()
+            .flat_map(|obstacle| obstacle.bounding_boxes())
+        {
+            renderer.stroke_rect(&bounding_box, &SELECTION);
+        }
+    }
+
+    pub fn tool_name(&self) -> &'static str {
+        match self.palette[self.tool] {
+            ObstacleDef::Stone { .. } => "stone",
+            ObstacleDef::Spring { .. } => "spring",
+            ObstacleDef::Barrel { .. } => "barrel",
+            ObstacleDef::Wall { .. } => "wall",
+            ObstacleDef::Platform { one_way: true, .. } => "one-way platform",
+            ObstacleDef::Platform {
+                crumble_after: Some(_),
+                ..
+            } => "crumbling platform",
+            ObstacleDef::Platform { sway: Some(_), .. } => "swaying platform",
+            ObstacleDef::Platform { .. } => "platform",
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use super::settings::Settings;
+use crate::browser;
+
+// Gamepad rumble, as strength from 0 to 1 and length in milliseconds.
+const COLLISION_RUMBLE: (f64, f64) = (0.8, 200.0);
+// Phone vibration patterns in milliseconds, alternating buzz and pause.
+const KNOCKOUT_VIBRATION: &[u32] = &[80, 40, 160];
+const LANDING_VIBRATION: &[u32] = &[15];
+
+// Moments worth feeling as well as seeing. Phones buzz on knockouts and
+// landings, and a player's gamepad rumbles when they run into something.
+#[derive(Clone, Copy, Debug)]
+pub enum Haptic {
+    Knockout,
+    Landing,
+    Collision,
+}
+
+pub fn trigger(haptic: Haptic, player: usize, settings: &Settings) {
+    if !settings.haptics {
+        return;
+    }
+    let result = match haptic {
+        Haptic::Knockout => browser::vibrate(KNOCKOUT_VIBRATION),
+        Haptic::Landing => browser::vibrate(LANDING_VIBRATION),
+        Haptic::Collision => {
+            let (strength, duration) = COLLISION_RUMBLE;
+            browser::rumble(player as u32, strength, duration)
+        }
+    };
+    if let Err(err) = result {
+        trace!("No haptics for {:?}: {:?}", haptic, err);
+    }
+}
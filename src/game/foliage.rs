@@ -0,0 +1,86 @@
+use super::{HEIGHT, WIDTH};
+use crate::engine::{
+    rng::Rng,
+    scene::{Entity, Layer},
+    style::Color,
+    DrawOptions, Point, Rect, Renderer,
+};
+
+const TUFTS: usize = 9;
+const SPAN: i16 = WIDTH * 2;
+const TUFT_WIDTH: i16 = 36;
+const BLADES: i16 = 4;
+const FOLIAGE_COLOR: Color = Color::rgb(0x2E, 0x5E, 0x1E);
+
+// Grass tufts along the bottom edge of the screen, drawn in front of the boy.
+// They are scenery only and loop around every SPAN pixels.
+pub struct Foliage {
+    tufts: Vec<Point>,
+}
+
+impl Foliage {
+    pub fn new() -> Self {
+        let mut rng = Rng::new(0x0F01_1A6E);
+        let spacing = SPAN / TUFTS as i16;
+        Foliage {
+            tufts: (0..TUFTS as i16)
+                .map(|index| Point {
+                    x: index * spacing + rng.range(0, spacing - TUFT_WIDTH),
+                    y: HEIGHT - rng.range(0, 8),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Entity for Foliage {
+    fn draw(&self, renderer: &Renderer, options: &DrawOptions) {
+        let color = FOLIAGE_COLOR.with_alpha(options.alpha as f32);
+        let blade_width = TUFT_WIDTH / BLADES;
+        for tuft in &self.tufts {
+            for blade in 0..BLADES {
+                let left = tuft.x + blade * blade_width;
+                let height = 18 + (blade % 2) * 10;
+                renderer.fill_polygon(
+                    &[
+                        Point { x: left, y: tuft.y },
+                        Point {
+                            x: left + blade_width / 2 + (blade - 1) * 2,
+                            y: tuft.y - height,
+                        },
+                        Point {
+                            x: left + blade_width,
+                            y: tuft.y,
+                        },
+                    ],
+                    color,
+                );
+            }
+        }
+    }
+
+    fn bounding_boxes(&self) -> Vec<Rect> {
+        self.tufts
+            .iter()
+            .map(|tuft| Rect {
+                x: tuft.x,
+                y: tuft.y - 28,
+                width: TUFT_WIDTH,
+                height: 28,
+            })
+            .collect()
+    }
+
+    fn move_horizontally(&mut self, distance: i16) {
+        for tuft in &mut self.tufts {
+            tuft.x += distance;
+            if tuft.x + TUFT_WIDTH < 0 {
+                tuft.x += SPAN;
+            }
+        }
+    }
+
+    fn layer(&self) -> Layer {
+        Layer::Foreground
+    }
+}
@@ -0,0 +1,70 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "walk-the-dog.ghost";
+const SAMPLE_EVERY: u32 = 2;
+
+// One recorded moment: distance run, vertical position, and which frame of
+// which animation was showing. Stored as a tuple to keep the JSON small.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Sample(pub i32, pub i16, pub u8, pub u8);
+
+impl Sample {
+    pub fn distance(&self) -> i32 {
+        self.0
+    }
+
+    pub fn y(&self) -> i16 {
+        self.1
+    }
+
+    pub fn animation(&self) -> usize {
+        usize::from(self.2)
+    }
+
+    pub fn frame(&self) -> u8 {
+        self.3
+    }
+}
+
+// A run's positional trace, sampled every SAMPLE_EVERY updates and played
+// back by update count, so the ghost keeps the pace the run had.
+#[derive(Default, Serialize, Deserialize)]
+pub struct GhostTrace {
+    samples: Vec<Sample>,
+}
+
+impl GhostTrace {
+    pub fn load() -> Result<Option<Self>> {
+        browser::local_storage()?
+            .get_item(STORAGE_KEY)
+            .map_err(|err| anyhow!("Could not read {}: {:#?}", STORAGE_KEY, err))?
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|err| anyhow!("Could not parse {}: {}", STORAGE_KEY, err))
+            })
+            .transpose()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        browser::local_storage()?
+            .set_item(STORAGE_KEY, &json)
+            .map_err(|err| anyhow!("Could not write {}: {:#?}", STORAGE_KEY, err))
+    }
+
+    pub fn record(&mut self, tick: u32, sample: Sample) {
+        if tick.is_multiple_of(SAMPLE_EVERY) {
+            self.samples.push(sample);
+        }
+    }
+
+    pub fn at(&self, tick: u32) -> Option<&Sample> {
+        self.samples.get((tick / SAMPLE_EVERY) as usize)
+    }
+
+    pub fn distance(&self) -> i32 {
+        self.samples.last().map_or(0, Sample::distance)
+    }
+}
@@ -0,0 +1,118 @@
+use super::{
+    actions::{ActionState, Bindings},
+    bot::Bot,
+    coins::Coins,
+    course::Course,
+    ghost::Sample,
+    obstacles::{Cause, Obstacle},
+    segments::SegmentLibrary,
+    stamina::Stamina,
+    terrain::Terrain,
+    world::{Unobserved, World},
+    Controls, GameConfig, Player, RedHatBoy, PIXELS_PER_METER,
+};
+use crate::engine::{fixed::Fixed, rng::Rng};
+use anyhow::Result;
+
+// One boy's run with nothing drawn, stepped through the same World as the
+// walk. Given the same seed and the same controls tick by tick it ends up
+// exactly where the real run did, which is what lets the balance
+// simulation play whole runs and an online race rebuild the rival's run
+// from nothing but their inputs.
+pub struct HeadlessRun {
+    player: Player,
+    rng: Rng,
+    coins: Coins,
+    course: Course,
+    obstacles: Vec<Box<dyn Obstacle>>,
+    stamina: Stamina,
+    scroll: Fixed,
+    distance: i32,
+}
+
+impl HeadlessRun {
+    // `boy` is copied and put back at the start, running.
+    pub fn start(
+        boy: &RedHatBoy,
+        segments: &SegmentLibrary,
+        terrain: &Terrain,
+        config: &GameConfig,
+        seed: u32,
+    ) -> Self {
+        let mut boy = boy.clone();
+        boy.reset();
+        boy.run_right();
+        let mut coins = Coins::new();
+        let mut course = Course::open(segments);
+        let obstacles = course.spawn_due(segments, 0, terrain, &mut coins);
+        HeadlessRun {
+            player: Player {
+                home: boy.pos_x(),
+                boy,
+                inputs: vec![],
+                bindings: Bindings::default(),
+                actions: ActionState::default(),
+                lives: config.lives,
+            },
+            rng: Rng::new(seed),
+            coins,
+            course,
+            obstacles,
+            stamina: Stamina::new(),
+            scroll: Fixed::default(),
+            distance: 0,
+        }
+    }
+
+    pub fn step(
+        &mut self,
+        controls: &Controls,
+        segments: &SegmentLibrary,
+        terrain: &Terrain,
+        config: &GameConfig,
+    ) -> Result<()> {
+        self.player.control(controls, config.air_control);
+        World {
+            players: std::slice::from_mut(&mut self.player),
+            obstacles: &mut self.obstacles,
+            course: &mut self.course,
+            rng: &mut self.rng,
+            coins: &mut self.coins,
+            stamina: &mut self.stamina,
+            scroll: &mut self.scroll,
+            distance: &mut self.distance,
+            segments,
+            terrain,
+            config,
+        }
+        .step(controls.boost, &mut Unobserved)?;
+        Ok(())
+    }
+
+    // What the attract bot would press now.
+    pub fn bot_controls(&self, terrain: &Terrain) -> Result<Controls> {
+        Ok(Bot::decide(
+            &self.player.boy.bounding_box()?,
+            &self.obstacles,
+            terrain,
+            self.distance,
+        ))
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.player.boy.kind().is_game_over()
+    }
+
+    pub fn meters(&self) -> i32 {
+        self.distance / PIXELS_PER_METER
+    }
+
+    pub fn cause(&self) -> Option<Cause> {
+        self.player.boy.cause()
+    }
+
+    // Where the boy is, for drawing him as a ghost.
+    pub fn sample(&self) -> Sample {
+        self.player.boy.ghost_sample(self.distance)
+    }
+}
@@ -0,0 +1,80 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "walk-the-dog.run-history";
+pub const MAX_RUNS: usize = 30;
+const TREND_WINDOW: usize = 5;
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct RunHistory {
+    distances: Vec<i32>,
+    // Kept apart from `distances`, which only holds the last MAX_RUNS.
+    #[serde(default)]
+    best: i32,
+}
+
+impl RunHistory {
+    pub fn load() -> Result<Self> {
+        match browser::local_storage()?
+            .get_item(STORAGE_KEY)
+            .map_err(|err| anyhow!("Could not read {}: {:#?}", STORAGE_KEY, err))?
+        {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|err| anyhow!("Could not parse {}: {}", STORAGE_KEY, err)),
+            None => Ok(RunHistory::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        browser::local_storage()?
+            .set_item(STORAGE_KEY, &json)
+            .map_err(|err| anyhow!("Could not write {}: {:#?}", STORAGE_KEY, err))
+    }
+
+    pub fn record(&mut self, distance: i32) {
+        self.best = self.personal_best().max(distance);
+        self.distances.push(distance);
+        if self.distances.len() > MAX_RUNS {
+            self.distances.remove(0);
+        }
+    }
+
+    // A continued run goes further than when it was first recorded, so its
+    // entry is updated rather than a second one added.
+    pub fn record_continued(&mut self, distance: i32) {
+        self.best = self.personal_best().max(distance);
+        match self.distances.last_mut() {
+            Some(last) => *last = distance,
+            None => self.distances.push(distance),
+        }
+    }
+
+    pub fn distances(&self) -> &[i32] {
+        &self.distances
+    }
+
+    // Histories saved before `best` was kept still hold it in their runs.
+    pub fn personal_best(&self) -> i32 {
+        self.distances.iter().copied().fold(self.best, i32::max)
+    }
+
+    // The kept run that set the personal best, if it is still kept.
+    pub fn personal_best_index(&self) -> Option<usize> {
+        let best = self.personal_best();
+        self.distances
+            .iter()
+            .rposition(|distance| *distance == best)
+    }
+
+    // Moving average over the previous few runs, one value per run.
+    pub fn trend(&self) -> Vec<f64> {
+        (0..self.distances.len())
+            .map(|index| {
+                let window = &self.distances[index.saturating_sub(TREND_WINDOW - 1)..=index];
+                f64::from(window.iter().sum::<i32>()) / window.len() as f64
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+use super::StateKind;
+use crate::{
+    browser,
+    engine::{
+        assets::{AssetStore, Scope},
+        KeyState, Rect,
+    },
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// How far the boy's hitbox is inset from his sprite: `x` and `y` from the left
+// and top edges, and `width` off the total width.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct HitboxOffsets {
+    pub x: i16,
+    pub y: i16,
+    pub width: i16,
+}
+
+impl HitboxOffsets {
+    pub fn apply(&self, destination: Rect) -> Rect {
+        Rect {
+            x: destination.x + self.x,
+            y: destination.y + self.y,
+            width: destination.width - self.width,
+            height: destination.height - self.y,
+        }
+    }
+}
+
+// A collision rectangle for one animation frame, relative to the top-left
+// of the frame's destination box.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct FrameHitbox {
+    pub x: i16,
+    pub y: i16,
+    pub width: i16,
+    pub height: i16,
+}
+
+// The hitboxes from the sheet's `.hitboxes.json` sidecar. A frame listed
+// under `frames` by its sprite name gets exactly that rectangle; otherwise the
+// state's offsets apply, and `default` for states without an entry.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Hitboxes {
+    default: HitboxOffsets,
+    #[serde(default)]
+    states: HashMap<StateKind, HitboxOffsets>,
+    #[serde(default)]
+    frames: HashMap<String, FrameHitbox>,
+}
+
+impl Hitboxes {
+    pub async fn load(assets: &AssetStore, scope: Scope, sheet: &str) -> Result<Self> {
+        assets.sheet_sidecar_in(scope, sheet, "hitboxes").await
+    }
+
+    pub fn bounding_box(&self, kind: StateKind, frame: &str, destination: Rect) -> Rect {
+        match self.frames.get(frame) {
+            Some(hitbox) => Rect {
+                x: destination.x + hitbox.x,
+                y: destination.y + hitbox.y,
+                width: hitbox.width,
+                height: hitbox.height,
+            },
+            None => self.for_state(kind).apply(destination),
+        }
+    }
+
+    pub fn for_state(&self, kind: StateKind) -> HitboxOffsets {
+        self.states.get(&kind).copied().unwrap_or(self.default)
+    }
+
+    fn for_state_mut(&mut self, kind: StateKind) -> &mut HitboxOffsets {
+        let default = self.default;
+        self.states.entry(kind).or_insert(default)
+    }
+}
+
+const NUDGE_KEYS: [&str; 5] = ["ArrowLeft", "ArrowRight", "ArrowUp", "ArrowDown", "KeyE"];
+
+// Debug tool, on with `?hitboxes` while the game is paused. The arrows move
+// the current state's offsets, Shift with Left/Right changes its width, and E
+// copies the whole set as JSON for `hitboxes.json`.
+pub struct HitboxEditor {
+    held: [bool; 5],
+}
+
+impl HitboxEditor {
+    pub fn new() -> Self {
+        HitboxEditor { held: [true; 5] }
+    }
+
+    // Returns whether the offsets changed.
+    pub fn update(
+        &mut self,
+        keystate: &KeyState,
+        kind: StateKind,
+        hitboxes: &mut Hitboxes,
+    ) -> bool {
+        let pressed = NUDGE_KEYS.map(|key| keystate.is_pressed(key));
+        let [left, right, up, down, export]: [bool; 5] =
+            std::array::from_fn(|index| pressed[index] && !self.held[index]);
+        self.held = pressed;
+        if export {
+            if let Err(err) = Self::export(hitboxes) {
+                warn!("Could not export the hitboxes: {:?}", err);
+            }
+        }
+        let resize = keystate.is_pressed("ShiftLeft") || keystate.is_pressed("ShiftRight");
+        let offsets = hitboxes.for_state_mut(kind);
+        let horizontal = i16::from(right) - i16::from(left);
+        if resize {
+            offsets.width -= horizontal;
+        } else {
+            offsets.x += horizontal;
+        }
+        offsets.y += i16::from(down) - i16::from(up);
+        left || right || up || down
+    }
+
+    fn export(hitboxes: &Hitboxes) -> Result<()> {
+        let json = serde_json::to_string_pretty(hitboxes)?;
+        log!("Hitboxes:\n{}", json);
+        browser::copy_to_clipboard(&json)
+    }
+}
@@ -0,0 +1,110 @@
+use super::{HEIGHT, WIDTH};
+use crate::{
+    browser,
+    engine::{timer::Timer, Letterbox, Point},
+};
+use anyhow::Result;
+
+// Safe-area insets can change with rotation, fullscreen or a resize, and
+// are cheap enough to read again this often.
+const REFRESH_TICKS: u16 = 30;
+
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomCenter,
+}
+
+// Canvas pixels at each edge the device covers with a notch, rounded
+// corners or a home bar.
+#[derive(Clone, Copy, Default)]
+struct Insets {
+    top: i16,
+    right: i16,
+    bottom: i16,
+    left: i16,
+}
+
+// Places HUD elements from the corner or edge they belong to, kept clear of
+// the safe-area insets wherever the canvas reaches the edge of the screen.
+pub struct HudLayout {
+    insets: Insets,
+    refresh: Timer,
+}
+
+impl HudLayout {
+    pub fn new() -> Self {
+        HudLayout {
+            insets: Insets::default(),
+            refresh: Timer::default(),
+        }
+    }
+
+    // Call once per update.
+    pub fn update(&mut self) {
+        if self.refresh.is_running() {
+            self.refresh.tick();
+            return;
+        }
+        self.refresh = Timer::after(REFRESH_TICKS);
+        match read_insets() {
+            Ok(insets) => self.insets = insets,
+            Err(err) => warn!("Could not read the safe-area insets: {:?}", err),
+        }
+    }
+
+    // `offset` points inward from the anchor: right and down from the top
+    // left, left and down from the top right, up from the bottom.
+    pub fn place(&self, anchor: Anchor, offset: Point) -> Point {
+        let Insets {
+            top,
+            right,
+            bottom,
+            left,
+        } = self.insets;
+        match anchor {
+            Anchor::TopLeft => Point {
+                x: left + offset.x,
+                y: top + offset.y,
+            },
+            Anchor::TopRight => Point {
+                x: WIDTH - right - offset.x,
+                y: top + offset.y,
+            },
+            Anchor::BottomLeft => Point {
+                x: left + offset.x,
+                y: HEIGHT - bottom - offset.y,
+            },
+            Anchor::BottomCenter => Point {
+                x: (WIDTH + left - right) / 2 + offset.x,
+                y: HEIGHT - bottom - offset.y,
+            },
+        }
+    }
+}
+
+// The insets are in CSS pixels from the edges of the viewport. Only the part
+// that reaches past the letterboxed view's own margin covers it, and the view
+// is scaled, so both are taken into account.
+fn read_insets() -> Result<Insets> {
+    let [top, right, bottom, left] = browser::safe_area_insets()?;
+    let (viewport_width, viewport_height) = browser::viewport_size()?;
+    let canvas = browser::canvas()?;
+    let bounds = canvas.get_bounding_client_rect();
+    let letterbox = Letterbox::of_element(&canvas);
+    let [view_left, view_top, view_right, view_bottom] = letterbox.edges();
+    let scale = if letterbox.scale() > 0.0 {
+        1.0 / letterbox.scale()
+    } else {
+        1.0
+    };
+    let covered = |inset: f64, margin: f64| ((inset - margin).max(0.0) * scale).round() as i16;
+    Ok(Insets {
+        top: covered(top, bounds.top() + view_top),
+        right: covered(right, viewport_width - bounds.left() - view_right),
+        bottom: covered(bottom, viewport_height - bounds.top() - view_bottom),
+        left: covered(left, bounds.left() + view_left),
+    })
+}
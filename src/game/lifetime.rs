@@ -0,0 +1,154 @@
+use super::{obstacles::Hazard, HEIGHT, WIDTH};
+use crate::{
+    browser,
+    engine::{
+        style::{Color, TextAlign, TextStyle},
+        Point, Rect, Renderer,
+    },
+    i18n::Locale,
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const STORAGE_KEY: &str = "walk-the-dog.lifetime-stats";
+
+const LIFETIME_TITLE: TextStyle = TextStyle {
+    font: "bold 24px sans-serif",
+    color: Color::BLACK,
+    align: TextAlign::Center,
+};
+
+const LIFETIME_TEXT: TextStyle = TextStyle {
+    font: "bold 18px sans-serif",
+    align: TextAlign::Left,
+    ..LIFETIME_TITLE
+};
+
+const LIFETIME_LABEL: TextStyle = TextStyle {
+    font: "18px sans-serif",
+    ..LIFETIME_TEXT
+};
+
+const LIFETIME_VALUE: TextStyle = TextStyle {
+    align: TextAlign::Right,
+    ..LIFETIME_LABEL
+};
+
+const LIFETIME_HINT: TextStyle = TextStyle {
+    font: "14px sans-serif",
+    ..LIFETIME_TITLE
+};
+
+// Totals over every run ever played on this device, unlike `RunHistory`,
+// which only keeps the last few.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LifetimeStats {
+    meters: i64,
+    runs: u32,
+    deaths: HashMap<Hazard, u32>,
+}
+
+impl LifetimeStats {
+    pub fn load() -> Result<Self> {
+        match browser::local_storage()?
+            .get_item(STORAGE_KEY)
+            .map_err(|err| anyhow!("Could not read {}: {:#?}", STORAGE_KEY, err))?
+        {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|err| anyhow!("Could not parse {}: {}", STORAGE_KEY, err)),
+            None => Ok(LifetimeStats::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        browser::local_storage()?
+            .set_item(STORAGE_KEY, &json)
+            .map_err(|err| anyhow!("Could not write {}: {:#?}", STORAGE_KEY, err))
+    }
+
+    // `cause` is what ended the run, when something in the course did.
+    pub fn record(&mut self, meters: i32, cause: Option<Hazard>) {
+        self.meters += i64::from(meters);
+        self.runs += 1;
+        if let Some(cause) = cause {
+            *self.deaths.entry(cause).or_default() += 1;
+        }
+    }
+
+    // Another knockout in a run that was already recorded and then
+    // continued, `meters` further than it got before.
+    pub fn record_continued(&mut self, meters: i32, cause: Option<Hazard>) {
+        self.meters += i64::from(meters);
+        if let Some(cause) = cause {
+            *self.deaths.entry(cause).or_default() += 1;
+        }
+    }
+
+    fn average_meters(&self) -> i64 {
+        if self.runs == 0 {
+            0
+        } else {
+            self.meters / i64::from(self.runs)
+        }
+    }
+
+    pub fn draw(&self, renderer: &Renderer, locale: &Locale) {
+        renderer.fill_rect(
+            &Rect {
+                x: 0,
+                y: 0,
+                width: WIDTH,
+                height: HEIGHT,
+            },
+            Color::WHITE.with_alpha(0.85),
+        );
+        renderer.draw_text(
+            locale.tr("lifetime.title"),
+            &Point {
+                x: WIDTH / 2,
+                y: 100,
+            },
+            &LIFETIME_TITLE,
+        );
+        let totals = [
+            ("lifetime.distance", format!("{}m", self.meters)),
+            ("lifetime.runs", self.runs.to_string()),
+            ("lifetime.average", format!("{}m", self.average_meters())),
+        ];
+        let deaths = Hazard::ALL.iter().map(|hazard| {
+            (
+                hazard.label(),
+                self.deaths.get(hazard).copied().unwrap_or(0).to_string(),
+            )
+        });
+        let mut y = 160;
+        for (row, (label, value)) in totals.into_iter().enumerate() {
+            draw_row(renderer, locale.tr(label), &value, y + row as i16 * 30);
+        }
+        y += 3 * 30 + 20;
+        renderer.draw_text(
+            locale.tr("lifetime.deaths"),
+            &Point { x: 90, y },
+            &LIFETIME_TEXT,
+        );
+        for (row, (label, value)) in deaths.enumerate() {
+            draw_row(renderer, locale.tr(label), &value, y + 30 + row as i16 * 26);
+        }
+        renderer.draw_text(
+            locale.tr("prompt.back"),
+            &Point {
+                x: WIDTH / 2,
+                y: 540,
+            },
+            &LIFETIME_HINT,
+        );
+    }
+}
+
+fn draw_row(renderer: &Renderer, label: &str, value: &str, y: i16) {
+    renderer.draw_text(label, &Point { x: 90, y }, &LIFETIME_LABEL);
+    renderer.draw_text(value, &Point { x: WIDTH - 90, y }, &LIFETIME_VALUE);
+}
@@ -0,0 +1,133 @@
+use super::{
+    audio::{Cue, Cues},
+    settings::Settings,
+    StateObserver, HEIGHT, WIDTH,
+};
+use crate::engine::{
+    style::{Color, StrokeStyle, TextAlign, TextStyle},
+    timer::Timer,
+    Point, Renderer,
+};
+
+pub const MILESTONE_METERS: i32 = 100;
+const TEXT_FRAMES: u16 = 60;
+const TEXT_RISE: i16 = 60;
+const TEXT: TextStyle = TextStyle {
+    font: "bold 24px sans-serif",
+    color: Color::rgb(0xFF, 0xC1, 0x07),
+    align: TextAlign::Center,
+};
+const SPEED_LINE_FRAMES: u16 = 40;
+const SPEED_LINES: i16 = 12;
+const SPEED_LINE_LENGTH: i16 = 140;
+
+pub struct Chime<'a> {
+    pub cues: &'a Cues,
+    pub settings: &'a Settings,
+}
+
+impl StateObserver for Chime<'_> {
+    fn on_milestone(&mut self, _meters: i32) {
+        self.cues.play(Cue::Milestone, self.settings);
+    }
+}
+
+struct FloatingText {
+    text: String,
+    origin: Point,
+    life: Timer,
+}
+
+// Text that drifts up from where it was spawned and fades out.
+#[derive(Default)]
+pub struct FloatingTexts {
+    texts: Vec<FloatingText>,
+}
+
+impl FloatingTexts {
+    pub fn spawn(&mut self, text: String, origin: Point) {
+        self.texts.push(FloatingText {
+            text,
+            origin,
+            life: Timer::after(TEXT_FRAMES),
+        });
+    }
+
+    pub fn update(&mut self) {
+        self.texts.iter_mut().for_each(|text| {
+            text.life.tick();
+        });
+        self.texts.retain(|text| text.life.is_running());
+    }
+
+    pub fn draw(&self, renderer: &Renderer) {
+        for text in &self.texts {
+            let progress = 1.0 - text.life.fraction_left(TEXT_FRAMES);
+            renderer.draw_text(
+                &text.text,
+                &Point {
+                    x: text.origin.x,
+                    y: text.origin.y - (f32::from(TEXT_RISE) * progress) as i16,
+                },
+                &TextStyle {
+                    color: TEXT.color.with_alpha(1.0 - progress),
+                    ..TEXT
+                },
+            );
+        }
+    }
+}
+
+// Shows "+100m" over the boy.
+pub struct MilestoneText<'a> {
+    pub texts: &'a mut FloatingTexts,
+    pub origin: Point,
+}
+
+impl StateObserver for MilestoneText<'_> {
+    fn on_milestone(&mut self, _meters: i32) {
+        self.texts
+            .spawn(format!("+{}m", MILESTONE_METERS), self.origin);
+    }
+}
+
+// White streaks rushing across the screen for a moment.
+#[derive(Default)]
+pub struct SpeedLines {
+    frames: Timer,
+}
+
+impl SpeedLines {
+    pub fn update(&mut self) {
+        self.frames.tick();
+    }
+
+    pub fn draw(&self, renderer: &Renderer) {
+        if !self.frames.is_running() {
+            return;
+        }
+        let elapsed = self.frames.elapsed(SPEED_LINE_FRAMES) as i16;
+        let style = StrokeStyle {
+            color: Color::WHITE.with_alpha(0.6 * self.frames.fraction_left(SPEED_LINE_FRAMES)),
+            width: 2.0,
+        };
+        for line in 0..SPEED_LINES {
+            let y = (line * 97 + 31) % HEIGHT;
+            let x = WIDTH - (elapsed * 40 + line * 131) % (WIDTH + SPEED_LINE_LENGTH);
+            renderer.draw_line(
+                &Point { x, y },
+                &Point {
+                    x: x + SPEED_LINE_LENGTH,
+                    y,
+                },
+                &style,
+            );
+        }
+    }
+}
+
+impl StateObserver for &mut SpeedLines {
+    fn on_milestone(&mut self, _meters: i32) {
+        self.frames = Timer::after(SPEED_LINE_FRAMES);
+    }
+}
@@ -0,0 +1,71 @@
+use super::{segments::ObstacleDef, WIDTH};
+use crate::engine::{style::Color, Point, Rect, Renderer};
+
+// How many screens past the right edge the strip covers.
+pub const PREVIEW_SCREENS: i16 = 2;
+const STRIP_WIDTH: i16 = 300;
+const STRIP_HEIGHT: i16 = 20;
+const SCALE: i16 = PREVIEW_SCREENS * WIDTH / STRIP_WIDTH;
+const BACKGROUND: Color = Color::rgba(0, 0, 0, 0.25);
+const STONE: Color = Color::rgb(0x88, 0x88, 0x88);
+const SPRING: Color = Color::rgb(0x3C, 0xB3, 0x4A);
+const BARREL: Color = Color::rgb(0x9C, 0x5B, 0x2E);
+const WALL: Color = Color::rgb(0x44, 0x44, 0x55);
+const PLATFORM: Color = Color::rgb(0xE8, 0xC1, 0x6A);
+
+// An icon for `obstacle`, relative to where it sits on the strip, along
+// with its color.
+fn icon(obstacle: &ObstacleDef) -> (Rect, Color) {
+    let (width, height, color) = match obstacle {
+        ObstacleDef::Stone { .. } => (4, 6, STONE),
+        ObstacleDef::Spring { .. } => (4, 8, SPRING),
+        ObstacleDef::Barrel { .. } => (5, 5, BARREL),
+        ObstacleDef::Wall { .. } => (3, STRIP_HEIGHT - 4, WALL),
+        ObstacleDef::Platform { bounding_boxes, .. } => {
+            let right = bounding_boxes.iter().map(Rect::right).max().unwrap_or(0);
+            ((right / SCALE).max(4), 3, PLATFORM)
+        }
+    };
+    let bottom = match obstacle {
+        ObstacleDef::Platform { .. } => STRIP_HEIGHT / 2,
+        _ => STRIP_HEIGHT - 2,
+    };
+    (
+        Rect {
+            x: 0,
+            y: bottom - height,
+            width,
+            height,
+        },
+        color,
+    )
+}
+
+// The obstacles coming up in the next couple of screens as simple icons on a
+// strip whose bottom middle is at `bottom_center`. `ahead` pairs each
+// obstacle with its screen x.
+pub fn draw(renderer: &Renderer, bottom_center: &Point, ahead: &[(i16, &ObstacleDef)]) {
+    let strip = Rect {
+        x: bottom_center.x - STRIP_WIDTH / 2,
+        y: bottom_center.y - STRIP_HEIGHT,
+        width: STRIP_WIDTH,
+        height: STRIP_HEIGHT,
+    };
+    renderer.fill_rect(&strip, BACKGROUND);
+    for (x, obstacle) in ahead {
+        let offset = (x - WIDTH) / SCALE;
+        if !(0..STRIP_WIDTH).contains(&offset) {
+            continue;
+        }
+        let (icon, color) = icon(obstacle);
+        renderer.fill_rect(
+            &Rect {
+                x: strip.x + offset,
+                y: strip.y + icon.y,
+                width: icon.width.min(STRIP_WIDTH - offset),
+                ..icon
+            },
+            color,
+        );
+    }
+}
@@ -0,0 +1,784 @@
+use super::{terrain::Terrain, RedHatBoy, HEIGHT};
+use crate::engine::{
+    self,
+    fixed::Fixed,
+    scene::Entity,
+    timer::Timer,
+    tween::{Easing, Tween},
+    DrawOptions, Point, Prerendered, Rect, Renderer, Texture,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+// What touching an obstacle did to the boy this frame.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Contact {
+    None,
+    Landed,
+    Bounced,
+    // Pushed back out of something solid.
+    Blocked,
+    Hit(Hazard),
+}
+
+// What in the course ended a run.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Hazard {
+    Stone,
+    Barrel,
+    Platform,
+    Wall,
+    Pit,
+}
+
+// What ended a run, and how many meters in.
+#[derive(Clone, Copy, Debug)]
+pub struct Cause {
+    pub hazard: Hazard,
+    pub meters: i32,
+}
+
+impl Hazard {
+    pub const ALL: [Hazard; 5] = [
+        Hazard::Stone,
+        Hazard::Barrel,
+        Hazard::Platform,
+        Hazard::Wall,
+        Hazard::Pit,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Hazard::Stone => "hazard.stone",
+            Hazard::Barrel => "hazard.barrel",
+            Hazard::Platform => "hazard.platform",
+            Hazard::Wall => "hazard.wall",
+            Hazard::Pit => "hazard.pit",
+        }
+    }
+
+    // Reads "Tripped on a stone at {distance}m" and the like.
+    pub fn cause(self) -> &'static str {
+        match self {
+            Hazard::Stone => "cause.stone",
+            Hazard::Barrel => "cause.barrel",
+            Hazard::Platform => "cause.platform",
+            Hazard::Wall => "cause.wall",
+            Hazard::Pit => "cause.pit",
+        }
+    }
+}
+
+// Something in the course the boy can run into. Obstacles scroll and draw
+// like any other entity, but are owned by the Walk so it can check each one
+// against every player.
+pub trait Obstacle: Entity + ObstacleClone {
+    fn check_intersection(&self, boy: &mut RedHatBoy) -> Result<Contact>;
+
+    // Called once per fixed step, after the world scrolled, with the world x
+    // of the screen's left edge. Moving and animated obstacles advance here.
+    fn tick(&mut self, _terrain: &Terrain, _distance: i32) {}
+
+    // A boy landed on, ran along or bounced off the obstacle this frame.
+    fn stood_on(&mut self) {}
+
+    // Whether the obstacle can be dropped from the course.
+    fn is_spent(&self) -> bool {
+        self.right() < 0
+    }
+
+    // Called each frame with each running boy's box, along with which
+    // player he is. True on the frame he clears the obstacle after coming
+    // close without touching it.
+    fn near_missed(&mut self, _player: usize, _boy: &Rect) -> bool {
+        false
+    }
+
+    fn left(&self) -> i16 {
+        self.bounding_boxes()
+            .iter()
+            .map(|bounding_box| bounding_box.x)
+            .min()
+            .unwrap_or(i16::MAX)
+    }
+
+    fn right(&self) -> i16 {
+        self.bounding_boxes()
+            .iter()
+            .map(Rect::right)
+            .max()
+            .unwrap_or(i16::MIN)
+    }
+}
+
+// How close the boy has to come to an obstacle for a near miss.
+const NEAR_MISS_MARGIN: i16 = 30;
+
+// Watches the margin around an obstacle. A boy who enters it and leaves
+// again without touching the obstacle scores a near miss, once per obstacle
+// and player, so one boy touching it doesn't spoil the other's.
+#[derive(Clone, Default)]
+pub struct Proximity {
+    players: Vec<Watch>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Watch {
+    grazed: bool,
+    touched: bool,
+    scored: bool,
+}
+
+impl Proximity {
+    fn update(&mut self, obstacle: &[Rect], player: usize, boy: &Rect) -> bool {
+        if self.players.len() <= player {
+            self.players.resize(player + 1, Watch::default());
+        }
+        self.players[player].update(obstacle, boy)
+    }
+}
+
+impl Watch {
+    fn update(&mut self, obstacle: &[Rect], boy: &Rect) -> bool {
+        if self.scored {
+            return false;
+        }
+        let near = obstacle
+            .iter()
+            .any(|bounding_box| bounding_box.inflate(NEAR_MISS_MARGIN).intersects(boy));
+        self.touched |= obstacle
+            .iter()
+            .any(|bounding_box| bounding_box.intersects(boy));
+        if near {
+            self.grazed = true;
+            return false;
+        }
+        self.scored = self.grazed;
+        self.scored && !self.touched
+    }
+}
+
+// Lets checkpoints keep a copy of the course.
+pub trait ObstacleClone {
+    fn clone_box(&self) -> Box<dyn Obstacle>;
+}
+
+impl<T: Obstacle + Clone> ObstacleClone for T {
+    fn clone_box(&self) -> Box<dyn Obstacle> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Obstacle> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// Knocks the boy down on any touch.
+#[derive(Clone)]
+pub struct Barrier {
+    image: engine::Image,
+    proximity: Proximity,
+}
+
+impl Barrier {
+    pub fn new(image: engine::Image) -> Self {
+        Barrier {
+            image,
+            proximity: Proximity::default(),
+        }
+    }
+}
+
+impl Entity for Barrier {
+    fn draw(&self, renderer: &Renderer, options: &DrawOptions) {
+        self.image.draw(renderer, options);
+    }
+
+    fn bounding_boxes(&self) -> Vec<Rect> {
+        vec![*self.image.bounding_box()]
+    }
+
+    fn move_horizontally(&mut self, distance: i16) {
+        self.image.move_horizontally(distance);
+    }
+
+    fn batch_key(&self) -> Option<String> {
+        Some(self.image.source().to_string())
+    }
+}
+
+impl Obstacle for Barrier {
+    fn check_intersection(&self, boy: &mut RedHatBoy) -> Result<Contact> {
+        Ok(
+            if boy.bounding_box()?.intersects(self.image.bounding_box()) {
+                Contact::Hit(Hazard::Stone)
+            } else {
+                Contact::None
+            },
+        )
+    }
+
+    fn near_missed(&mut self, player: usize, boy: &Rect) -> bool {
+        self.proximity
+            .update(&[*self.image.bounding_box()], player, boy)
+    }
+}
+
+// A row of tiles the boy can land on. Bounding boxes are relative to the
+// platform's top-left, so the ends can be lower than the middle. A one-way
+// platform only stops the boy falling onto its top; he passes through it
+// from below and from the sides.
+#[derive(Clone)]
+pub struct Platform {
+    composite: Prerendered,
+    bounding_boxes: Vec<Rect>,
+    one_way: bool,
+    crumble: Option<Crumble>,
+    sway: Option<Sway>,
+    pub position: Point,
+}
+
+const CRUMBLE_FRAMES: u16 = 30;
+const CRUMBLE_DROP: f64 = 225.0;
+const SWAY_TICKS: u16 = 90;
+
+// Rises `height` pixels and sinks back, easing in and out at each end. The
+// boy stands on it, so it moves in fixed point to replay the same everywhere.
+#[derive(Clone, Copy)]
+struct Sway {
+    height: i16,
+    rising: bool,
+    timer: Timer,
+}
+
+impl Sway {
+    fn new(height: i16) -> Self {
+        Sway {
+            height,
+            rising: true,
+            timer: Timer::after(SWAY_TICKS),
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.timer.tick() {
+            self.rising = !self.rising;
+            self.timer = Timer::after(SWAY_TICKS);
+        }
+    }
+
+    fn offset(&self) -> i16 {
+        let progress = Fixed::from_ratio(self.timer.elapsed(SWAY_TICKS) as i16, SWAY_TICKS as i16);
+        let risen = if self.rising {
+            progress.ease_in_out()
+        } else {
+            Fixed::from_int(1) - progress.ease_in_out()
+        };
+        -(Fixed::from_int(self.height) * risen).round()
+    }
+}
+
+// A platform that gives way once it has been stood on for `after` ticks,
+// counted across every landing. It shakes for the second half of that, then
+// has no collision while it falls away.
+#[derive(Clone, Copy)]
+struct Crumble {
+    after: u16,
+    // Standing ticks left before it goes.
+    stood: Timer,
+    standing: bool,
+    // How far it has dropped since giving way.
+    collapsed: Option<Tween>,
+}
+
+impl Crumble {
+    fn offset(&self) -> Point {
+        match self.collapsed {
+            Some(fall) => Point {
+                x: 0,
+                y: fall.value().round() as i16,
+            },
+            None if self.stood.remaining() < self.after / 2 => Point {
+                x: if self.stood.remaining() % 4 < 2 {
+                    -2
+                } else {
+                    2
+                },
+                y: 0,
+            },
+            None => Point { x: 0, y: 0 },
+        }
+    }
+}
+
+impl Platform {
+    pub fn new(
+        composite: Prerendered,
+        bounding_boxes: Vec<Rect>,
+        one_way: bool,
+        position: Point,
+    ) -> Self {
+        Platform {
+            composite,
+            bounding_boxes,
+            one_way,
+            crumble: None,
+            sway: None,
+            position,
+        }
+    }
+
+    pub fn with_crumble(mut self, after: Option<u16>) -> Self {
+        self.crumble = after.map(|after| Crumble {
+            after,
+            stood: Timer::after(after),
+            standing: false,
+            collapsed: None,
+        });
+        self
+    }
+
+    pub fn with_sway(mut self, height: Option<i16>) -> Self {
+        self.sway = height.map(Sway::new);
+        self
+    }
+
+    // Where the top-left corner is now, with any sway.
+    fn top_left(&self) -> Point {
+        Point {
+            x: self.position.x,
+            y: self.position.y + self.sway.map_or(0, |sway| sway.offset()),
+        }
+    }
+
+    fn is_collapsed(&self) -> bool {
+        self.crumble
+            .is_some_and(|crumble| crumble.collapsed.is_some())
+    }
+
+    pub fn destination_box(&self) -> Rect {
+        let top_left = self.top_left();
+        Rect {
+            x: top_left.x,
+            y: top_left.y,
+            ..self.composite.frame()
+        }
+    }
+}
+
+impl Entity for Platform {
+    fn draw(&self, renderer: &Renderer, options: &DrawOptions) {
+        let (offset, alpha) = match self.crumble {
+            Some(Crumble {
+                collapsed: Some(fall),
+                ..
+            }) if fall.is_done() => return,
+            Some(crumble) => (
+                crumble.offset(),
+                crumble
+                    .collapsed
+                    .map_or(1.0, |fall| 1.0 - fall.value() / CRUMBLE_DROP),
+            ),
+            None => (Point { x: 0, y: 0 }, 1.0),
+        };
+        let destination = self.destination_box();
+        renderer.draw_prerendered(
+            &self.composite,
+            &Rect {
+                x: destination.x + offset.x,
+                y: destination.y + offset.y,
+                ..destination
+            },
+            &DrawOptions {
+                alpha: options.alpha * alpha,
+                ..*options
+            },
+        );
+
+        for bounding_box in &self.bounding_boxes() {
+            renderer.draw_rect(bounding_box);
+        }
+    }
+
+    // Nothing once it has given way, so the minimap, the bot and the editor
+    // all see the gap the boy does.
+    fn bounding_boxes(&self) -> Vec<Rect> {
+        if self.is_collapsed() {
+            return vec![];
+        }
+        let top_left = self.top_left();
+        self.bounding_boxes
+            .iter()
+            .map(|bounding_box| Rect {
+                x: top_left.x + bounding_box.x,
+                y: top_left.y + bounding_box.y,
+                ..*bounding_box
+            })
+            .collect()
+    }
+
+    fn move_horizontally(&mut self, distance: i16) {
+        self.position.x += distance;
+    }
+}
+
+// Feet this far below a platform's top still land on it, so clipping the
+// edge on the way down doesn't end the run.
+const EDGE_FORGIVENESS: i16 = 12;
+
+impl Obstacle for Platform {
+    fn check_intersection(&self, boy: &mut RedHatBoy) -> Result<Contact> {
+        let mut contact = Contact::None;
+        let velocity = boy.velocity_y();
+        for bounding_box in &self.bounding_boxes() {
+            let boy_box = boy.bounding_box()?;
+            if !boy_box.intersects(bounding_box) {
+                continue;
+            }
+            let feet_on_top = boy_box.bottom() - bounding_box.y <= EDGE_FORGIVENESS;
+            // Where his head was last frame, before this frame's rise.
+            let came_from_below = boy_box.y - velocity >= bounding_box.bottom();
+            if self.one_way {
+                // Only feet that were above the top last frame land.
+                if velocity > 0 && boy_box.bottom() - velocity <= bounding_box.y + EDGE_FORGIVENESS
+                {
+                    boy.land_on(bounding_box.y);
+                    contact = Contact::Landed;
+                }
+            } else if velocity > 0 && (boy.pos_y() < self.top_left().y || feet_on_top) {
+                boy.land_on(bounding_box.y);
+                contact = Contact::Landed;
+            } else if velocity < 0 && came_from_below {
+                boy.bump_head(bounding_box.bottom() - boy_box.y);
+            } else {
+                return Ok(Contact::Hit(Hazard::Platform));
+            }
+        }
+        Ok(contact)
+    }
+
+    fn tick(&mut self, _terrain: &Terrain, _distance: i32) {
+        if let Some(sway) = &mut self.sway {
+            sway.tick();
+        }
+        let Some(crumble) = &mut self.crumble else {
+            return;
+        };
+        match &mut crumble.collapsed {
+            Some(fall) => {
+                fall.tick();
+            }
+            None if crumble.standing => {
+                crumble.stood.tick();
+                if !crumble.stood.is_running() {
+                    crumble.collapsed = Some(Tween::new(
+                        0.0,
+                        CRUMBLE_DROP,
+                        CRUMBLE_FRAMES,
+                        Easing::EaseIn,
+                    ));
+                }
+            }
+            None => {}
+        }
+        crumble.standing = false;
+    }
+
+    fn stood_on(&mut self) {
+        if let Some(crumble) = &mut self.crumble {
+            crumble.standing = true;
+        }
+    }
+}
+
+const BOUNCE_SPEED: i16 = -32;
+const SQUASH_FRAMES: u8 = 4;
+const RECOIL_FRAMES: u8 = 12;
+
+// A pad on the ground that throws the boy higher than a jump when he lands
+// on it. He runs straight through it otherwise. `frames` are the rest,
+// squashed and extended cells of the spring sheet.
+#[derive(Clone)]
+pub struct Spring {
+    image: Texture,
+    frames: [Rect; 3],
+    pad: Rect,
+    position: Point,
+    // Ticks since the last bounce, while the animation plays.
+    bounced: Option<u8>,
+}
+
+impl Spring {
+    pub fn new(image: Texture, frames: [Rect; 3], pad: Rect, position: Point) -> Self {
+        Spring {
+            image,
+            frames,
+            pad,
+            position,
+            bounced: None,
+        }
+    }
+
+    fn frame(&self) -> &Rect {
+        let [rest, squashed, extended] = &self.frames;
+        match self.bounced {
+            Some(tick) if tick < SQUASH_FRAMES => squashed,
+            Some(tick) if tick < RECOIL_FRAMES => extended,
+            _ => rest,
+        }
+    }
+}
+
+impl Entity for Spring {
+    fn draw(&self, renderer: &Renderer, options: &DrawOptions) {
+        let frame = self.frame();
+        renderer.draw_image(
+            &self.image,
+            frame,
+            &Rect {
+                x: self.position.x,
+                y: self.position.y,
+                ..*frame
+            },
+            options,
+        );
+        renderer.draw_rect(&self.bounding_boxes()[0]);
+    }
+
+    fn bounding_boxes(&self) -> Vec<Rect> {
+        vec![Rect {
+            x: self.position.x + self.pad.x,
+            y: self.position.y + self.pad.y,
+            ..self.pad
+        }]
+    }
+
+    fn move_horizontally(&mut self, distance: i16) {
+        self.position.x += distance;
+    }
+
+    fn batch_key(&self) -> Option<String> {
+        Some(self.image.source().to_string())
+    }
+}
+
+impl Obstacle for Spring {
+    fn check_intersection(&self, boy: &mut RedHatBoy) -> Result<Contact> {
+        let pad = self.bounding_boxes()[0];
+        let boy_box = boy.bounding_box()?;
+        let velocity = boy.velocity_y();
+        let from_above = boy_box.bottom() - velocity <= pad.y + EDGE_FORGIVENESS;
+        Ok(if boy_box.intersects(&pad) && velocity > 0 && from_above {
+            boy.bounce(BOUNCE_SPEED);
+            Contact::Bounced
+        } else {
+            Contact::None
+        })
+    }
+
+    fn tick(&mut self, _terrain: &Terrain, _distance: i32) {
+        self.bounced = self
+            .bounced
+            .map(|tick| tick + 1)
+            .filter(|tick| *tick < RECOIL_FRAMES);
+    }
+
+    fn stood_on(&mut self) {
+        self.bounced = Some(0);
+    }
+}
+
+const BARREL_GRAVITY: i16 = 1;
+// Ground up to this much higher than the barrel is rolled up onto; anything
+// taller, like the far wall of a pit it fell in, is not.
+const BARREL_STEP: i16 = 16;
+
+// A barrel rolling toward the boy at its own speed on top of the scroll. It
+// follows the ground, drops into pits and knocks the boy down on contact.
+// `frames` are the turns of the barrel sheet, in rolling order.
+#[derive(Clone)]
+pub struct Barrel {
+    image: Texture,
+    frames: Vec<Rect>,
+    position: Point,
+    velocity: Point,
+    // How far it has rolled, to pick the frame.
+    rolled: i16,
+    proximity: Proximity,
+}
+
+impl Barrel {
+    pub fn new(image: Texture, frames: Vec<Rect>, position: Point, speed: i16) -> Self {
+        Barrel {
+            image,
+            frames,
+            position,
+            velocity: Point { x: -speed, y: 0 },
+            rolled: 0,
+            proximity: Proximity::default(),
+        }
+    }
+
+    fn frame(&self) -> &Rect {
+        const PIXELS_PER_FRAME: i16 = 8;
+        let frame = (self.rolled / PIXELS_PER_FRAME).rem_euclid(self.frames.len() as i16);
+        &self.frames[frame as usize]
+    }
+
+    fn size(&self) -> &Rect {
+        &self.frames[0]
+    }
+}
+
+impl Entity for Barrel {
+    fn draw(&self, renderer: &Renderer, options: &DrawOptions) {
+        renderer.draw_image(
+            &self.image,
+            self.frame(),
+            &self.bounding_boxes()[0],
+            options,
+        );
+        renderer.draw_rect(&self.bounding_boxes()[0]);
+    }
+
+    fn bounding_boxes(&self) -> Vec<Rect> {
+        vec![Rect {
+            x: self.position.x,
+            y: self.position.y,
+            ..*self.size()
+        }]
+    }
+
+    fn move_horizontally(&mut self, distance: i16) {
+        self.position.x += distance;
+    }
+
+    fn batch_key(&self) -> Option<String> {
+        Some(self.image.source().to_string())
+    }
+}
+
+impl Obstacle for Barrel {
+    fn check_intersection(&self, boy: &mut RedHatBoy) -> Result<Contact> {
+        Ok(
+            if boy.bounding_box()?.intersects(&self.bounding_boxes()[0]) {
+                Contact::Hit(Hazard::Barrel)
+            } else {
+                Contact::None
+            },
+        )
+    }
+
+    fn near_missed(&mut self, player: usize, boy: &Rect) -> bool {
+        let bounding_boxes = self.bounding_boxes();
+        self.proximity.update(&bounding_boxes, player, boy)
+    }
+
+    fn tick(&mut self, terrain: &Terrain, distance: i32) {
+        self.position.x += self.velocity.x;
+        self.rolled -= self.velocity.x;
+        self.velocity.y += BARREL_GRAVITY;
+        self.position.y += self.velocity.y;
+        let middle = self.position.x + self.size().width / 2;
+        let floor = terrain
+            .ground_at(distance + i32::from(middle))
+            .map(|ground| ground - self.size().height);
+        if let Some(floor) = floor.filter(|floor| {
+            (0..=self.velocity.y + BARREL_STEP).contains(&(self.position.y - floor))
+        }) {
+            self.position.y = floor;
+            self.velocity.y = 0;
+        }
+    }
+
+    fn is_spent(&self) -> bool {
+        self.right() < 0 || self.position.y > HEIGHT
+    }
+}
+
+// Solid ground-tile blocks. Landing on top and bumping the underside work as
+// on a platform; running into a side stops the boy there, so the scroll
+// carries him back toward the left edge of the screen until he gets over.
+#[derive(Clone)]
+pub struct Wall {
+    image: Texture,
+    tile: Rect,
+    bounds: Rect,
+}
+
+impl Wall {
+    pub fn new(image: Texture, tile: Rect, bounds: Rect) -> Self {
+        Wall {
+            image,
+            tile,
+            bounds,
+        }
+    }
+}
+
+impl Entity for Wall {
+    fn draw(&self, renderer: &Renderer, options: &DrawOptions) {
+        let mut y = self.bounds.y;
+        while y < self.bounds.bottom() {
+            let height = self.tile.height.min(self.bounds.bottom() - y);
+            renderer.draw_image(
+                &self.image,
+                &Rect {
+                    height,
+                    ..self.tile
+                },
+                &Rect {
+                    x: self.bounds.x,
+                    y,
+                    width: self.tile.width,
+                    height,
+                },
+                options,
+            );
+            y += height;
+        }
+        renderer.draw_rect(&self.bounds);
+    }
+
+    fn bounding_boxes(&self) -> Vec<Rect> {
+        vec![self.bounds]
+    }
+
+    fn move_horizontally(&mut self, distance: i16) {
+        self.bounds.x += distance;
+    }
+
+    fn batch_key(&self) -> Option<String> {
+        Some(self.image.source().to_string())
+    }
+}
+
+impl Obstacle for Wall {
+    fn check_intersection(&self, boy: &mut RedHatBoy) -> Result<Contact> {
+        let boy_box = boy.bounding_box()?;
+        if !boy_box.intersects(&self.bounds) {
+            return Ok(Contact::None);
+        }
+        let velocity = boy.velocity_y();
+        if velocity > 0 && boy_box.bottom() - velocity <= self.bounds.y + EDGE_FORGIVENESS {
+            boy.land_on(self.bounds.y);
+            return Ok(Contact::Landed);
+        }
+        if velocity < 0 && boy_box.y - velocity >= self.bounds.bottom() {
+            boy.bump_head(self.bounds.bottom() - boy_box.y);
+            return Ok(Contact::None);
+        }
+        // Out through whichever side is nearer. This moves him straight
+        // away, so the obstacles after this one see where he ends up.
+        let middle = boy_box.x + boy_box.width / 2;
+        if middle < self.bounds.x + self.bounds.width / 2 {
+            boy.shift(self.bounds.x - boy_box.right());
+        } else {
+            boy.shift(self.bounds.right() - boy_box.x);
+        }
+        Ok(Contact::Blocked)
+    }
+}
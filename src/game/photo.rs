@@ -0,0 +1,109 @@
+use super::actions::{Action, ActionState};
+use crate::engine::{timer::Timer, Camera};
+
+const PAN_SPEED: i16 = 8;
+const ZOOM_STEP: f64 = 0.02;
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+const HINT_TICKS: u16 = 180;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Filter {
+    None,
+    Grayscale,
+    Sepia,
+}
+
+impl Filter {
+    fn next(self) -> Self {
+        match self {
+            Filter::None => Filter::Grayscale,
+            Filter::Grayscale => Filter::Sepia,
+            Filter::Sepia => Filter::None,
+        }
+    }
+
+    // As a canvas `filter` value.
+    pub fn css(self) -> &'static str {
+        match self {
+            Filter::None => "none",
+            Filter::Grayscale => "grayscale(1)",
+            Filter::Sepia => "sepia(1)",
+        }
+    }
+}
+
+pub enum PhotoAction {
+    None,
+    Save,
+    Close,
+}
+
+// A free camera over the paused world with the HUD hidden. The direction
+// actions pan, plus and minus zoom, F changes the filter, Confirm saves a PNG
+// and Pause goes back to the pause menu.
+pub struct PhotoMode {
+    camera: Camera,
+    zoom: f64,
+    filter: Filter,
+    hint: Timer,
+    // Enter hides the hint first, so the saved frame is drawn without it.
+    saving: bool,
+}
+
+impl PhotoMode {
+    pub fn new(camera: &Camera) -> Self {
+        PhotoMode {
+            camera: camera.clone(),
+            zoom: 1.0,
+            filter: Filter::None,
+            hint: Timer::after(HINT_TICKS),
+            saving: false,
+        }
+    }
+
+    pub fn update(&mut self, actions: &ActionState) -> PhotoAction {
+        if self.saving {
+            self.saving = false;
+            return PhotoAction::Save;
+        }
+        if actions.was_just_pressed(Action::Pause) {
+            return PhotoAction::Close;
+        }
+        let axis = |negative: Action, positive: Action| {
+            i16::from(actions.is_held(positive)) - i16::from(actions.is_held(negative))
+        };
+        self.camera.pan_by(
+            axis(Action::Left, Action::Right) * PAN_SPEED,
+            axis(Action::Up, Action::Down) * PAN_SPEED,
+        );
+        let zoom = axis(Action::ZoomOut, Action::ZoomIn);
+        if zoom != 0 {
+            self.zoom =
+                (self.zoom + f64::from(zoom.signum()) * ZOOM_STEP).clamp(MIN_ZOOM, MAX_ZOOM);
+            self.camera.zoom_to(self.zoom);
+        }
+        self.camera.update();
+        if actions.was_just_pressed(Action::Filter) {
+            self.filter = self.filter.next();
+        }
+        if actions.was_just_pressed(Action::Confirm) {
+            self.hint = Timer::default();
+            self.saving = true;
+        }
+        self.hint.tick();
+        PhotoAction::None
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    pub fn shows_hint(&self) -> bool {
+        self.hint.is_running()
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{browser, engine::timer::Timer};
+use std::{cell::Cell, rc::Rc};
+
+// Below this charge and unplugged counts as low.
+const LOW_LEVEL: f64 = 0.2;
+// About a minute of updates between checks.
+const REFRESH_TICKS: u16 = 3600;
+
+// Keeps track of whether the battery is running low, for the battery saver
+// to switch on by itself. Browsers that don't report the battery never
+// count as low.
+pub struct BatteryWatch {
+    low: Rc<Cell<bool>>,
+    refresh: Timer,
+}
+
+impl BatteryWatch {
+    pub fn new() -> Self {
+        BatteryWatch {
+            low: Rc::new(Cell::new(false)),
+            refresh: Timer::default(),
+        }
+    }
+
+    // Call once per update.
+    pub fn update(&mut self) {
+        if self.refresh.is_running() {
+            self.refresh.tick();
+            return;
+        }
+        self.refresh = Timer::after(REFRESH_TICKS);
+        let low = Rc::clone(&self.low);
+        browser::spawn_local(async move {
+            match browser::battery().await {
+                Ok(battery) => low.set(
+                    battery.is_some_and(|battery| !battery.charging && battery.level <= LOW_LEVEL),
+                ),
+                Err(err) => warn!("Could not read the battery: {:#}", err),
+            }
+        });
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.low.get()
+    }
+}
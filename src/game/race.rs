@@ -0,0 +1,162 @@
+use super::{
+    ghost::Sample, headless::HeadlessRun, segments::SegmentLibrary, terrain::Terrain, Controls,
+    GameConfig, RedHatBoy,
+};
+use crate::{
+    browser,
+    net::peer::{Peer, PeerStatus},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum RaceMessage {
+    Start { seed: u32 },
+    Tick { tick: u32, controls: Controls },
+    // The sender is back on the title screen and wants another race.
+    Ready,
+}
+
+// What a race needs to replay the rival's run.
+pub struct Track<'a> {
+    pub boy: &'a RedHatBoy,
+    pub segments: &'a SegmentLibrary,
+    pub terrain: &'a Terrain,
+    pub config: &'a GameConfig,
+}
+
+// An online race against one remote player. The host picks the seed so both
+// sides build the same course, then each side sends what its player pressed
+// every tick. The course and the physics are deterministic, so replaying
+// those inputs from the seed rebuilds the rival's run exactly, and it is
+// shown as a ghost. Once both sides are back on the title screen the host
+// starts the next race.
+pub struct Race {
+    peer: Peer,
+    seed: Option<u32>,
+    ready: bool,
+    rival_ready: bool,
+    rival: Option<HeadlessRun>,
+    // The next tick each side sends, counted from the start of the race.
+    tick: u32,
+    rival_tick: u32,
+}
+
+impl Race {
+    pub fn join(
+        endpoint: &'static str,
+        ice_servers: &'static [&'static str],
+        room: String,
+    ) -> Self {
+        Race {
+            peer: Peer::connect(endpoint, ice_servers, room),
+            seed: None,
+            ready: true,
+            rival_ready: true,
+            rival: None,
+            tick: 0,
+            rival_tick: 0,
+        }
+    }
+
+    // Returns the seed on the update a race should start.
+    pub fn update(&mut self, track: &Track) -> Option<u32> {
+        if self.peer.status() != PeerStatus::Open {
+            return None;
+        }
+        let mut start = None;
+        for message in self.peer.receive() {
+            match serde_json::from_str(&message) {
+                Ok(RaceMessage::Start { seed }) => {
+                    self.begin(seed, track);
+                    start = Some(seed);
+                }
+                Ok(RaceMessage::Tick { tick, controls }) => self.replay(tick, &controls, track),
+                Ok(RaceMessage::Ready) => self.rival_ready = true,
+                Err(err) => {
+                    warn!("Ignoring race message {}: {}", message, err);
+                }
+            }
+        }
+        if self.seed.is_none() && self.peer.is_host() && self.ready && self.rival_ready {
+            let seed = browser::random_seed();
+            self.send(&RaceMessage::Start { seed });
+            self.begin(seed, track);
+            start = Some(seed);
+        }
+        start
+    }
+
+    // What the local player pressed this tick.
+    pub fn report(&mut self, controls: Controls) {
+        self.send(&RaceMessage::Tick {
+            tick: self.tick,
+            controls,
+        });
+        self.tick += 1;
+    }
+
+    // The local player went back to the title screen. The race waits there
+    // until the rival has too.
+    pub fn rematch(&mut self) {
+        if self.seed.take().is_some() {
+            self.ready = true;
+            self.send(&RaceMessage::Ready);
+        }
+    }
+
+    pub fn seed(&self) -> Option<u32> {
+        self.seed
+    }
+
+    pub fn rival(&self) -> Option<Sample> {
+        self.rival.as_ref().map(HeadlessRun::sample)
+    }
+
+    pub fn status(&self) -> PeerStatus {
+        self.peer.status()
+    }
+
+    fn begin(&mut self, seed: u32, track: &Track) {
+        self.seed = Some(seed);
+        self.ready = false;
+        self.rival_ready = false;
+        self.rival = Some(HeadlessRun::start(
+            track.boy,
+            track.segments,
+            track.terrain,
+            track.config,
+            seed,
+        ));
+        self.tick = 0;
+        self.rival_tick = 0;
+    }
+
+    // Ticks come in order over the data channel, and each side stops
+    // sending before it asks for the next race, so every one belongs to the
+    // race under way.
+    fn replay(&mut self, tick: u32, controls: &Controls, track: &Track) {
+        let Some(rival) = &mut self.rival else {
+            return;
+        };
+        if tick != self.rival_tick {
+            warn!(
+                "Race tick {} arrived when {} was due, the rival may drift",
+                tick, self.rival_tick
+            );
+        }
+        self.rival_tick = tick + 1;
+        if let Err(err) = rival.step(controls, track.segments, track.terrain, track.config) {
+            warn!("Could not replay the rival's tick {}: {:?}", tick, err);
+        }
+    }
+
+    fn send(&self, message: &RaceMessage) {
+        match serde_json::to_string(message) {
+            Ok(json) => self.peer.send(&json),
+            Err(err) => {
+                error!("Could not encode a race message: {}", err);
+            }
+        }
+    }
+}
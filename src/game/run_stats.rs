@@ -0,0 +1,47 @@
+use super::{StateKind, StateObserver};
+
+// Counts from one run for the game-over screen. Jumps and slides are seen
+// as the boy changes state; the rest are recorded where they happen.
+#[derive(Clone, Copy, Default)]
+pub struct RunStats {
+    jumps: u32,
+    slides: u32,
+    max_combo: u32,
+    platforms_landed: u32,
+    near_misses: u32,
+}
+
+impl RunStats {
+    pub fn record_combo(&mut self, chain: u32) {
+        self.max_combo = self.max_combo.max(chain);
+    }
+
+    pub fn record_landing(&mut self) {
+        self.platforms_landed += 1;
+    }
+
+    pub fn record_near_miss(&mut self) {
+        self.near_misses += 1;
+    }
+
+    // Each count with the locale key that shows it, in display order.
+    pub fn lines(&self) -> [(&'static str, u32); 5] {
+        [
+            ("run.jumps", self.jumps),
+            ("run.slides", self.slides),
+            ("run.max_combo", self.max_combo),
+            ("run.platforms", self.platforms_landed),
+            ("run.near_misses", self.near_misses),
+        ]
+    }
+}
+
+impl StateObserver for &mut RunStats {
+    fn on_enter(&mut self, state: StateKind) {
+        match state {
+            StateKind::Jumping => self.jumps += 1,
+            StateKind::Sliding => self.slides += 1,
+            _ => {}
+        }
+    }
+}
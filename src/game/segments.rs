@@ -0,0 +1,424 @@
+use super::{
+    coins::Coins,
+    obstacles::{Barrel, Barrier, Obstacle, Platform, Spring, Wall},
+    terrain::Terrain,
+    HEIGHT,
+};
+use crate::engine::{
+    self,
+    assets::{AssetStore, Scope},
+    rng::Rng,
+    Point, Prerendered, Rect, Sheet, SheetRect, Texture,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// One obstacle in `segments.json`. `x` is from the segment's left edge, and
+// `top` is how far above the ground the obstacle's top edge sits; stones
+// always rest on the ground, like springs. Platforms with `one_way` can be jumped through
+// from below, ones with `crumble_after` give way after being stood on for
+// that many ticks, and ones with `sway` rise that many pixels and sink back.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObstacleDef {
+    Stone {
+        x: i16,
+    },
+    Spring {
+        x: i16,
+    },
+    // Rolls toward the boy at `speed` pixels a frame faster than the scroll.
+    Barrel {
+        x: i16,
+        #[serde(default = "default_barrel_speed")]
+        speed: i16,
+    },
+    // A solid column of one tile, repeated from `top` down to `bottom` above
+    // the ground. Raised off the ground it makes a ceiling.
+    Wall {
+        x: i16,
+        top: i16,
+        #[serde(default)]
+        bottom: i16,
+        cell: String,
+    },
+    Platform {
+        x: i16,
+        top: i16,
+        cells: Vec<String>,
+        bounding_boxes: Vec<Rect>,
+        #[serde(default)]
+        one_way: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        crumble_after: Option<u16>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sway: Option<i16>,
+    },
+}
+
+fn default_barrel_speed() -> i16 {
+    3
+}
+
+impl ObstacleDef {
+    pub fn x(&self) -> i16 {
+        match self {
+            ObstacleDef::Stone { x }
+            | ObstacleDef::Spring { x }
+            | ObstacleDef::Barrel { x, .. }
+            | ObstacleDef::Wall { x, .. }
+            | ObstacleDef::Platform { x, .. } => *x,
+        }
+    }
+
+    pub fn move_to(&mut self, new_x: i16, new_top: i16) {
+        match self {
+            ObstacleDef::Stone { x }
+            | ObstacleDef::Spring { x }
+            | ObstacleDef::Barrel { x, .. } => *x = new_x,
+            ObstacleDef::Wall { x, top, bottom, .. } => {
+                *x = new_x;
+                *bottom += new_top - *top;
+                *top = new_top;
+            }
+            ObstacleDef::Platform { x, top, .. } => {
+                *x = new_x;
+                *top = new_top;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CollectibleDef {
+    x: i16,
+    top: i16,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SegmentDef {
+    pub name: String,
+    pub width: i16,
+    pub obstacles: Vec<ObstacleDef>,
+    #[serde(default)]
+    pub collectibles: Vec<CollectibleDef>,
+}
+
+#[derive(Deserialize)]
+pub struct SegmentsFile {
+    pub segments: Vec<SegmentDef>,
+}
+
+// Everything a library is built from, each sheet with its image.
+pub struct SegmentAssets {
+    pub segments: SegmentsFile,
+    pub tiles: (Sheet, Texture),
+    pub spring: (Sheet, Texture),
+    pub barrel: (Sheet, Texture),
+    pub stone: Texture,
+}
+
+impl SegmentAssets {
+    pub async fn load(assets: &AssetStore) -> Result<Self> {
+        Ok(SegmentAssets {
+            // The library keeps the segments it parses, so the file is only
+            // read again by the editor.
+            segments: assets.data_in(Scope::Editor, "segments").await?,
+            tiles: (assets.sheet("tiles").await?, assets.image("tiles").await?),
+            spring: (assets.sheet("spring").await?, assets.image("spring").await?),
+            barrel: (assets.sheet("barrel").await?, assets.image("barrel").await?),
+            stone: assets.image("stone").await?,
+        })
+    }
+}
+
+// The course layouts from `segments.json`. The first segment always opens a
+// run; the rest are picked with the run's RNG, so the same seed builds the
+// same course. Each distinct row of platform tiles is composited once.
+pub struct SegmentLibrary {
+    segments: Vec<SegmentDef>,
+    composites: HashMap<Vec<String>, Prerendered>,
+    stone: Texture,
+    spring: Texture,
+    spring_frames: [Rect; 3],
+    barrel: Texture,
+    barrel_frames: Vec<Rect>,
+    tiles: Texture,
+    wall_tiles: HashMap<String, Rect>,
+}
+
+fn frame_rect(frame: &SheetRect) -> Rect {
+    Rect {
+        x: frame.x,
+        y: frame.y,
+        width: frame.w,
+        height: frame.h,
+    }
+}
+
+const SPRING_CELLS: [&str; 3] = ["rest.png", "compressed.png", "extended.png"];
+// The top of the pad and everything under it, in the rest cell.
+const SPRING_PAD: Rect = Rect {
+    x: 0,
+    y: 18,
+    width: 64,
+    height: 38,
+};
+
+impl SegmentLibrary {
+    pub async fn load(assets: &AssetStore) -> Result<Self> {
+        SegmentLibrary::new(SegmentAssets::load(assets).await?)
+    }
+
+    pub fn new(assets: SegmentAssets) -> Result<Self> {
+        let SegmentAssets {
+            segments: file,
+            tiles: (tiles, tiles_image),
+            spring: (springs, spring),
+            barrel: (barrels, barrel),
+            stone,
+        } = assets;
+        if file.segments.is_empty() {
+            return Err(anyhow!("segments.json has no segments"));
+        }
+        let mut composites = HashMap::new();
+        let mut wall_tiles = HashMap::new();
+        for segment in &file.segments {
+            for obstacle in &segment.obstacles {
+                if let ObstacleDef::Wall { cell, .. } = obstacle {
+                    tiles
+                        .validate(&tiles_image, std::slice::from_ref(cell))
+                        .with_context(|| format!("Tiles sheet, segment {}", segment.name))?;
+                    wall_tiles.insert(cell.clone(), frame_rect(&tiles.frames[cell].frame));
+                }
+                let ObstacleDef::Platform { cells, .. } = obstacle else {
+                    continue;
+                };
+                if composites.contains_key(cells) {
+                    continue;
+                }
+                tiles
+                    .validate(&tiles_image, cells)
+                    .with_context(|| format!("Tiles sheet, segment {}", segment.name))?;
+                let frames: Vec<Rect> = cells
+                    .iter()
+                    .map(|cell| frame_rect(&tiles.frames[cell].frame))
+                    .collect();
+                composites.insert(cells.clone(), Prerendered::new(&tiles_image, &frames)?);
+            }
+        }
+        springs
+            .validate(&spring, &SPRING_CELLS)
+            .context("Spring sheet")?;
+        let spring_frames = SPRING_CELLS.map(|cell| frame_rect(&springs.frames[cell].frame));
+        if barrels.frames.is_empty() {
+            return Err(anyhow!("Barrel sheet has no frames"));
+        }
+        let barrel_cells: Vec<String> = (1..=barrels.frames.len())
+            .map(|frame| format!("{}.png", frame))
+            .collect();
+        barrels
+            .validate(&barrel, &barrel_cells)
+            .context("Barrel sheet")?;
+        let barrel_frames = barrel_cells
+            .iter()
+            .map(|cell| frame_rect(&barrels.frames[cell].frame))
+            .collect();
+        Ok(SegmentLibrary {
+            segments: file.segments,
+            composites,
+            stone,
+            spring,
+            spring_frames,
+            barrel,
+            barrel_frames,
+            tiles: tiles_image,
+            wall_tiles,
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn opening(&self) -> usize {
+        0
+    }
+
+    pub fn pick(&self, rng: &mut Rng) -> usize {
+        rng.range(0, self.segments.len() as i16) as usize
+    }
+
+    pub fn width(&self, index: usize) -> i16 {
+        self.segments[index].width
+    }
+
+    pub fn obstacles(&self, index: usize) -> &[ObstacleDef] {
+        &self.segments[index].obstacles
+    }
+
+    // One of each kind of obstacle the library knows how to draw, at x 0.
+    pub fn palette(&self) -> Vec<ObstacleDef> {
+        let mut palette = vec![
+            ObstacleDef::Stone { x: 0 },
+            ObstacleDef::Spring { x: 0 },
+            ObstacleDef::Barrel {
+                x: 0,
+                speed: default_barrel_speed(),
+            },
+        ];
+        palette.extend(
+            self.segments
+                .iter()
+                .flat_map(|segment| &segment.obstacles)
+                .find(|obstacle| matches!(obstacle, ObstacleDef::Wall { .. }))
+                .cloned(),
+        );
+        for obstacle in self.segments.iter().flat_map(|segment| &segment.obstacles) {
+            let ObstacleDef::Platform {
+                top,
+                cells,
+                bounding_boxes,
+                one_way,
+                crumble_after,
+                sway,
+                ..
+            } = obstacle
+            else {
+                continue;
+            };
+            let known = palette.iter().any(|known| {
+                matches!(
+                    known,
+                    ObstacleDef::Platform {
+                        cells: known_cells,
+                        one_way: known_one_way,
+                        crumble_after: known_crumble_after,
+                        sway: known_sway,
+                        ..
+                    } if known_cells == cells
+                        && known_one_way == one_way
+                        && known_crumble_after == crumble_after
+                        && known_sway == sway
+                )
+            });
+            if !known {
+                palette.push(ObstacleDef::Platform {
+                    x: 0,
+                    top: *top,
+                    cells: cells.clone(),
+                    bounding_boxes: bounding_boxes.clone(),
+                    one_way: *one_way,
+                    crumble_after: *crumble_after,
+                    sway: *sway,
+                });
+            }
+        }
+        palette
+    }
+
+    // Places segment `index` with its left edge at screen x `start`. Stones
+    // that would land in a pit are left out.
+    pub fn spawn(
+        &self,
+        index: usize,
+        start: i16,
+        distance: i32,
+        terrain: &Terrain,
+        coins: &mut Coins,
+    ) -> Vec<Box<dyn Obstacle>> {
+        let segment = &self.segments[index];
+        for collectible in &segment.collectibles {
+            let x = start + collectible.x;
+            if let Some(ground) = terrain.ground_at(distance + i32::from(x)) {
+                coins.place(Point {
+                    x,
+                    y: ground - collectible.top,
+                });
+            }
+        }
+        segment
+            .obstacles
+            .iter()
+            .filter_map(|obstacle| self.build(obstacle, start, distance, terrain))
+            .collect()
+    }
+
+    pub fn build(
+        &self,
+        obstacle: &ObstacleDef,
+        start: i16,
+        distance: i32,
+        terrain: &Terrain,
+    ) -> Option<Box<dyn Obstacle>> {
+        let x = start + obstacle.x();
+        // The ground under the middle of something `width` wide. Stones,
+        // springs and barrels stand on it; walls and platforms are placed
+        // `top` above the ground at their left edge, as in the editor.
+        let ground = |width: i16| terrain.ground_at(distance + i32::from(x + width / 2));
+        match obstacle {
+            ObstacleDef::Stone { .. } => Some(Box::new(Barrier::new(engine::Image::new(
+                self.stone.clone(),
+                Point {
+                    x,
+                    y: ground(self.stone.width() as i16)? - self.stone.height() as i16,
+                },
+            )))),
+            ObstacleDef::Spring { .. } => Some(Box::new(Spring::new(
+                self.spring.clone(),
+                self.spring_frames,
+                SPRING_PAD,
+                Point {
+                    x,
+                    y: ground(self.spring_frames[0].width)? - self.spring_frames[0].height,
+                },
+            ))),
+            ObstacleDef::Barrel { speed, .. } => Some(Box::new(Barrel::new(
+                self.barrel.clone(),
+                self.barrel_frames.clone(),
+                Point {
+                    x,
+                    y: ground(self.barrel_frames[0].width)? - self.barrel_frames[0].height,
+                },
+                *speed,
+            ))),
+            ObstacleDef::Wall {
+                top, bottom, cell, ..
+            } => {
+                let ground = ground(0).unwrap_or(HEIGHT);
+                Some(Box::new(Wall::new(
+                    self.tiles.clone(),
+                    *self.wall_tiles.get(cell)?,
+                    Rect {
+                        x,
+                        y: ground - top,
+                        width: self.wall_tiles.get(cell)?.width,
+                        height: top - bottom,
+                    },
+                )))
+            }
+            ObstacleDef::Platform {
+                top,
+                cells,
+                bounding_boxes,
+                one_way,
+                crumble_after,
+                sway,
+                ..
+            } => Some(Box::new(
+                Platform::new(
+                    self.composites.get(cells)?.clone(),
+                    bounding_boxes.clone(),
+                    *one_way,
+                    Point {
+                        x,
+                        y: ground(0).unwrap_or(HEIGHT) - top,
+                    },
+                )
+                .with_crumble(*crumble_after)
+                .with_sway(*sway),
+            )),
+        }
+    }
+}
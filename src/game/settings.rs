@@ -0,0 +1,385 @@
+use super::{
+    actions::{Action, ActionState, KeyBindings, PLAYER_ONE_KEYS, PLAYER_TWO_KEYS},
+    HEIGHT, WIDTH,
+};
+use crate::{
+    browser,
+    engine::{
+        style::{Color, Palette, TextAlign, TextStyle},
+        ui::Ui,
+        Point, Rect, Renderer, Scaling,
+    },
+    i18n::Locale,
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "walk-the-dog.settings";
+const VOLUME_STEP: f32 = 0.1;
+// Baseline of the first row and the spacing between rows.
+const FIRST_ROW: i16 = 192;
+const ROW_STEP: i16 = 30;
+// On the volume row, where the other rows show their value.
+const VOLUME_SLIDER: Rect = Rect {
+    x: WIDTH - 260,
+    y: FIRST_ROW + 2 * ROW_STEP - 10,
+    width: 160,
+    height: 24,
+};
+
+const MENU_TEXT: TextStyle = TextStyle {
+    font: "bold 20px sans-serif",
+    color: Color::BLACK,
+    align: TextAlign::Left,
+};
+
+const MENU_VALUE: TextStyle = TextStyle {
+    align: TextAlign::Right,
+    ..MENU_TEXT
+};
+
+const MENU_TITLE: TextStyle = TextStyle {
+    align: TextAlign::Center,
+    ..MENU_TEXT
+};
+
+const MENU_HINT: TextStyle = TextStyle {
+    font: "14px sans-serif",
+    ..MENU_TITLE
+};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyLayout {
+    // Player one on the arrows and Space, player two on WASD.
+    Arrows,
+    Wasd,
+}
+
+impl KeyLayout {
+    pub fn player_keys(self, index: usize) -> &'static KeyBindings {
+        match (self, index) {
+            (KeyLayout::Arrows, 0) | (KeyLayout::Wasd, 1) => PLAYER_ONE_KEYS,
+            _ => PLAYER_TWO_KEYS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BatterySaver {
+    // On while the battery is low and not charging.
+    Auto,
+    On,
+    Off,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    pub hitboxes: bool,
+    pub sound: bool,
+    // 0 to 1.
+    pub volume: f32,
+    pub screen_shake: bool,
+    pub colorblind: bool,
+    // Drops screen shake, particle bursts and background scrolling.
+    pub reduced_motion: bool,
+    pub layout: KeyLayout,
+    // A strip along the bottom previewing the obstacles ahead.
+    pub minimap: bool,
+    // Vibration on phones and rumble on gamepads that support them.
+    pub haptics: bool,
+    pub scaling: Scaling,
+    // Draws at 30fps.
+    pub battery_saver: BatterySaver,
+    // Silenced for this visit by `?mute`, leaving the saved choice alone.
+    #[serde(skip)]
+    pub muted: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            hitboxes: true,
+            sound: true,
+            volume: 1.0,
+            screen_shake: true,
+            colorblind: false,
+            reduced_motion: browser::prefers_reduced_motion().unwrap_or(false),
+            layout: KeyLayout::Arrows,
+            minimap: false,
+            haptics: true,
+            scaling: Scaling::Smooth,
+            battery_saver: BatterySaver::Auto,
+            muted: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn palette(&self) -> Palette {
+        if self.colorblind {
+            Palette::COLORBLIND
+        } else {
+            Palette::STANDARD
+        }
+    }
+
+    pub fn saves_battery(&self, battery_low: bool) -> bool {
+        match self.battery_saver {
+            BatterySaver::Auto => battery_low,
+            BatterySaver::On => true,
+            BatterySaver::Off => false,
+        }
+    }
+
+    pub fn audible_volume(&self) -> f32 {
+        if self.sound && !self.muted {
+            self.volume
+        } else {
+            0.0
+        }
+    }
+
+    pub fn load() -> Result<Self> {
+        match browser::local_storage()?
+            .get_item(STORAGE_KEY)
+            .map_err(|err| anyhow!("Could not read {}: {:#?}", STORAGE_KEY, err))?
+        {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|err| anyhow!("Could not parse {}: {}", STORAGE_KEY, err)),
+            None => Ok(Settings::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        browser::local_storage()?
+            .set_item(STORAGE_KEY, &json)
+            .map_err(|err| anyhow!("Could not write {}: {:#?}", STORAGE_KEY, err))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Item {
+    Hitboxes,
+    Sound,
+    Volume,
+    ScreenShake,
+    Colorblind,
+    ReducedMotion,
+    Keys,
+    Minimap,
+    Haptics,
+    Scaling,
+    BatterySaver,
+}
+
+impl Item {
+    const ALL: [Item; 11] = [
+        Item::Hitboxes,
+        Item::Sound,
+        Item::Volume,
+        Item::ScreenShake,
+        Item::Colorblind,
+        Item::ReducedMotion,
+        Item::Keys,
+        Item::Minimap,
+        Item::Haptics,
+        Item::Scaling,
+        Item::BatterySaver,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Item::Hitboxes => "settings.hitboxes",
+            Item::Sound => "settings.sound",
+            Item::Volume => "settings.volume",
+            Item::ScreenShake => "settings.shake",
+            Item::Colorblind => "settings.colorblind",
+            Item::ReducedMotion => "settings.reduced_motion",
+            Item::Keys => "settings.keys",
+            Item::Minimap => "settings.minimap",
+            Item::Haptics => "settings.haptics",
+            Item::Scaling => "settings.scaling",
+            Item::BatterySaver => "settings.battery_saver",
+        }
+    }
+
+    // Volume shows a slider instead.
+    fn value(self, settings: &Settings) -> &'static str {
+        let on_off = |on: bool| if on { "settings.on" } else { "settings.off" };
+        match self {
+            Item::Hitboxes => on_off(settings.hitboxes),
+            Item::Sound => on_off(settings.sound),
+            Item::Volume => "",
+            Item::ScreenShake => on_off(settings.screen_shake),
+            Item::Colorblind => on_off(settings.colorblind),
+            Item::ReducedMotion => on_off(settings.reduced_motion),
+            Item::Keys => match settings.layout {
+                KeyLayout::Arrows => "settings.keys.arrows",
+                KeyLayout::Wasd => "settings.keys.wasd",
+            },
+            Item::Minimap => on_off(settings.minimap),
+            Item::Haptics => on_off(settings.haptics),
+            Item::Scaling => match settings.scaling {
+                Scaling::Smooth => "settings.scaling.smooth",
+                Scaling::Pixelated => "settings.scaling.pixelated",
+                Scaling::Integer => "settings.scaling.integer",
+            },
+            Item::BatterySaver => match settings.battery_saver {
+                BatterySaver::Auto => "settings.battery_saver.auto",
+                BatterySaver::On => "settings.on",
+                BatterySaver::Off => "settings.off",
+            },
+        }
+    }
+
+    fn toggle(self, settings: &mut Settings) {
+        match self {
+            Item::Hitboxes => settings.hitboxes = !settings.hitboxes,
+            Item::Sound => settings.sound = !settings.sound,
+            Item::Volume => {}
+            Item::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            Item::Colorblind => settings.colorblind = !settings.colorblind,
+            Item::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            Item::Minimap => settings.minimap = !settings.minimap,
+            Item::Haptics => settings.haptics = !settings.haptics,
+            Item::Keys => {
+                settings.layout = match settings.layout {
+                    KeyLayout::Arrows => KeyLayout::Wasd,
+                    KeyLayout::Wasd => KeyLayout::Arrows,
+                }
+            }
+            Item::Scaling => {
+                settings.scaling = match settings.scaling {
+                    Scaling::Smooth => Scaling::Pixelated,
+                    Scaling::Pixelated => Scaling::Integer,
+                    Scaling::Integer => Scaling::Smooth,
+                }
+            }
+            Item::BatterySaver => {
+                settings.battery_saver = match settings.battery_saver {
+                    BatterySaver::Auto => BatterySaver::On,
+                    BatterySaver::On => BatterySaver::Off,
+                    BatterySaver::Off => BatterySaver::Auto,
+                }
+            }
+        }
+    }
+}
+
+pub enum MenuAction {
+    None,
+    Changed,
+    Close,
+}
+
+// Up and Down pick a row, Confirm flips it, Left and Right set the volume
+// and Pause goes back. Actions act once per press. Clicking a row flips it
+// too, and the volume slider can be dragged.
+pub struct SettingsMenu {
+    cursor: usize,
+}
+
+impl SettingsMenu {
+    pub fn new() -> Self {
+        SettingsMenu { cursor: 0 }
+    }
+
+    pub fn update(
+        &mut self,
+        actions: &ActionState,
+        ui: &mut Ui,
+        locale: &Locale,
+        settings: &mut Settings,
+    ) -> MenuAction {
+        let clicked = self.declare(ui, locale, settings);
+        let slid = ui.slider(VOLUME_SLIDER, &mut settings.volume);
+        if let Some(row) = clicked {
+            self.cursor = row;
+            Item::ALL[row].toggle(settings);
+            return MenuAction::Changed;
+        }
+        if slid {
+            return MenuAction::Changed;
+        }
+        let up = actions.was_just_pressed(Action::Up);
+        let down = actions.was_just_pressed(Action::Down);
+        let left = actions.was_just_pressed(Action::Left);
+        let right = actions.was_just_pressed(Action::Right);
+        let toggle = actions.was_just_pressed(Action::Confirm);
+        let back = actions.was_just_pressed(Action::Pause);
+        if back {
+            MenuAction::Close
+        } else if toggle {
+            Item::ALL[self.cursor].toggle(settings);
+            MenuAction::Changed
+        } else if matches!(Item::ALL[self.cursor], Item::Volume) && (left || right) {
+            let step = if right { VOLUME_STEP } else { -VOLUME_STEP };
+            settings.volume = (settings.volume + step).clamp(0.0, 1.0);
+            MenuAction::Changed
+        } else {
+            if up {
+                self.cursor = (self.cursor + Item::ALL.len() - 1) % Item::ALL.len();
+            }
+            if down {
+                self.cursor = (self.cursor + 1) % Item::ALL.len();
+            }
+            MenuAction::None
+        }
+    }
+
+    // Lays the rows out on `ui` and returns the one clicked, if any.
+    fn declare(&self, ui: &mut Ui, locale: &Locale, settings: &Settings) -> Option<usize> {
+        let mut clicked = None;
+        for (row, item) in Item::ALL.iter().enumerate() {
+            let y = FIRST_ROW + row as i16 * ROW_STEP;
+            let rect = Rect {
+                x: 90,
+                y: y - 22,
+                width: WIDTH - 180,
+                height: 30,
+            };
+            if ui.button(rect, locale.tr(item.label()), MENU_TEXT, row == self.cursor) {
+                clicked = Some(row);
+            }
+            ui.label(
+                locale.tr(item.value(settings)),
+                Point { x: WIDTH - 100, y },
+                MENU_VALUE,
+            );
+        }
+        clicked.filter(|row| !matches!(Item::ALL[*row], Item::Volume))
+    }
+
+    // The rows are widgets on the `Ui` passed to `update`.
+    pub fn draw(&self, renderer: &Renderer, locale: &Locale) {
+        renderer.fill_rect(
+            &Rect {
+                x: 0,
+                y: 0,
+                width: WIDTH,
+                height: HEIGHT,
+            },
+            Color::WHITE.with_alpha(0.85),
+        );
+        renderer.draw_text(
+            locale.tr("settings.title"),
+            &Point {
+                x: WIDTH / 2,
+                y: 160,
+            },
+            &MENU_TITLE,
+        );
+        renderer.draw_text(
+            locale.tr("settings.hint"),
+            &Point {
+                x: WIDTH / 2,
+                y: 524,
+            },
+            &MENU_HINT,
+        );
+    }
+}
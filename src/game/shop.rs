@@ -0,0 +1,394 @@
+use super::{
+    actions::{Action, ActionState},
+    settings::MenuAction,
+    HEIGHT, WIDTH,
+};
+use crate::{
+    browser,
+    engine::{
+        assets::AssetStore,
+        recolor::{self, HueSwap},
+        style::{Color, TextAlign, TextStyle},
+        ui::Ui,
+        Point, Rect, Renderer, Texture,
+    },
+    i18n::Locale,
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const STORAGE_KEY: &str = "walk-the-dog.wardrobe";
+
+const SHOP_TEXT: TextStyle = TextStyle {
+    font: "bold 20px sans-serif",
+    color: Color::BLACK,
+    align: TextAlign::Left,
+};
+
+const SHOP_VALUE: TextStyle = TextStyle {
+    align: TextAlign::Right,
+    ..SHOP_TEXT
+};
+
+const SHOP_TITLE: TextStyle = TextStyle {
+    align: TextAlign::Center,
+    ..SHOP_TEXT
+};
+
+const SHOP_HINT: TextStyle = TextStyle {
+    font: "14px sans-serif",
+    ..SHOP_TITLE
+};
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum ItemDescriptor {
+    Outfit {
+        id: String,
+        price: u32,
+        swaps: Vec<HueSwap>,
+    },
+    Background {
+        id: String,
+        price: u32,
+        background: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct ShopDescriptor {
+    items: Vec<ItemDescriptor>,
+}
+
+enum Look {
+    // Recolors the boy's sheet.
+    Outfit(Vec<HueSwap>),
+    Background(Texture),
+}
+
+struct Item {
+    id: String,
+    price: u32,
+    look: Look,
+}
+
+impl Item {
+    async fn load(assets: &AssetStore, descriptor: ItemDescriptor) -> Result<Self> {
+        Ok(match descriptor {
+            ItemDescriptor::Outfit { id, price, swaps } => Item {
+                look: Look::Outfit(swaps),
+                id,
+                price,
+            },
+            ItemDescriptor::Background {
+                id,
+                price,
+                background,
+            } => Item {
+                look: Look::Background(assets.image(&background).await?),
+                id,
+                price,
+            },
+        })
+    }
+}
+
+// What the player has bought and is wearing, kept between runs along with
+// the treats they have left to spend.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Wardrobe {
+    treats: u32,
+    owned: Vec<String>,
+    outfit: Option<String>,
+    background: Option<String>,
+}
+
+impl Wardrobe {
+    fn load() -> Result<Self> {
+        match browser::local_storage()?
+            .get_item(STORAGE_KEY)
+            .map_err(|err| anyhow!("Could not read {}: {:#?}", STORAGE_KEY, err))?
+        {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|err| anyhow!("Could not parse {}: {}", STORAGE_KEY, err)),
+            None => Ok(Wardrobe::default()),
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        browser::local_storage()?
+            .set_item(STORAGE_KEY, &json)
+            .map_err(|err| anyhow!("Could not write {}: {:#?}", STORAGE_KEY, err))
+    }
+}
+
+// Cosmetics from `shop.json`, bought with the coins collected over every run.
+// Each outfit's recolored sheet is made when it is put on and let go of when
+// it is taken off.
+pub struct Shop {
+    items: Vec<Item>,
+    wardrobe: Wardrobe,
+    sheet: Texture,
+    skins: HashMap<String, Texture>,
+    // Worn for this visit over the wardrobe's outfit, without buying it.
+    trial: Option<String>,
+}
+
+impl Shop {
+    // `sheet` is the boy's sheet as drawn.
+    pub async fn load(assets: &AssetStore, sheet: Texture) -> Result<Self> {
+        let descriptor: ShopDescriptor = assets.data("shop").await?;
+        let mut items = vec![];
+        for item in descriptor.items {
+            items.push(Item::load(assets, item).await?);
+        }
+        let wardrobe = Wardrobe::load().unwrap_or_else(|err| {
+            warn!("Could not load the wardrobe: {:?}", err);
+            Wardrobe::default()
+        });
+        let mut shop = Shop {
+            items,
+            wardrobe,
+            sheet,
+            skins: HashMap::new(),
+            trial: None,
+        };
+        shop.prepare_skin(shop.wardrobe.outfit.clone());
+        Ok(shop)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.wardrobe.save()
+    }
+
+    pub fn deposit(&mut self, treats: u32) {
+        self.wardrobe.treats += treats;
+    }
+
+    pub fn withdraw(&mut self, treats: u32) {
+        self.wardrobe.treats = self.wardrobe.treats.saturating_sub(treats);
+    }
+
+    fn is_owned(&self, item: &Item) -> bool {
+        self.wardrobe.owned.contains(&item.id)
+    }
+
+    fn is_worn(&self, item: &Item) -> bool {
+        let worn = match item.look {
+            Look::Outfit(_) => &self.wardrobe.outfit,
+            Look::Background(_) => &self.wardrobe.background,
+        };
+        worn.as_ref() == Some(&item.id)
+    }
+
+    fn worn(&self, slot: &Option<String>) -> Option<&Look> {
+        let id = slot.as_ref()?;
+        self.items
+            .iter()
+            .find(|item| &item.id == id)
+            .map(|item| &item.look)
+    }
+
+    // Wears `outfit` until an outfit is chosen in the shop, as `?character=`
+    // asks.
+    pub fn try_on(&mut self, outfit: &str) -> Result<()> {
+        let outfit = Some(outfit.to_string());
+        if !matches!(self.worn(&outfit), Some(Look::Outfit(_))) {
+            return Err(anyhow!("No outfit named {:?} in the shop", outfit));
+        }
+        self.prepare_skin(outfit.clone());
+        self.trial = outfit;
+        Ok(())
+    }
+
+    // The boy's sheet in the outfit he is wearing.
+    pub fn skin(&self) -> Texture {
+        self.trial
+            .as_ref()
+            .or(self.wardrobe.outfit.as_ref())
+            .and_then(|outfit| self.skins.get(outfit))
+            .unwrap_or(&self.sheet)
+            .clone()
+    }
+
+    fn prepare_skin(&mut self, outfit: Option<String>) {
+        let Some(outfit) = outfit else {
+            return;
+        };
+        if self.skins.contains_key(&outfit) {
+            return;
+        }
+        let Some(Look::Outfit(swaps)) = self.worn(&Some(outfit.clone())) else {
+            return;
+        };
+        match recolor::recolor(&self.sheet, &outfit, swaps) {
+            Ok(skin) => {
+                self.skins.insert(outfit, skin);
+            }
+            Err(err) => warn!("Could not recolor the sheet for {}: {:?}", outfit, err),
+        }
+    }
+
+    // Recolored sheets are as big as the boy's own, so only the one being
+    // worn is kept.
+    fn release_skins(&mut self) {
+        let worn = self.trial.as_ref().or(self.wardrobe.outfit.as_ref());
+        self.skins.retain(|outfit, _| Some(outfit) == worn);
+    }
+
+    pub fn background(&self) -> Option<&Texture> {
+        match self.worn(&self.wardrobe.background)? {
+            Look::Background(background) => Some(background),
+            Look::Outfit(_) => None,
+        }
+    }
+
+    // Buys the item if the treats cover it, then wears it, or takes it off
+    // if it was already on. Returns whether anything changed.
+    fn choose(&mut self, index: usize) -> bool {
+        let item = &self.items[index];
+        if !self.is_owned(item) {
+            if self.wardrobe.treats < item.price {
+                return false;
+            }
+            self.wardrobe.treats -= item.price;
+            self.wardrobe.owned.push(item.id.clone());
+        }
+        let worn = !self.is_worn(item);
+        let slot = match item.look {
+            Look::Outfit(_) => &mut self.wardrobe.outfit,
+            Look::Background(_) => &mut self.wardrobe.background,
+        };
+        if matches!(item.look, Look::Outfit(_)) {
+            self.trial = None;
+        }
+        *slot = worn.then(|| item.id.clone());
+        self.prepare_skin(self.wardrobe.outfit.clone());
+        self.release_skins();
+        true
+    }
+}
+
+// Laid out and driven like the settings menu: Up and Down pick an item,
+// Confirm or a click buys or wears it and Pause goes back.
+pub struct ShopMenu {
+    cursor: usize,
+}
+
+impl ShopMenu {
+    pub fn new() -> Self {
+        ShopMenu { cursor: 0 }
+    }
+
+    pub fn update(
+        &mut self,
+        actions: &ActionState,
+        ui: &mut Ui,
+        locale: &Locale,
+        shop: &mut Shop,
+    ) -> MenuAction {
+        if let Some(row) = self.declare(ui, locale, shop) {
+            self.cursor = row;
+            return Self::choose(shop, row);
+        }
+        let up = actions.was_just_pressed(Action::Up);
+        let down = actions.was_just_pressed(Action::Down);
+        let choose = actions.was_just_pressed(Action::Confirm);
+        let back = actions.was_just_pressed(Action::Pause);
+        if back {
+            MenuAction::Close
+        } else if shop.items.is_empty() {
+            MenuAction::None
+        } else if choose {
+            Self::choose(shop, self.cursor)
+        } else {
+            let count = shop.items.len();
+            if up {
+                self.cursor = (self.cursor + count - 1) % count;
+            }
+            if down {
+                self.cursor = (self.cursor + 1) % count;
+            }
+            MenuAction::None
+        }
+    }
+
+    fn choose(shop: &mut Shop, row: usize) -> MenuAction {
+        if shop.choose(row) {
+            MenuAction::Changed
+        } else {
+            MenuAction::None
+        }
+    }
+
+    // Lays the items out on `ui` and returns the one clicked, if any.
+    fn declare(&self, ui: &mut Ui, locale: &Locale, shop: &Shop) -> Option<usize> {
+        let mut clicked = None;
+        for (row, item) in shop.items.iter().enumerate() {
+            let y = 220 + row as i16 * 36;
+            let rect = Rect {
+                x: 90,
+                y: y - 24,
+                width: WIDTH - 180,
+                height: 34,
+            };
+            let name = locale.tr(&format!("shop.item.{}", item.id)).to_string();
+            if ui.button(rect, name, SHOP_TEXT, row == self.cursor) {
+                clicked = Some(row);
+            }
+            let value = if shop.is_worn(item) {
+                locale.tr("shop.worn").to_string()
+            } else if shop.is_owned(item) {
+                locale.tr("shop.owned").to_string()
+            } else {
+                locale
+                    .tr("shop.price")
+                    .replace("{price}", &item.price.to_string())
+            };
+            ui.label(value, Point { x: WIDTH - 100, y }, SHOP_VALUE);
+        }
+        clicked
+    }
+
+    // The items are widgets on the `Ui` passed to `update`.
+    pub fn draw(&self, renderer: &Renderer, locale: &Locale, shop: &Shop) {
+        renderer.fill_rect(
+            &Rect {
+                x: 0,
+                y: 0,
+                width: WIDTH,
+                height: HEIGHT,
+            },
+            Color::WHITE.with_alpha(0.85),
+        );
+        renderer.draw_text(
+            locale.tr("shop.title"),
+            &Point {
+                x: WIDTH / 2,
+                y: 140,
+            },
+            &SHOP_TITLE,
+        );
+        renderer.draw_text(
+            &locale
+                .tr("shop.treats")
+                .replace("{treats}", &shop.wardrobe.treats.to_string()),
+            &Point {
+                x: WIDTH / 2,
+                y: 170,
+            },
+            &SHOP_TITLE,
+        );
+        renderer.draw_text(
+            locale.tr("shop.hint"),
+            &Point {
+                x: WIDTH / 2,
+                y: 466,
+            },
+            &SHOP_HINT,
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use super::{PIXELS_PER_METER, WIDTH};
+use crate::{
+    engine::{font::BitmapFont, style::Color, Camera, Point, Rect, Renderer},
+    i18n::Locale,
+};
+
+const GROUND: i16 = 600;
+const BOARD_TOP: i16 = 430;
+const BOARD_PADDING: i16 = 6;
+const POST_WIDTH: i16 = 6;
+const SPAWN_AHEAD: i32 = WIDTH as i32 + 100;
+const BOARD_COLOR: Color = Color::rgb(0xC8, 0x9B, 0x5E);
+const POST_COLOR: Color = Color::rgb(0x6B, 0x4A, 0x2B);
+const FONT: BitmapFont = BitmapFont {
+    scale: 3,
+    color: Color::rgb(0x3B, 0x24, 0x10),
+};
+
+#[derive(Clone, Copy)]
+pub struct SignpostConfig {
+    pub every: i32,
+    // Locale keys of the messages, shown in turn.
+    pub messages: &'static [&'static str],
+}
+
+#[derive(Clone)]
+struct Signpost {
+    x: i16,
+    text: String,
+}
+
+impl Signpost {
+    fn board(&self) -> Rect {
+        let width = FONT.measure(&self.text) + BOARD_PADDING * 2;
+        Rect {
+            x: self.x - width / 2,
+            y: BOARD_TOP,
+            width,
+            height: FONT.line_height() + BOARD_PADDING * 2,
+        }
+    }
+
+    fn bounding_box(&self) -> Rect {
+        let board = self.board();
+        Rect {
+            height: GROUND - board.y,
+            ..board
+        }
+    }
+
+    fn draw(&self, renderer: &Renderer) {
+        let board = self.board();
+        renderer.fill_rect(
+            &Rect {
+                x: self.x - POST_WIDTH / 2,
+                y: board.bottom(),
+                width: POST_WIDTH,
+                height: GROUND - board.bottom(),
+            },
+            POST_COLOR,
+        );
+        renderer.fill_rect(&board, BOARD_COLOR);
+        FONT.draw(
+            renderer,
+            &self.text,
+            &Point {
+                x: board.x + BOARD_PADDING,
+                y: board.y + BOARD_PADDING,
+            },
+        );
+    }
+}
+
+// Signs alternate between distance markers and the configured messages,
+// half an interval apart, so the player passes one every `every / 2` meters.
+#[derive(Clone)]
+pub struct Signposts {
+    posts: Vec<Signpost>,
+    next_sign: i32,
+    placed: usize,
+}
+
+impl Signposts {
+    pub fn new(config: &SignpostConfig) -> Self {
+        Signposts {
+            posts: vec![],
+            next_sign: config.every / 2,
+            placed: 0,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        distance: i32,
+        anchor_x: i16,
+        velocity: i16,
+        config: &SignpostConfig,
+        locale: &Locale,
+    ) {
+        self.posts.iter_mut().for_each(|post| post.x += velocity);
+        self.posts.retain(|post| post.bounding_box().right() > 0);
+
+        while distance + SPAWN_AHEAD >= self.next_sign * PIXELS_PER_METER {
+            let text = if self.placed % 2 == 1 {
+                Some(format!("{}m", self.next_sign))
+            } else if config.messages.is_empty() {
+                None
+            } else {
+                let key = config.messages[self.placed / 2 % config.messages.len()];
+                Some(locale.tr_ascii(key).to_string())
+            };
+            if let Some(text) = text {
+                let ahead = self.next_sign * PIXELS_PER_METER - distance;
+                self.posts.push(Signpost {
+                    x: anchor_x + ahead as i16,
+                    text,
+                });
+            }
+            self.placed += 1;
+            self.next_sign += config.every / 2;
+        }
+    }
+
+    pub fn draw(&self, renderer: &Renderer, camera: &Camera) {
+        self.posts
+            .iter()
+            .filter(|post| camera.is_visible(&post.bounding_box()))
+            .for_each(|post| post.draw(renderer));
+    }
+}
@@ -0,0 +1,195 @@
+use super::{
+    headless::HeadlessRun,
+    obstacles::Hazard,
+    segments::{SegmentAssets, SegmentLibrary},
+    terrain::Terrain,
+    GameConfig, RedHatBoy, SpeedConfig,
+};
+use crate::engine::{Sheet, Texture};
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{collections::HashMap, fmt::Write, fs, path::PathBuf};
+
+const MANIFEST: &str = "static/assets.json";
+// A run the bot is still going in by here counts as cleared.
+const MAX_METERS: i32 = 5000;
+
+// The top speed curves compared, the game's own first.
+fn curves() -> [(&'static str, SpeedConfig); 4] {
+    let default = GameConfig::default().speed;
+    [
+        ("default", default),
+        (
+            "gentle",
+            SpeedConfig {
+                meters_per_step: default.meters_per_step * 2,
+                ..default
+            },
+        ),
+        (
+            "steep",
+            SpeedConfig {
+                meters_per_step: default.meters_per_step / 2,
+                max: default.max + 2,
+                ..default
+            },
+        ),
+        (
+            "fast",
+            SpeedConfig {
+                base: default.base + 2,
+                max: default.max + 2,
+                ..default
+            },
+        ),
+    ]
+}
+
+// Plays `runs` seeded runs on each speed curve with the attract bot at the
+// controls and nothing drawn, for tuning the balance. The same seeds are
+// used on every curve, so each one is played on the same courses.
+pub fn simulate(runs: u32) -> Result<String> {
+    let (boy, segments, terrain) = read_world()?;
+    let mut report = String::new();
+    for (name, speed) in curves() {
+        let config = GameConfig {
+            speed,
+            ..GameConfig::default()
+        };
+        let ends = (1..=runs)
+            .map(|seed| play(&boy, &segments, &terrain, &config, seed))
+            .collect::<Result<Vec<RunEnd>>>()?;
+        writeln!(report, "{}", summarize(name, ends))?;
+    }
+    Ok(report)
+}
+
+// The boy, the segment library and the terrain, from the files on disk.
+pub fn read_world() -> Result<(RedHatBoy, SegmentLibrary, Terrain)> {
+    let files = Files::open()?;
+    let boy = RedHatBoy::new(
+        files.sheet("rhb")?,
+        files.image("rhb")?,
+        files.sheet_sidecar("rhb", "hitboxes")?,
+    )?;
+    let segments = SegmentLibrary::new(SegmentAssets {
+        segments: files.data("segments")?,
+        tiles: (files.sheet("tiles")?, files.image("tiles")?),
+        spring: (files.sheet("spring")?, files.image("spring")?),
+        barrel: (files.sheet("barrel")?, files.image("barrel")?),
+        stone: files.image("stone")?,
+    })?;
+    let terrain = files.data::<Terrain>("terrain")?.checked()?;
+    Ok((boy, segments, terrain))
+}
+
+// How far a run got, and what ended it unless it was cleared.
+struct RunEnd {
+    meters: i32,
+    hazard: Option<Hazard>,
+}
+
+// One run with the attract bot at the controls.
+fn play(
+    boy: &RedHatBoy,
+    segments: &SegmentLibrary,
+    terrain: &Terrain,
+    config: &GameConfig,
+    seed: u32,
+) -> Result<RunEnd> {
+    let mut run = HeadlessRun::start(boy, segments, terrain, config, seed);
+    while !run.is_over() && run.meters() < MAX_METERS {
+        let controls = run.bot_controls(terrain)?;
+        run.step(&controls, segments, terrain, config)?;
+    }
+    Ok(RunEnd {
+        meters: run.meters(),
+        hazard: run.cause().map(|cause| cause.hazard),
+    })
+}
+
+// One line per curve: the mean, median and longest run, then how many runs
+// each hazard ended.
+fn summarize(name: &str, mut ends: Vec<RunEnd>) -> String {
+    ends.sort_by_key(|end| end.meters);
+    let runs = ends.len().max(1);
+    let mean = ends.iter().map(|end| end.meters).sum::<i32>() / runs as i32;
+    let median = ends.get(ends.len() / 2).map_or(0, |end| end.meters);
+    let longest = ends.last().map_or(0, |end| end.meters);
+    let mut line = format!(
+        "{:<8} mean {:>5}m  median {:>5}m  longest {:>5}m ",
+        name, mean, median, longest
+    );
+    for hazard in Hazard::ALL {
+        let count = ends.iter().filter(|end| end.hazard == Some(hazard)).count();
+        line.push_str(&format!(" {:?} {}", hazard, count));
+    }
+    let cleared = ends.iter().filter(|end| end.hazard.is_none()).count();
+    line.push_str(&format!("  cleared {}", cleared));
+    line
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    images: HashMap<String, String>,
+    sheets: HashMap<String, String>,
+    data: HashMap<String, String>,
+}
+
+// The files the asset manifest names, read from disk relative to the
+// working directory. Images are only their size, from the PNG header.
+struct Files {
+    manifest: Manifest,
+}
+
+impl Files {
+    fn open() -> Result<Self> {
+        Ok(Files {
+            manifest: read_json(&PathBuf::from(MANIFEST))?,
+        })
+    }
+
+    fn image(&self, name: &str) -> Result<Texture> {
+        let path = local_path(&self.manifest.images, name)?;
+        let bytes = fs::read(&path).with_context(|| format!("Reading {}", path.display()))?;
+        let (width, height) =
+            png_size(&bytes).ok_or_else(|| anyhow!("{} is not a PNG", path.display()))?;
+        Ok(Texture::blank(&path.to_string_lossy(), width, height))
+    }
+
+    fn sheet(&self, name: &str) -> Result<Sheet> {
+        read_json(&local_path(&self.manifest.sheets, name)?)
+    }
+
+    fn sheet_sidecar<T: DeserializeOwned>(&self, sheet: &str, suffix: &str) -> Result<T> {
+        let path =
+            local_path(&self.manifest.sheets, sheet)?.with_extension(format!("{}.json", suffix));
+        read_json(&path)
+    }
+
+    fn data<T: DeserializeOwned>(&self, name: &str) -> Result<T> {
+        read_json(&local_path(&self.manifest.data, name)?)
+    }
+}
+
+// Manifest paths are rooted at the site, which is the repository root.
+fn local_path(section: &HashMap<String, String>, name: &str) -> Result<PathBuf> {
+    section
+        .get(name)
+        .map(|path| PathBuf::from(path.trim_start_matches('/')))
+        .ok_or_else(|| anyhow!("No asset named '{}' in the manifest", name))
+}
+
+fn read_json<T: DeserializeOwned>(path: &PathBuf) -> Result<T> {
+    let text = fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Parsing {}", path.display()))
+}
+
+// Width and height from the IHDR chunk, which always comes first.
+fn png_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let read = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    Some((read(16)?, read(20)?))
+}
@@ -0,0 +1,84 @@
+use crate::engine::{style::Color, Point, Rect, Renderer};
+
+const MAX_STAMINA: u16 = 240;
+const DRAIN: u16 = 2;
+const REGEN: u16 = 1;
+const BOOST_SPEED: i16 = 3;
+const BOOST_SCORE_MULTIPLIER: u32 = 2;
+const METER_WIDTH: i16 = 120;
+const METER_HEIGHT: i16 = 8;
+const METER_COLOR: Color = Color::rgb(0x2C, 0x9E, 0xE8);
+const EXHAUSTED_COLOR: Color = Color::rgb(0x88, 0x88, 0x88);
+const METER_BACKGROUND: Color = Color::rgba(0, 0, 0, 0.2);
+
+// Holding boost runs faster and scores more while the meter lasts; it
+// refills whenever boost isn't held. Running it dry locks boost out until
+// the key is let go.
+pub struct Stamina {
+    level: u16,
+    boosting: bool,
+    exhausted: bool,
+}
+
+impl Stamina {
+    pub fn new() -> Self {
+        Stamina {
+            level: MAX_STAMINA,
+            boosting: false,
+            exhausted: false,
+        }
+    }
+
+    // Returns whether the meter ran dry this update.
+    pub fn update(&mut self, held: bool) -> bool {
+        if !held {
+            self.exhausted = false;
+        }
+        self.boosting = held && !self.exhausted && self.level > 0;
+        if !self.boosting {
+            self.level = (self.level + REGEN).min(MAX_STAMINA);
+            return false;
+        }
+        self.level = self.level.saturating_sub(DRAIN);
+        self.exhausted = self.level == 0;
+        self.exhausted
+    }
+
+    pub fn speed_bonus(&self) -> i16 {
+        if self.boosting {
+            BOOST_SPEED
+        } else {
+            0
+        }
+    }
+
+    pub fn score_multiplier(&self) -> u32 {
+        if self.boosting {
+            BOOST_SCORE_MULTIPLIER
+        } else {
+            1
+        }
+    }
+
+    pub fn draw(&self, renderer: &Renderer, top_left: &Point) {
+        let background = Rect {
+            x: top_left.x,
+            y: top_left.y,
+            width: METER_WIDTH,
+            height: METER_HEIGHT,
+        };
+        renderer.fill_rect(&background, METER_BACKGROUND);
+        renderer.fill_rect(
+            &Rect {
+                width: (i32::from(METER_WIDTH) * i32::from(self.level) / i32::from(MAX_STAMINA))
+                    as i16,
+                ..background
+            },
+            if self.exhausted {
+                EXHAUSTED_COLOR
+            } else {
+                METER_COLOR
+            },
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{browser, game::StateKind};
+use serde::Serialize;
+use std::{cell::RefCell, rc::Rc};
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GameEvent {
+    RunStarted { players: usize, daily: bool },
+    KnockedOut { meters: i32, score: u32 },
+    ScoreMilestone { score: u32 },
+    Transition { from: StateKind, to: StateKind },
+}
+
+// Where play events go. Deployments that want numbers point
+// `GameConfig::telemetry_endpoint` at a collector; everyone else gets the
+// no-op.
+pub trait Telemetry {
+    fn record(&self, event: GameEvent);
+}
+
+pub struct NoTelemetry;
+
+impl Telemetry for NoTelemetry {
+    fn record(&self, _event: GameEvent) {}
+}
+
+// Queues events and POSTs them as one JSON array every FLUSH_MS, or as soon
+// as MAX_BATCH are waiting. When the page is hidden or unloaded whatever is
+// left goes out as a beacon, which the browser delivers even if the tab is
+// closing. Failures are only logged; the game never waits on the collector.
+pub struct FetchTelemetry {
+    endpoint: &'static str,
+    queue: Rc<RefCell<Vec<GameEvent>>>,
+}
+
+const FLUSH_MS: i32 = 10_000;
+const MAX_BATCH: usize = 50;
+
+impl FetchTelemetry {
+    pub fn new(endpoint: &'static str) -> Self {
+        let queue = Rc::new(RefCell::new(vec![]));
+        let timer_queue = Rc::clone(&queue);
+        browser::spawn_local(async move {
+            loop {
+                if let Err(err) = browser::sleep(FLUSH_MS).await {
+                    warn!("Telemetry stopped flushing: {:?}", err);
+                    return;
+                }
+                post(endpoint, &timer_queue);
+            }
+        });
+        let hide_queue = Rc::clone(&queue);
+        if let Err(err) = browser::on_page_hide(move || {
+            if let Some(body) = take_batch(&hide_queue) {
+                if let Err(err) = browser::send_beacon(endpoint, &body) {
+                    debug!("Telemetry unavailable: {:?}", err);
+                }
+            }
+        }) {
+            warn!("Telemetry may be lost when the page closes: {:?}", err);
+        }
+        FetchTelemetry { endpoint, queue }
+    }
+}
+
+impl Telemetry for FetchTelemetry {
+    fn record(&self, event: GameEvent) {
+        self.queue.borrow_mut().push(event);
+        if self.queue.borrow().len() >= MAX_BATCH {
+            post(self.endpoint, &self.queue);
+        }
+    }
+}
+
+// Empties the queue into one JSON body, unless there was nothing in it.
+fn take_batch(queue: &RefCell<Vec<GameEvent>>) -> Option<String> {
+    let events = std::mem::take(&mut *queue.borrow_mut());
+    if events.is_empty() {
+        return None;
+    }
+    match serde_json::to_string(&events) {
+        Ok(body) => Some(body),
+        Err(err) => {
+            error!("Could not encode telemetry events: {}", err);
+            None
+        }
+    }
+}
+
+fn post(endpoint: &'static str, queue: &RefCell<Vec<GameEvent>>) {
+    if let Some(body) = take_batch(queue) {
+        browser::spawn_local(async move {
+            if let Err(err) = browser::post_json(endpoint, &body).await {
+                debug!("Telemetry unavailable: {:?}", err);
+            }
+        });
+    }
+}
+
+pub fn from_config(endpoint: Option<&'static str>) -> Box<dyn Telemetry> {
+    match endpoint {
+        Some(endpoint) => Box::new(FetchTelemetry::new(endpoint)),
+        None => Box::new(NoTelemetry),
+    }
+}
@@ -0,0 +1,89 @@
+use crate::engine::{assets::AssetStore, style::Color, Point, Rect, Renderer};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+const SAMPLE_STEP: i16 = 16;
+const GROUND_COLOR: Color = Color::rgb(0x7A, 0x52, 0x30);
+
+// Ground height as a function of world x: a piecewise-linear heightmap of
+// (x, y) control points that repeats every `period` pixels, with optional
+// (start, end) gaps where there is no ground at all. World x is the screen
+// x plus the distance the world has scrolled.
+#[derive(Deserialize)]
+pub struct Terrain {
+    period: i32,
+    points: Vec<(i32, i16)>,
+    #[serde(default)]
+    gaps: Vec<(i32, i32)>,
+}
+
+impl Terrain {
+    pub async fn load(assets: &AssetStore) -> Result<Self> {
+        assets.data::<Terrain>("terrain").await?.checked()
+    }
+
+    pub fn checked(self) -> Result<Self> {
+        let sorted = self.points.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        let in_period = self
+            .points
+            .iter()
+            .all(|(x, _)| (0..self.period).contains(x));
+        let gaps_in_period = self
+            .gaps
+            .iter()
+            .all(|(start, end)| 0 <= *start && start < end && *end <= self.period);
+        if self.points.is_empty() || !sorted || !in_period || !gaps_in_period {
+            return Err(anyhow!(
+                "Terrain points must be sorted by x and lie within the period, as must gaps"
+            ));
+        }
+        Ok(self)
+    }
+
+    pub fn ground_at(&self, world_x: i32) -> Option<i16> {
+        let x = world_x.rem_euclid(self.period);
+        if self
+            .gaps
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&x))
+        {
+            return None;
+        }
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        let (start, end) = match self.points.iter().position(|(point_x, _)| *point_x > x) {
+            Some(0) => ((last.0 - self.period, last.1), first),
+            Some(index) => (self.points[index - 1], self.points[index]),
+            None => (last, (first.0 + self.period, first.1)),
+        };
+        let rise = i32::from(end.1 - start.1);
+        Some((i32::from(start.1) + rise * (x - start.0) / (end.0 - start.0)) as i16)
+    }
+
+    // Each stretch of ground between gaps is filled as its own polygon.
+    pub fn draw(&self, renderer: &Renderer, view: &Rect, distance: i32) {
+        let mut outline: Vec<Point> = vec![];
+        for x in (view.x..=view.right() + SAMPLE_STEP).step_by(SAMPLE_STEP as usize) {
+            match self.ground_at(distance + i32::from(x)) {
+                Some(y) => outline.push(Point { x, y }),
+                None => Self::fill_stretch(renderer, &mut outline, view.bottom()),
+            }
+        }
+        Self::fill_stretch(renderer, &mut outline, view.bottom());
+    }
+
+    fn fill_stretch(renderer: &Renderer, outline: &mut Vec<Point>, bottom: i16) {
+        if let (Some(first), Some(last)) = (outline.first().copied(), outline.last().copied()) {
+            outline.push(Point {
+                x: last.x,
+                y: bottom,
+            });
+            outline.push(Point {
+                x: first.x,
+                y: bottom,
+            });
+            renderer.fill_polygon(outline, GROUND_COLOR);
+        }
+        outline.clear();
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{
+    browser,
+    engine::{
+        assets::{AssetStore, Scope},
+        style::Color,
+        Texture,
+    },
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::{cell::RefCell, rc::Rc};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThemeDescriptor {
+    name: String,
+    background: String,
+    tint: Option<String>,
+    tile_tint: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThemesDescriptor {
+    every: i32,
+    fade_frames: u16,
+    themes: Vec<ThemeDescriptor>,
+}
+
+pub struct Theme {
+    name: String,
+    background_name: String,
+    // Only held for the themes on screen and the one coming up next.
+    background: Option<Texture>,
+    tint: Color,
+    tile_tint: Color,
+}
+
+impl Theme {
+    pub fn tint(&self) -> Option<Color> {
+        (self.tint.a > 0.0).then_some(self.tint)
+    }
+
+    pub fn background(&self) -> Option<&Texture> {
+        self.background.as_ref()
+    }
+
+    fn new(descriptor: ThemeDescriptor) -> Result<Self> {
+        let parse = |color: Option<String>| {
+            color.map_or(Ok(Color::WHITE.with_alpha(0.0)), |color| color.parse())
+        };
+        Ok(Theme {
+            tint: parse(descriptor.tint).with_context(|| format!("Theme {}", descriptor.name))?,
+            tile_tint: parse(descriptor.tile_tint)
+                .with_context(|| format!("Theme {}", descriptor.name))?,
+            name: descriptor.name,
+            background_name: descriptor.background,
+            background: None,
+        })
+    }
+}
+
+// The theme changes every `every` meters, cycling through the list. While a
+// change is in progress the previous theme stays underneath and the new one
+// fades in over `fade_frames` updates.
+//
+// Backgrounds are loaded one theme ahead and let go of once their theme has
+// faded out, so a long run holds at most three whatever the list's length.
+// A theme whose background hasn't arrived yet waits for it.
+pub struct Themes {
+    themes: Vec<Theme>,
+    every: i32,
+    fade_frames: u16,
+    current: usize,
+    previous: Option<usize>,
+    fade: u16,
+    // The theme whose background is loading, and where it arrives.
+    loading: Option<usize>,
+    arrived: Rc<RefCell<Option<Result<Texture>>>>,
+}
+
+impl Themes {
+    pub async fn load(assets: &AssetStore) -> Result<Self> {
+        let descriptor: ThemesDescriptor = assets.data("themes").await?;
+        if descriptor.themes.is_empty() || descriptor.every <= 0 {
+            return Err(anyhow!(
+                "Themes need at least one theme and a positive interval"
+            ));
+        }
+        let mut themes = descriptor
+            .themes
+            .into_iter()
+            .map(Theme::new)
+            .collect::<Result<Vec<_>>>()?;
+        let upcoming = 1 % themes.len();
+        for index in [0, upcoming] {
+            let theme = &mut themes[index];
+            theme.background = Some(
+                assets
+                    .image_in(Scope::Theme, &theme.background_name)
+                    .await?,
+            );
+        }
+        Ok(Themes {
+            themes,
+            every: descriptor.every,
+            fade_frames: descriptor.fade_frames.max(1),
+            current: 0,
+            previous: None,
+            fade: 0,
+            loading: None,
+            arrived: Rc::new(RefCell::new(None)),
+        })
+    }
+
+    pub fn update(&mut self, meters: i32, assets: &Rc<AssetStore>) {
+        self.receive();
+        let index = (meters.max(0) / self.every) as usize % self.themes.len();
+        if index != self.current {
+            if self.themes[index].background.is_some() {
+                self.previous = Some(self.current);
+                self.current = index;
+                self.fade = 0;
+            } else {
+                self.request(index, assets);
+            }
+        }
+        if self.previous.is_some() {
+            self.fade += 1;
+            if self.fade >= self.fade_frames {
+                self.previous = None;
+                self.release(assets);
+            }
+        }
+    }
+
+    // Keeps the current theme's background and the next one's, and starts
+    // loading the next if it was let go of.
+    fn release(&mut self, assets: &Rc<AssetStore>) {
+        let upcoming = (self.current + 1) % self.themes.len();
+        for (index, theme) in self.themes.iter_mut().enumerate() {
+            if index != self.current && index != upcoming {
+                theme.background = None;
+            }
+        }
+        assets.unload(Scope::Theme);
+        self.request(upcoming, assets);
+    }
+
+    fn request(&mut self, index: usize, assets: &Rc<AssetStore>) {
+        if self.loading.is_some() || self.themes[index].background.is_some() {
+            return;
+        }
+        self.loading = Some(index);
+        let assets = Rc::clone(assets);
+        let name = self.themes[index].background_name.clone();
+        let arrived = Rc::clone(&self.arrived);
+        browser::spawn_local(async move {
+            let background = assets.image_in(Scope::Theme, &name).await;
+            *arrived.borrow_mut() = Some(background);
+        });
+    }
+
+    fn receive(&mut self) {
+        let Some(background) = self.arrived.borrow_mut().take() else {
+            return;
+        };
+        let Some(index) = self.loading.take() else {
+            return;
+        };
+        self.themes[index].background = match background {
+            Ok(background) => Some(background),
+            // Its tint over the current background is better than never
+            // getting past it.
+            Err(err) => {
+                warn!(
+                    "Could not load the background for theme {}: {:#}",
+                    self.themes[index].name, err
+                );
+                self.themes[self.current].background.clone()
+            }
+        };
+    }
+
+    fn progress(&self) -> f32 {
+        f32::from(self.fade) / f32::from(self.fade_frames)
+    }
+
+    // Layers to draw back to front, each with its opacity.
+    pub fn layers(&self) -> Vec<(&Theme, f64)> {
+        let current = &self.themes[self.current];
+        match self.previous {
+            Some(previous) => vec![
+                (&self.themes[previous], 1.0),
+                (current, f64::from(self.progress())),
+            ],
+            None => vec![(current, 1.0)],
+        }
+    }
+
+    pub fn tile_tint(&self) -> Option<Color> {
+        let current = self.themes[self.current].tile_tint;
+        let tint = match self.previous {
+            Some(previous) => self.themes[previous]
+                .tile_tint
+                .lerp(current, self.progress()),
+            None => current,
+        };
+        (tint.a > 0.0).then_some(tint)
+    }
+}
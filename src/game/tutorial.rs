@@ -0,0 +1,88 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+
+const STORAGE_KEY: &str = "walk-the-dog.tutorial-done";
+// How far ahead of the boy, in pixels, an obstacle gets its prompt.
+const PROMPT_RANGE: i16 = 220;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    JumpStone,
+    JumpPlatform,
+}
+
+// Shows a prompt while the stone, and then the platform, comes up on the first
+// run. Each prompt stays until the boy takes off while it is showing; once
+// both are done the tutorial never shows again.
+pub struct Tutorial {
+    step: Option<Step>,
+    showing: bool,
+    was_jumping: bool,
+}
+
+impl Tutorial {
+    pub fn load() -> Result<Self> {
+        let done = browser::local_storage()?
+            .get_item(STORAGE_KEY)
+            .map_err(|err| anyhow!("Could not read {}: {:#?}", STORAGE_KEY, err))?
+            .is_some();
+        Ok(if done {
+            Tutorial::done()
+        } else {
+            Tutorial {
+                step: Some(Step::JumpStone),
+                showing: false,
+                was_jumping: false,
+            }
+        })
+    }
+
+    pub fn done() -> Self {
+        Tutorial {
+            step: None,
+            showing: false,
+            was_jumping: false,
+        }
+    }
+
+    // Gaps run from the boy's front edge to each obstacle's left edge.
+    pub fn update(&mut self, stone_gap: i16, platform_gap: i16, jumping: bool) {
+        let Some(step) = self.step else {
+            return;
+        };
+        let took_off = jumping && !self.was_jumping;
+        self.was_jumping = jumping;
+        if self.showing && took_off {
+            self.showing = false;
+            self.step = match step {
+                Step::JumpStone => Some(Step::JumpPlatform),
+                Step::JumpPlatform => {
+                    if let Err(err) = save() {
+                        warn!("Could not save the tutorial: {:?}", err);
+                    }
+                    None
+                }
+            };
+            return;
+        }
+        let gap = match step {
+            Step::JumpStone => stone_gap,
+            Step::JumpPlatform => platform_gap,
+        };
+        self.showing = (0..PROMPT_RANGE).contains(&gap);
+    }
+
+    // The locale key for the prompt to show right now.
+    pub fn prompt(&self) -> Option<&'static str> {
+        match self.step.filter(|_| self.showing)? {
+            Step::JumpStone => Some("tutorial.jump"),
+            Step::JumpPlatform => Some("tutorial.platform"),
+        }
+    }
+}
+
+fn save() -> Result<()> {
+    browser::local_storage()?
+        .set_item(STORAGE_KEY, "1")
+        .map_err(|err| anyhow!("Could not write {}: {:#?}", STORAGE_KEY, err))
+}
@@ -0,0 +1,211 @@
+use super::{
+    coins::Coins,
+    course::Course,
+    obstacles::{Cause, Contact, Hazard, Obstacle},
+    segments::SegmentLibrary,
+    stamina::Stamina,
+    terrain::Terrain,
+    GameConfig, Player, RedHatBoy, StateKind, StateObserver, HEIGHT, PIT_BOTTOM, PIXELS_PER_METER,
+};
+use crate::engine::{fixed::Fixed, rng::Rng};
+use anyhow::Result;
+
+// Something in a step the game reacts to with sound, feedback or score.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WorldEvent {
+    Exhausted,
+    Bounced,
+    // Only hits that cost a life.
+    Hit { player: usize, knocked_out: bool },
+    NearMiss { player: usize },
+    Landed { player: usize },
+}
+
+// Hands out an observer for one boy's state changes while he updates and
+// takes his events.
+pub trait Watch {
+    fn watch(&mut self, boy: &RedHatBoy) -> Result<Box<dyn StateObserver + '_>>;
+}
+
+pub struct Unobserved;
+
+impl StateObserver for Unobserved {}
+
+impl Watch for Unobserved {
+    fn watch(&mut self, _boy: &RedHatBoy) -> Result<Box<dyn StateObserver + '_>> {
+        Ok(Box::new(Unobserved))
+    }
+}
+
+// The world scrolls with the first player still in the run.
+pub fn lead(players: &[Player]) -> &RedHatBoy {
+    &players
+        .iter()
+        .find(|player| !player.boy.kind().is_game_over())
+        .unwrap_or(&players[0])
+        .boy
+}
+
+pub fn is_game_over(players: &[Player]) -> bool {
+    players
+        .iter()
+        .all(|player| player.boy.kind().is_game_over())
+}
+
+pub fn next_scroll(scroll: Fixed, lead: &RedHatBoy, top_speed: i16) -> Fixed {
+    const SCROLL_ACCELERATION: Fixed = Fixed::from_ratio(1, 16);
+    match lead.kind() {
+        StateKind::Idle | StateKind::Falling | StateKind::KnockedOut | StateKind::FellOff => {
+            Fixed::default()
+        }
+        _ if scroll == Fixed::default() => Fixed::from_int(lead.walking_speed()),
+        _ => (scroll + SCROLL_ACCELERATION).min(Fixed::from_int(top_speed)),
+    }
+}
+
+// Everything a run's rules touch, borrowed from whoever keeps it: the walk
+// when the run is played, or a headless run when it is simulated or
+// replayed for a race. Both step it the same way, so the same seed and the
+// same controls end up in the same place.
+pub struct World<'a> {
+    pub players: &'a mut [Player],
+    pub obstacles: &'a mut Vec<Box<dyn Obstacle>>,
+    pub course: &'a mut Course,
+    pub rng: &'a mut Rng,
+    pub coins: &'a mut Coins,
+    pub stamina: &'a mut Stamina,
+    pub scroll: &'a mut Fixed,
+    pub distance: &'a mut i32,
+    pub segments: &'a SegmentLibrary,
+    pub terrain: &'a Terrain,
+    pub config: &'a GameConfig,
+}
+
+impl World<'_> {
+    // One tick, once the players' controls are in: speed, the boys, the
+    // scroll, the obstacles and the course, then every contact. Returns what
+    // happened for the game to react to.
+    pub fn step(&mut self, boost: bool, watch: &mut dyn Watch) -> Result<Vec<WorldEvent>> {
+        let mut events = vec![];
+        let running = lead(self.players).kind() != StateKind::Idle && !is_game_over(self.players);
+        if self.stamina.update(boost && running) {
+            events.push(WorldEvent::Exhausted);
+        }
+        let top_speed = self
+            .config
+            .speed
+            .top_speed(*self.distance / PIXELS_PER_METER)
+            + self.stamina.speed_bonus();
+        for player in self.players.iter_mut() {
+            let catch_up = (player.home - player.boy.pos_x()).signum();
+            player.boy.set_top_speed(top_speed + catch_up);
+            let feet_x = *self.distance + i32::from(player.boy.feet()?.x);
+            let ground = self.terrain.ground_at(feet_x);
+            let mut observer = watch.watch(&player.boy)?;
+            player
+                .boy
+                .update(ground.unwrap_or(PIT_BOTTOM), &mut *observer);
+            if ground.is_none() && player.boy.feet()?.y > HEIGHT {
+                player.boy.fall(Cause {
+                    hazard: Hazard::Pit,
+                    meters: feet_x / PIXELS_PER_METER,
+                });
+            }
+        }
+        *self.scroll = next_scroll(*self.scroll, lead(self.players), top_speed);
+        let velocity = -self.scroll.round();
+        for player in self.players.iter_mut() {
+            player.boy.shift(player.boy.walking_speed() + velocity);
+        }
+        for obstacle in self.obstacles.iter_mut() {
+            obstacle.move_horizontally(velocity);
+            obstacle.tick(self.terrain, *self.distance);
+        }
+        // Obstacles that scroll off the left edge are dropped, and once the
+        // end of the course comes on screen another segment is laid down
+        // after it, spaced by the run's RNG so the same seed always builds
+        // the same course.
+        self.obstacles.retain(|obstacle| !obstacle.is_spent());
+        self.course.extend(velocity, self.segments, self.rng);
+        self.obstacles.extend(self.course.spawn_due(
+            self.segments,
+            *self.distance,
+            self.terrain,
+            self.coins,
+        ));
+
+        let mut landing = vec![false; self.players.len()];
+        for (index, landing) in landing.iter_mut().enumerate() {
+            let boy = &mut self.players[index].boy;
+            let jumping = boy.kind().is_airborne();
+            let contacts = self
+                .obstacles
+                .iter_mut()
+                .map(|obstacle| {
+                    let contact = obstacle.check_intersection(boy)?;
+                    if matches!(contact, Contact::Landed | Contact::Bounced) {
+                        obstacle.stood_on();
+                    }
+                    Ok(contact)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            *landing |= jumping && contacts.contains(&Contact::Landed);
+            if contacts.contains(&Contact::Bounced) {
+                events.push(WorldEvent::Bounced);
+            }
+            if !boy.kind().is_game_over() && boy.bounding_box()?.right() < 0 {
+                self.players[index].push_off(*self.distance);
+            }
+            let hit_by =
+                contacts
+                    .iter()
+                    .zip(self.obstacles.iter())
+                    .find_map(|(contact, obstacle)| match contact {
+                        Contact::Hit(hazard) => Some(Cause {
+                            hazard: *hazard,
+                            meters: (*self.distance + i32::from(obstacle.left()))
+                                / PIXELS_PER_METER,
+                        }),
+                        _ => None,
+                    });
+            let near_miss = self.near_missed(index)?;
+            if let Some(cause) = hit_by {
+                let player = &mut self.players[index];
+                if player.take_hit(cause) {
+                    events.push(WorldEvent::Hit {
+                        player: index,
+                        knocked_out: player.lives == 0,
+                    });
+                }
+            } else if near_miss {
+                events.push(WorldEvent::NearMiss { player: index });
+            }
+        }
+
+        for (index, landing) in landing.into_iter().enumerate() {
+            let boy = &mut self.players[index].boy;
+            let mut observer = watch.watch(boy)?;
+            boy.process_events(&mut *observer);
+            if landing && !boy.kind().is_airborne() {
+                events.push(WorldEvent::Landed { player: index });
+            }
+        }
+        *self.distance += i32::from(self.scroll.round());
+        Ok(events)
+    }
+
+    // Every obstacle sees the boy each frame, so each can tell when he has
+    // cleared it.
+    fn near_missed(&mut self, index: usize) -> Result<bool> {
+        let boy = &self.players[index].boy;
+        if boy.kind().is_game_over() {
+            return Ok(false);
+        }
+        let boy = boy.bounding_box()?;
+        let mut missed = false;
+        for obstacle in self.obstacles.iter_mut() {
+            missed |= obstacle.near_missed(index, &boy);
+        }
+        Ok(missed)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{browser, engine::assets::AssetStore};
+use std::collections::HashMap;
+
+const SUPPORTED_LOCALES: [&str; 2] = ["en", "ja"];
+const DEFAULT_LOCALE: &str = "en";
+// Shown when the locale file can't be fetched, so a bad connection doesn't
+// keep the game from starting.
+const BUILT_IN: &str = include_str!("../static/locales/en.json");
+
+pub struct Locale {
+    strings: HashMap<String, String>,
+    english: HashMap<String, String>,
+}
+
+impl Locale {
+    pub async fn load(assets: &AssetStore) -> Self {
+        let language = browser::language().unwrap_or_else(|_| DEFAULT_LOCALE.to_string());
+        let locale = locale_for(&language);
+        match assets.data(&format!("locale.{}", locale)).await {
+            Ok(strings) => Locale {
+                strings,
+                english: built_in_strings(),
+            },
+            Err(err) => {
+                warn!(
+                    "Could not load the '{}' strings, using the built-in English: {:?}",
+                    locale, err
+                );
+                Locale::built_in()
+            }
+        }
+    }
+
+    fn built_in() -> Self {
+        Locale {
+            strings: built_in_strings(),
+            english: built_in_strings(),
+        }
+    }
+
+    pub fn tr<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    // For text drawn with the bitmap font, which only has ASCII glyphs:
+    // anything the font can't draw comes out in English instead.
+    pub fn tr_ascii<'a>(&'a self, key: &'a str) -> &'a str {
+        match self.tr(key) {
+            text if text.is_ascii() => text,
+            _ => self.english.get(key).map(String::as_str).unwrap_or(key),
+        }
+    }
+}
+
+fn built_in_strings() -> HashMap<String, String> {
+    serde_json::from_str(BUILT_IN).expect("The built-in strings are not JSON")
+}
+
+// navigator.language is a BCP 47 tag such as "ja-JP"; only the primary
+// language subtag is used to pick a locale file.
+fn locale_for(language: &str) -> &'static str {
+    let primary = language.split('-').next().unwrap_or_default();
+    SUPPORTED_LOCALES
+        .iter()
+        .find(|locale| locale.eq_ignore_ascii_case(primary))
+        .copied()
+        .unwrap_or(DEFAULT_LOCALE)
+}
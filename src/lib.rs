@@ -0,0 +1,6 @@
+#[macro_use]
+pub mod browser;
+pub mod engine;
+pub mod game;
+mod i18n;
+mod net;
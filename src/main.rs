@@ -1,21 +1,20 @@
-#[macro_use]
-mod browser;
-mod engine;
-mod game;
-
-use engine::GameLoop;
-use game::WalkTheDog;
+use walk_the_dog::{
+    browser,
+    engine::{self, report_error, GameLoop},
+    game::WalkTheDog,
+};
 use wasm_bindgen::JsValue;
 
 pub fn main() -> Result<(), JsValue> {
-    console_error_panic_hook::set_once();
+    engine::crash::install();
+    browser::logging::init();
 
     browser::spawn_local(async move {
-        let game = WalkTheDog::new();
+        let game = WalkTheDog::new(browser::LaunchOptions::from_query());
 
-        GameLoop::start(game)
-            .await
-            .expect("Could not start game loop");
+        if let Err(err) = GameLoop::start(game).await {
+            report_error("start", &err);
+        }
     });
 
     Ok(())
@@ -0,0 +1,127 @@
+pub mod peer;
+
+use crate::browser;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, Ref, RefCell},
+    rc::Rc,
+};
+
+const TOP: usize = 10;
+const TIMEOUT_MS: i32 = 5000;
+const PLAYER_KEY: &str = "walk-the-dog.player-name";
+
+#[derive(Serialize)]
+struct Submission<'a> {
+    name: &'a str,
+    score: u32,
+    distance: i32,
+}
+
+#[derive(Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+}
+
+pub enum LeaderboardState {
+    Idle,
+    Loading,
+    Loaded(Vec<Entry>),
+    Offline,
+}
+
+// Talks to a plain HTTP endpoint: scores are POSTed as JSON and the top
+// entries come back from `GET {endpoint}?limit=10`. Requests run in the
+// background and any failure just leaves the board offline. Each submit or
+// reset starts a new generation, and a response from an older one is
+// dropped, so a slow reply can't fill in the board of a later run.
+pub struct Leaderboard {
+    endpoint: &'static str,
+    player: String,
+    state: Rc<RefCell<LeaderboardState>>,
+    generation: Rc<Cell<u32>>,
+}
+
+impl Leaderboard {
+    pub fn new(endpoint: &'static str) -> Self {
+        Leaderboard {
+            endpoint,
+            player: player_name().unwrap_or_else(|err| {
+                warn!("Could not load the player name: {:?}", err);
+                "Runner".to_string()
+            }),
+            state: Rc::new(RefCell::new(LeaderboardState::Idle)),
+            generation: Rc::new(Cell::new(0)),
+        }
+    }
+
+    pub fn submit(&self, score: u32, distance: i32) {
+        let generation = self.next_generation();
+        *self.state.borrow_mut() = LeaderboardState::Loading;
+        let state = Rc::clone(&self.state);
+        let current = Rc::clone(&self.generation);
+        let endpoint = self.endpoint;
+        let body = serde_json::to_string(&Submission {
+            name: &self.player,
+            score,
+            distance,
+        });
+        browser::spawn_local(async move {
+            let result = browser::with_timeout(
+                async {
+                    browser::post_json(endpoint, &body?).await?;
+                    let json = browser::fetch_json(&format!("{}?limit={}", endpoint, TOP)).await?;
+                    serde_wasm_bindgen::from_value::<Vec<Entry>>(json)
+                        .map_err(|err| anyhow!("Could not parse the leaderboard: {:#?}", err))
+                },
+                TIMEOUT_MS,
+            )
+            .await;
+            if current.get() != generation {
+                return;
+            }
+            *state.borrow_mut() = match result {
+                Ok(mut entries) => {
+                    entries.truncate(TOP);
+                    LeaderboardState::Loaded(entries)
+                }
+                Err(err) => {
+                    warn!("Leaderboard unavailable: {:?}", err);
+                    LeaderboardState::Offline
+                }
+            };
+        });
+    }
+
+    pub fn reset(&self) {
+        self.next_generation();
+        *self.state.borrow_mut() = LeaderboardState::Idle;
+    }
+
+    fn next_generation(&self) -> u32 {
+        let generation = self.generation.get().wrapping_add(1);
+        self.generation.set(generation);
+        generation
+    }
+
+    pub fn state(&self) -> Ref<'_, LeaderboardState> {
+        self.state.borrow()
+    }
+}
+
+fn player_name() -> Result<String> {
+    let storage = browser::local_storage()?;
+    if let Some(name) = storage
+        .get_item(PLAYER_KEY)
+        .map_err(|err| anyhow!("Could not read {}: {:#?}", PLAYER_KEY, err))?
+    {
+        return Ok(name);
+    }
+    let name = format!("Runner-{:04}", browser::random_seed() % 10_000);
+    storage
+        .set_item(PLAYER_KEY, &name)
+        .map_err(|err| anyhow!("Could not write {}: {:#?}", PLAYER_KEY, err))?;
+    Ok(name)
+}
@@ -0,0 +1,223 @@
+use crate::browser::{self, RetryPolicy};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    MessageEvent, RtcConfiguration, RtcDataChannel, RtcDataChannelEvent, RtcIceGatheringState,
+    RtcIceServer, RtcPeerConnection, RtcSdpType, RtcSessionDescriptionInit,
+};
+
+const GATHER_POLL_MS: i32 = 100;
+const GATHER_ATTEMPTS: u32 = 30;
+const ANSWER_POLL: RetryPolicy = RetryPolicy {
+    attempts: 7,
+    initial_backoff_ms: 1000,
+    timeout_ms: 5000,
+};
+
+#[derive(Serialize, Deserialize)]
+struct Signal {
+    sdp: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PeerStatus {
+    Connecting,
+    Open,
+    Closed,
+}
+
+struct Shared {
+    status: PeerStatus,
+    is_host: bool,
+    channel: Option<RtcDataChannel>,
+    inbox: Vec<String>,
+}
+
+// One data channel to one other player. The lobby is a plain HTTP endpoint:
+// whoever finds no offer under `{endpoint}/{room}/offer` hosts and posts one,
+// and the other side posts its reply to `{endpoint}/{room}/answer`. ICE
+// candidates are gathered before signalling so each side only posts once.
+pub struct Peer {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Peer {
+    // `ice_servers` are the STUN or TURN URLs used to find a route between
+    // the two players.
+    pub fn connect(
+        endpoint: &'static str,
+        ice_servers: &'static [&'static str],
+        room: String,
+    ) -> Self {
+        let shared = Rc::new(RefCell::new(Shared {
+            status: PeerStatus::Connecting,
+            is_host: false,
+            channel: None,
+            inbox: vec![],
+        }));
+        let task_shared = Rc::clone(&shared);
+        browser::spawn_local(async move {
+            if let Err(err) = handshake(endpoint, ice_servers, &room, &task_shared).await {
+                warn!("Could not join room {}: {:?}", room, err);
+                task_shared.borrow_mut().status = PeerStatus::Closed;
+            }
+        });
+        Peer { shared }
+    }
+
+    pub fn status(&self) -> PeerStatus {
+        self.shared.borrow().status
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.shared.borrow().is_host
+    }
+
+    pub fn send(&self, message: &str) {
+        if let Some(channel) = &self.shared.borrow().channel {
+            if let Err(err) = channel.send_with_str(message) {
+                warn!("Could not send to the peer {:#?}", err);
+            }
+        }
+    }
+
+    pub fn receive(&self) -> Vec<String> {
+        std::mem::take(&mut self.shared.borrow_mut().inbox)
+    }
+}
+
+async fn handshake(
+    endpoint: &str,
+    ice_servers: &[&str],
+    room: &str,
+    shared: &Rc<RefCell<Shared>>,
+) -> Result<()> {
+    let connection = new_connection(ice_servers)?;
+    let offer_url = format!("{}/{}/offer", endpoint, room);
+    let answer_url = format!("{}/{}/answer", endpoint, room);
+    match fetch_signal(&offer_url).await {
+        Ok(offer) => {
+            let channel_shared = Rc::clone(shared);
+            let ondatachannel = browser::closure_wrap(Box::new(move |event: RtcDataChannelEvent| {
+                attach(event.channel(), &channel_shared);
+            })
+                as Box<dyn FnMut(RtcDataChannelEvent)>);
+            connection.set_ondatachannel(Some(ondatachannel.as_ref().unchecked_ref()));
+            ondatachannel.forget();
+            JsFuture::from(
+                connection.set_remote_description(&description(RtcSdpType::Offer, &offer.sdp)),
+            )
+            .await
+            .map_err(|err| anyhow!("Could not accept the offer {:#?}", err))?;
+            let answer = JsFuture::from(connection.create_answer())
+                .await
+                .map_err(|err| anyhow!("Could not create an answer {:#?}", err))?;
+            let sdp = publish_local(&connection, RtcSdpType::Answer, &answer).await?;
+            post_signal(&answer_url, sdp).await
+        }
+        Err(_) => {
+            shared.borrow_mut().is_host = true;
+            attach(connection.create_data_channel("race"), shared);
+            let offer = JsFuture::from(connection.create_offer())
+                .await
+                .map_err(|err| anyhow!("Could not create an offer {:#?}", err))?;
+            let sdp = publish_local(&connection, RtcSdpType::Offer, &offer).await?;
+            post_signal(&offer_url, sdp).await?;
+            let answer = browser::retry(
+                &ANSWER_POLL,
+                || fetch_signal(&answer_url),
+                |attempt, _| {
+                    debug!("Waiting for a rival ({})", attempt);
+                },
+            )
+            .await?;
+            JsFuture::from(
+                connection.set_remote_description(&description(RtcSdpType::Answer, &answer.sdp)),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|err| anyhow!("Could not accept the answer {:#?}", err))
+        }
+    }
+}
+
+fn new_connection(ice_servers: &[&str]) -> Result<RtcPeerConnection> {
+    let servers = js_sys::Array::new();
+    for url in ice_servers {
+        let mut server = RtcIceServer::new();
+        server.urls(&JsValue::from_str(url));
+        servers.push(&server);
+    }
+    let mut config = RtcConfiguration::new();
+    config.ice_servers(&servers);
+    RtcPeerConnection::new_with_configuration(&config)
+        .map_err(|err| anyhow!("Could not create RtcPeerConnection {:#?}", err))
+}
+
+fn description(kind: RtcSdpType, sdp: &str) -> RtcSessionDescriptionInit {
+    let mut description = RtcSessionDescriptionInit::new(kind);
+    description.sdp(sdp);
+    description
+}
+
+// Applies a freshly created offer or answer and waits for ICE gathering, so
+// the returned SDP already lists every candidate.
+async fn publish_local(
+    connection: &RtcPeerConnection,
+    kind: RtcSdpType,
+    created: &JsValue,
+) -> Result<String> {
+    let sdp = js_sys::Reflect::get(created, &JsValue::from_str("sdp"))
+        .ok()
+        .and_then(|sdp| sdp.as_string())
+        .ok_or_else(|| anyhow!("Session description has no SDP"))?;
+    JsFuture::from(connection.set_local_description(&description(kind, &sdp)))
+        .await
+        .map_err(|err| anyhow!("Could not set the local description {:#?}", err))?;
+    for _ in 0..GATHER_ATTEMPTS {
+        if connection.ice_gathering_state() == RtcIceGatheringState::Complete {
+            break;
+        }
+        browser::sleep(GATHER_POLL_MS).await?;
+    }
+    connection
+        .local_description()
+        .map(|description| description.sdp())
+        .ok_or_else(|| anyhow!("No local description after ICE gathering"))
+}
+
+fn attach(channel: RtcDataChannel, shared: &Rc<RefCell<Shared>>) {
+    let open_shared = Rc::clone(shared);
+    let onopen = browser::closure_wrap(Box::new(move || {
+        open_shared.borrow_mut().status = PeerStatus::Open;
+    }) as Box<dyn FnMut()>);
+    let message_shared = Rc::clone(shared);
+    let onmessage = browser::closure_wrap(Box::new(move |event: MessageEvent| {
+        if let Some(message) = event.data().as_string() {
+            message_shared.borrow_mut().inbox.push(message);
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
+    let close_shared = Rc::clone(shared);
+    let onclose = browser::closure_wrap(Box::new(move || {
+        close_shared.borrow_mut().status = PeerStatus::Closed;
+    }) as Box<dyn FnMut()>);
+    channel.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+    channel.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    channel.set_onclose(Some(onclose.as_ref().unchecked_ref()));
+    onopen.forget();
+    onmessage.forget();
+    onclose.forget();
+    shared.borrow_mut().channel = Some(channel);
+}
+
+async fn fetch_signal(url: &str) -> Result<Signal> {
+    serde_wasm_bindgen::from_value(browser::fetch_json(url).await?)
+        .map_err(|err| anyhow!("Could not parse the signal at {}: {:#?}", url, err))
+}
+
+async fn post_signal(url: &str, sdp: String) -> Result<()> {
+    browser::post_json(url, &serde_json::to_string(&Signal { sdp })?).await
+}
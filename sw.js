@@ -0,0 +1,62 @@
+// Keeps the game playable offline. Everything is fetched from the network
+// first so new builds show up right away; the cache is only the fallback.
+const CACHE = "walk-the-dog-v1";
+
+// The bundle and stylesheet names are hashed by trunk, so they are read from the
+// preload links in index.html rather than listed here.
+async function bundleUrls() {
+  const html = await (await fetch("/", { cache: "no-store" })).text();
+  return [...html.matchAll(/href="([^"]+\.(?:js|wasm|css))"/g)].map((match) => match[1]);
+}
+
+async function assetUrls() {
+  const manifest = await (await fetch("/static/assets.json", { cache: "no-store" })).json();
+  return Object.values(manifest).flatMap((section) => Object.values(section));
+}
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(
+    (async () => {
+      const cache = await caches.open(CACHE);
+      const urls = ["/", "/static/assets.json"];
+      urls.push(...(await bundleUrls()), ...(await assetUrls()));
+      await cache.addAll(urls);
+      await self.skipWaiting();
+    })()
+  );
+});
+
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    (async () => {
+      const stale = (await caches.keys()).filter((key) => key !== CACHE);
+      await Promise.all(stale.map((key) => caches.delete(key)));
+      await self.clients.claim();
+    })()
+  );
+});
+
+self.addEventListener("fetch", (event) => {
+  const url = new URL(event.request.url);
+  if (event.request.method !== "GET" || url.origin !== self.location.origin || url.pathname.startsWith("/api/")) {
+    return;
+  }
+  event.respondWith(
+    (async () => {
+      const cache = await caches.open(CACHE);
+      try {
+        const response = await fetch(event.request);
+        if (response.ok) {
+          cache.put(event.request, response.clone());
+        }
+        return response;
+      } catch (err) {
+        const cached = await cache.match(event.request, { ignoreSearch: true });
+        if (cached) {
+          return cached;
+        }
+        throw err;
+      }
+    })()
+  );
+});